@@ -0,0 +1,299 @@
+//! dbmate migration adapter.
+//!
+//! Supports dbmate's single-file migrations: `<VERSION>_<NAME>.sql`, with the
+//! up and down SQL in sections opened by `-- migrate:up` and `-- migrate:down`.
+//!
+//! Each section is checked on its own. The other section is blanked in place
+//! (newlines kept), so reported lines and fix offsets match the original file.
+//! A section runs in a transaction unless its marker line has `transaction:false`.
+
+use super::{
+    MigrationAdapter, MigrationContext, MigrationFile, Result, TransactionOptOut,
+    collect_and_sort_entries, should_check_migration, sort_by_numeric_version,
+};
+use crate::violation::TextEdit;
+use camino::Utf8Path;
+use regex::Regex;
+use std::ops::Range;
+use std::sync::LazyLock;
+
+/// Regex for dbmate migration file names, as dbmate itself matches them.
+static DBMATE_NAME_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(\d+).*\.sql$").expect("valid regex pattern"));
+
+/// Regex for `start_after` values: a plain version number.
+static DBMATE_START_AFTER_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\d+$").expect("valid regex pattern"));
+
+/// Regex for section markers and their options.
+static SECTION_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?m)^--\s*migrate:(up|down)([^\r\n]*)").expect("valid regex pattern")
+});
+
+const UP_NO_TRANSACTION_HINT: &str =
+    "Add `transaction:false` to the section marker: `-- migrate:up transaction:false`.";
+
+const DOWN_NO_TRANSACTION_HINT: &str =
+    "Add `transaction:false` to the section marker: `-- migrate:down transaction:false`.";
+
+/// dbmate migration adapter.
+pub struct DbmateAdapter;
+
+/// One `-- migrate:up` / `-- migrate:down` section of a migration file.
+struct Section {
+    /// From the start of the marker line to the next marker (or end of file).
+    span: Range<usize>,
+    /// End of the marker line, where options go.
+    marker_end: usize,
+    transaction: bool,
+}
+
+/// Find the up and down sections of a migration file.
+///
+/// A file without markers is all up migration.
+fn sections(sql: &str) -> (Option<Section>, Option<Section>) {
+    let markers: Vec<_> = SECTION_REGEX.captures_iter(sql).collect();
+    let mut up = None;
+    let mut down = None;
+
+    for (i, cap) in markers.iter().enumerate() {
+        let marker = cap.get(0).expect("whole match");
+        let end = markers
+            .get(i + 1)
+            .map_or(sql.len(), |next| next.get(0).expect("whole match").start());
+        let section = Section {
+            span: marker.start()..end,
+            marker_end: marker.end(),
+            transaction: !cap[2]
+                .split_whitespace()
+                .any(|option| option == "transaction:false"),
+        };
+        let slot = if &cap[1] == "up" { &mut up } else { &mut down };
+        // dbmate rejects repeated markers; keep the first like its parser
+        slot.get_or_insert(section);
+    }
+
+    if markers.is_empty() {
+        up = Some(Section {
+            span: 0..sql.len(),
+            marker_end: 0,
+            transaction: true,
+        });
+    }
+    (up, down)
+}
+
+/// Replace everything outside `keep` with spaces, keeping line breaks.
+fn blank_outside(sql: &str, keep: Option<&Range<usize>>) -> String {
+    let keep = keep.cloned().unwrap_or_default();
+    let mut out = Vec::with_capacity(sql.len());
+    for (i, byte) in sql.bytes().enumerate() {
+        out.push(if keep.contains(&i) || byte == b'\n' {
+            byte
+        } else {
+            b' '
+        });
+    }
+    // Multi-byte characters are either kept whole or replaced byte by byte
+    String::from_utf8(out).expect("section boundaries are ASCII")
+}
+
+impl MigrationAdapter for DbmateAdapter {
+    fn collect_migration_files(
+        &self,
+        dir: &Utf8Path,
+        start_after: Option<&str>,
+        check_down: bool,
+    ) -> Result<Vec<MigrationFile>> {
+        let mut files = Vec::new();
+
+        for entry in collect_and_sort_entries(dir)? {
+            let Some(path) = Utf8Path::from_path(entry.path()) else {
+                continue;
+            };
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let Some(timestamp) = self.parse_timestamp(path.file_name().unwrap_or("")) else {
+                continue;
+            };
+            if !should_check_migration(start_after, &timestamp) {
+                continue;
+            }
+
+            files.push(MigrationFile::new(path.to_owned(), timestamp.clone()));
+            if check_down {
+                files.push(MigrationFile::down_section(path.to_owned(), timestamp));
+            }
+        }
+
+        sort_by_numeric_version(&mut files);
+        Ok(files)
+    }
+
+    fn parse_timestamp(&self, name: &str) -> Option<String> {
+        DBMATE_NAME_REGEX
+            .captures(name)
+            .and_then(|cap| cap.get(1))
+            .map(|m| m.as_str().to_string())
+    }
+
+    fn validate_timestamp(&self, timestamp: &str) -> Result<()> {
+        if DBMATE_START_AFTER_REGEX.is_match(timestamp) {
+            Ok(())
+        } else {
+            Err(format!(
+                "Invalid dbmate version format: {timestamp}. Expected: a version number (e.g. 20240101120000)"
+            )
+            .into())
+        }
+    }
+
+    fn extract_migration_metadata(&self, file_path: &Utf8Path) -> MigrationContext {
+        self.migration_context(&MigrationFile::new(file_path.to_owned(), String::new()))
+    }
+
+    fn migration_context(&self, file: &MigrationFile) -> MigrationContext {
+        let (up, down) = std::fs::read_to_string(&file.path)
+            .map(|sql| sections(&sql))
+            .unwrap_or_default();
+        let (section, hint) = if file.down_section {
+            (down, DOWN_NO_TRANSACTION_HINT)
+        } else {
+            (up, UP_NO_TRANSACTION_HINT)
+        };
+
+        MigrationContext {
+            run_in_transaction: section.is_none_or(|s| s.transaction),
+            no_transaction_hint: hint,
+            ..MigrationContext::default()
+        }
+    }
+
+    fn read_sql(&self, file: &MigrationFile) -> std::io::Result<String> {
+        let sql = std::fs::read_to_string(&file.path)?;
+        let (up, down) = sections(&sql);
+        let section = if file.down_section { down } else { up };
+        Ok(blank_outside(&sql, section.as_ref().map(|s| &s.span)))
+    }
+
+    fn transaction_opt_out(&self, file_path: &Utf8Path) -> TransactionOptOut {
+        let up = std::fs::read_to_string(file_path)
+            .ok()
+            .and_then(|sql| sections(&sql).0);
+        match up {
+            // A file without markers doesn't run under dbmate anyway
+            Some(up) if up.marker_end > 0 => {
+                TransactionOptOut::Edit(TextEdit::insert(up.marker_end, " transaction:false"))
+            }
+            _ => TransactionOptOut::Unsupported,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    const MIGRATION: &str = "-- migrate:up\nCREATE INDEX idx ON users(email);\n\n-- migrate:down transaction:false\nDROP INDEX CONCURRENTLY idx;\n";
+
+    #[test]
+    fn test_parse_timestamp() {
+        let adapter = DbmateAdapter;
+        assert_eq!(
+            adapter.parse_timestamp("20240101120000_create_users.sql"),
+            Some("20240101120000".to_string())
+        );
+        assert_eq!(adapter.parse_timestamp("1.sql"), Some("1".to_string()));
+        assert_eq!(adapter.parse_timestamp("create_users.sql"), None);
+        assert_eq!(adapter.parse_timestamp("20240101120000_notes.txt"), None);
+    }
+
+    #[test]
+    fn test_validate_timestamp() {
+        let adapter = DbmateAdapter;
+        assert!(adapter.validate_timestamp("20240101120000").is_ok());
+        assert!(adapter.validate_timestamp("2024_01_01_120000").is_err());
+    }
+
+    #[test]
+    fn test_collect_adds_down_sections_when_check_down() {
+        let temp_dir = tempdir().expect("Failed to create temp dir");
+        let dir = Utf8Path::from_path(temp_dir.path()).unwrap();
+        for name in ["20240102_b.sql", "20240101_a.sql", "notes.sql"] {
+            fs::write(dir.join(name), MIGRATION).unwrap();
+        }
+
+        let adapter = DbmateAdapter;
+        let files = adapter.collect_migration_files(dir, None, false).unwrap();
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].timestamp, "20240101");
+        assert!(files.iter().all(|f| !f.down_section));
+
+        let files = adapter
+            .collect_migration_files(dir, Some("20240101"), true)
+            .unwrap();
+        let sections: Vec<_> = files
+            .iter()
+            .map(|f| (f.timestamp.as_str(), f.down_section))
+            .collect();
+        assert_eq!(sections, [("20240102", false), ("20240102", true)]);
+    }
+
+    #[test]
+    fn test_read_sql_blanks_other_section() {
+        let temp_dir = tempdir().expect("Failed to create temp dir");
+        let path = Utf8Path::from_path(temp_dir.path())
+            .unwrap()
+            .join("1_index.sql");
+        fs::write(&path, MIGRATION).unwrap();
+
+        let adapter = DbmateAdapter;
+        let up = adapter
+            .read_sql(&MigrationFile::new(path.clone(), "1".into()))
+            .unwrap();
+        let down = adapter
+            .read_sql(&MigrationFile::down_section(path, "1".into()))
+            .unwrap();
+
+        assert_eq!(up.len(), MIGRATION.len());
+        assert_eq!(down.len(), MIGRATION.len());
+        assert_eq!(up.lines().count(), MIGRATION.lines().count());
+        assert!(up.contains("CREATE INDEX idx") && !up.contains("DROP INDEX"));
+        assert!(down.contains("DROP INDEX") && !down.contains("CREATE INDEX"));
+        assert_eq!(down.lines().nth(4), Some("DROP INDEX CONCURRENTLY idx;"));
+    }
+
+    #[test]
+    fn test_transaction_option_is_per_section() {
+        let temp_dir = tempdir().expect("Failed to create temp dir");
+        let path = Utf8Path::from_path(temp_dir.path())
+            .unwrap()
+            .join("1_index.sql");
+        fs::write(&path, MIGRATION).unwrap();
+
+        let adapter = DbmateAdapter;
+        assert!(adapter.extract_migration_metadata(&path).run_in_transaction);
+        assert!(
+            !adapter
+                .migration_context(&MigrationFile::down_section(path, "1".into()))
+                .run_in_transaction
+        );
+    }
+
+    #[test]
+    fn test_transaction_opt_out_edits_up_marker() {
+        let temp_dir = tempdir().expect("Failed to create temp dir");
+        let path = Utf8Path::from_path(temp_dir.path())
+            .unwrap()
+            .join("1_index.sql");
+        fs::write(&path, MIGRATION).unwrap();
+
+        let TransactionOptOut::Edit(edit) = DbmateAdapter.transaction_opt_out(&path) else {
+            panic!("expected an edit");
+        };
+        assert_eq!(edit, TextEdit::insert(13, " transaction:false"));
+    }
+}
@@ -9,8 +9,8 @@
 //! ```
 
 use super::{
-    MigrationAdapter, MigrationContext, MigrationFile, Result, collect_and_sort_entries,
-    is_single_migration_dir, normalize_timestamp, should_check_migration,
+    MigrationAdapter, MigrationContext, MigrationFile, Result, TransactionOptOut,
+    collect_and_sort_entries, is_single_migration_dir, normalize_timestamp, should_check_migration,
 };
 use camino::Utf8Path;
 use regex::Regex;
@@ -125,6 +125,42 @@ impl MigrationAdapter for DieselAdapter {
             run_in_transaction: parsed.run_in_transaction.unwrap_or(true),
             no_transaction_hint: NO_TRANSACTION_HINT,
             disabled_checks: parsed.disable_checks,
+            ..MigrationContext::default()
+        }
+    }
+
+    fn transaction_opt_out(&self, file_path: &Utf8Path) -> TransactionOptOut {
+        let path = file_path
+            .parent()
+            .unwrap_or_else(|| Utf8Path::new(""))
+            .join("metadata.toml");
+        let original = std::fs::read_to_string(&path).ok();
+
+        // Keep the rest of an existing metadata.toml (e.g. disable_checks) untouched
+        let mut replaced = false;
+        let mut lines: Vec<&str> = original
+            .as_deref()
+            .unwrap_or_default()
+            .lines()
+            .map(|line| {
+                if line.trim_start().starts_with("run_in_transaction") {
+                    replaced = true;
+                    "run_in_transaction = false"
+                } else {
+                    line
+                }
+            })
+            .collect();
+        if !replaced {
+            lines.insert(0, "run_in_transaction = false");
+        }
+
+        let contents = lines.join("\n") + "\n";
+
+        TransactionOptOut::MetadataFile {
+            path,
+            original,
+            contents,
         }
     }
 }
@@ -0,0 +1,389 @@
+//! Flyway migration adapter.
+//!
+//! Supports Flyway's SQL migration files, anywhere under the migrations directory:
+//! 1. Versioned: `V<VERSION>__<DESC>.sql`, where the version parts are separated
+//!    by `.` or `_` (`V1_2__add_users.sql` is version 1.2)
+//! 2. Undo: `U<VERSION>__<DESC>.sql`, checked only with `check_down`
+//! 3. Repeatable: `R__<DESC>.sql`, run after all versioned migrations whenever
+//!    they change, so `start_after` never skips them
+//!
+//! A migration runs outside a transaction when its `<file>.sql.conf` sets
+//! `executeInTransaction=false`, or when every statement in it is one Flyway
+//! detects as non-transactional (the CONCURRENTLY variants).
+
+use super::{MigrationAdapter, MigrationContext, MigrationFile, Result, TransactionOptOut};
+use camino::{Utf8Path, Utf8PathBuf};
+use pg_query::protobuf::Token;
+use regex::Regex;
+use std::cmp::Ordering;
+use std::sync::LazyLock;
+use walkdir::WalkDir;
+
+/// Regex for versioned and undo migration file stems: prefix, version, description.
+static FLYWAY_VERSIONED_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^([VU])(\d+(?:[._]\d+)*)__(.+)$").expect("valid regex pattern"));
+
+/// Regex for repeatable migration file stems.
+static FLYWAY_REPEATABLE_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^R__(.+)$").expect("valid regex pattern"));
+
+/// Regex for `start_after` values: a version, optionally prefixed with `V`.
+static FLYWAY_START_AFTER_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^V?\d+(?:[._]\d+)*$").expect("valid regex pattern"));
+
+/// Prefix of the timestamp given to repeatable migrations, which have no version.
+const REPEATABLE_PREFIX: &str = "R__";
+
+const NO_TRANSACTION_HINT: &str =
+    "Add `executeInTransaction=false` to a `<migration>.sql.conf` file next to the migration.";
+
+/// Flyway migration adapter.
+pub struct FlywayAdapter;
+
+/// Version parts of a normalized version (`1.2.10` → `[1, 2, 10]`).
+fn version_parts(version: &str) -> Vec<u64> {
+    version
+        .trim_start_matches('V')
+        .split(['.', '_'])
+        .map(|part| part.parse().unwrap_or(u64::MAX))
+        .collect()
+}
+
+/// Compare versions the way Flyway does: part by part, missing parts are zero.
+fn compare_versions(a: &str, b: &str) -> Ordering {
+    let (a, b) = (version_parts(a), version_parts(b));
+    let len = a.len().max(b.len());
+    let part = |parts: &[u64], i: usize| parts.get(i).copied().unwrap_or(0);
+    (0..len)
+        .map(|i| part(&a, i).cmp(&part(&b, i)))
+        .find(|ord| ord.is_ne())
+        .unwrap_or(Ordering::Equal)
+}
+
+/// Path of the per-script config file for a migration.
+fn conf_path(file_path: &Utf8Path) -> Utf8PathBuf {
+    Utf8PathBuf::from(format!("{file_path}.conf"))
+}
+
+/// Whether a per-script config sets `executeInTransaction=false`.
+fn conf_disables_transaction(conf: &str) -> bool {
+    conf.lines().any(|line| {
+        line.split_once('=').is_some_and(|(key, value)| {
+            key.trim() == "executeInTransaction" && value.trim().eq_ignore_ascii_case("false")
+        })
+    })
+}
+
+/// Whether every statement uses CONCURRENTLY, which Flyway runs outside a transaction.
+fn all_statements_non_transactional(sql: &str) -> bool {
+    let Ok(stmts) = pg_query::split_with_scanner(sql) else {
+        return false;
+    };
+    let is_concurrent = |stmt: &str| {
+        pg_query::scan(stmt).is_ok_and(|scan| {
+            scan.tokens
+                .iter()
+                .any(|t| t.token == Token::Concurrently as i32)
+        })
+    };
+    !stmts.is_empty() && stmts.iter().all(|stmt| is_concurrent(stmt))
+}
+
+impl MigrationAdapter for FlywayAdapter {
+    fn collect_migration_files(
+        &self,
+        dir: &Utf8Path,
+        start_after: Option<&str>,
+        check_down: bool,
+    ) -> Result<Vec<MigrationFile>> {
+        let mut versioned = Vec::new();
+        let mut repeatable = Vec::new();
+
+        // Flyway scans its locations recursively
+        for entry in WalkDir::new(dir).min_depth(1) {
+            let entry = entry?;
+            let Some(path) = Utf8Path::from_path(entry.path()) else {
+                continue;
+            };
+            if !entry.file_type().is_file() || path.extension() != Some("sql") {
+                continue;
+            }
+            let name = path.file_stem().unwrap_or("");
+
+            if let Some(cap) = FLYWAY_VERSIONED_REGEX.captures(name) {
+                if &cap[1] == "U" && !check_down {
+                    continue;
+                }
+                let timestamp = cap[2].replace('_', ".");
+                let after_start =
+                    start_after.is_none_or(|start| compare_versions(&timestamp, start).is_gt());
+                if after_start {
+                    versioned.push((
+                        &cap[1] == "U",
+                        MigrationFile::new(path.to_owned(), timestamp),
+                    ));
+                }
+            } else if let Some(cap) = FLYWAY_REPEATABLE_REGEX.captures(name) {
+                let timestamp = format!("{REPEATABLE_PREFIX}{}", &cap[1]);
+                repeatable.push(MigrationFile::repeatable(path.to_owned(), timestamp));
+            }
+        }
+
+        // Versions in order, each undo right after its migration; then repeatables by description
+        versioned.sort_by(|(a_undo, a), (b_undo, b)| {
+            compare_versions(&a.timestamp, &b.timestamp)
+                .then(a_undo.cmp(b_undo))
+                .then_with(|| a.path.cmp(&b.path))
+        });
+        repeatable.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
+
+        Ok(versioned
+            .into_iter()
+            .map(|(_, file)| file)
+            .chain(repeatable)
+            .collect())
+    }
+
+    fn parse_timestamp(&self, name: &str) -> Option<String> {
+        let stem = name.strip_suffix(".sql").unwrap_or(name);
+        FLYWAY_VERSIONED_REGEX
+            .captures(stem)
+            .map(|cap| cap[2].replace('_', "."))
+    }
+
+    fn validate_timestamp(&self, timestamp: &str) -> Result<()> {
+        if FLYWAY_START_AFTER_REGEX.is_match(timestamp) {
+            Ok(())
+        } else {
+            Err(format!(
+                "Invalid Flyway version format: {timestamp}. Expected: a version (e.g. 3, 1.2 or V1_2)"
+            )
+            .into())
+        }
+    }
+
+    fn extract_migration_metadata(&self, file_path: &Utf8Path) -> MigrationContext {
+        let conf_opt_out = std::fs::read_to_string(conf_path(file_path))
+            .is_ok_and(|conf| conf_disables_transaction(&conf));
+        let auto_detected = std::fs::read_to_string(file_path)
+            .is_ok_and(|sql| all_statements_non_transactional(&sql));
+
+        MigrationContext {
+            run_in_transaction: !conf_opt_out && !auto_detected,
+            no_transaction_hint: NO_TRANSACTION_HINT,
+            ..MigrationContext::default()
+        }
+    }
+
+    fn transaction_opt_out(&self, file_path: &Utf8Path) -> TransactionOptOut {
+        let path = conf_path(file_path);
+        let original = std::fs::read_to_string(&path).ok();
+        let mut contents = String::new();
+        let mut replaced = false;
+        // Overwrite an existing setting in place, so the file never holds both values
+        for line in original
+            .as_deref()
+            .unwrap_or_default()
+            .split_inclusive('\n')
+        {
+            if line
+                .split_once('=')
+                .is_some_and(|(key, _)| key.trim() == "executeInTransaction")
+            {
+                contents.push_str("executeInTransaction=false\n");
+                replaced = true;
+            } else {
+                contents.push_str(line);
+            }
+        }
+        if !replaced {
+            if !contents.is_empty() && !contents.ends_with('\n') {
+                contents.push('\n');
+            }
+            contents.push_str("executeInTransaction=false\n");
+        }
+        TransactionOptOut::MetadataFile {
+            path,
+            original,
+            contents,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_parse_timestamp() {
+        let adapter = FlywayAdapter;
+        assert_eq!(
+            adapter.parse_timestamp("V1__init.sql"),
+            Some("1".to_string())
+        );
+        assert_eq!(
+            adapter.parse_timestamp("V1_2__add_users"),
+            Some("1.2".to_string())
+        );
+        assert_eq!(
+            adapter.parse_timestamp("U2.1.3__drop_users.sql"),
+            Some("2.1.3".to_string())
+        );
+        assert_eq!(adapter.parse_timestamp("R__views.sql"), None);
+        assert_eq!(adapter.parse_timestamp("V1_init.sql"), None);
+    }
+
+    #[test]
+    fn test_validate_timestamp() {
+        let adapter = FlywayAdapter;
+        assert!(adapter.validate_timestamp("3").is_ok());
+        assert!(adapter.validate_timestamp("1.2").is_ok());
+        assert!(adapter.validate_timestamp("V1_2").is_ok());
+        assert!(adapter.validate_timestamp("1.").is_err());
+        assert!(adapter.validate_timestamp("R").is_err());
+    }
+
+    #[test]
+    fn test_compare_versions() {
+        assert!(compare_versions("1.10", "1.9").is_gt());
+        assert!(compare_versions("1.0", "1").is_eq());
+        assert!(compare_versions("2", "1.9.9").is_gt());
+        assert!(compare_versions("1.2", "V1_2").is_eq());
+    }
+
+    #[test]
+    fn test_collect_orders_versions_then_repeatables() {
+        let temp_dir = tempdir().expect("Failed to create temp dir");
+        let dir = Utf8Path::from_path(temp_dir.path()).unwrap();
+        fs::create_dir(dir.join("nested")).unwrap();
+        for name in [
+            "R__views.sql",
+            "R__functions.sql",
+            "V1_10__c.sql",
+            "V1_9__b.sql",
+            "nested/V1__a.sql",
+            "U1_9__b.sql",
+            "V2__d.sql.conf",
+            "README.md",
+        ] {
+            fs::write(dir.join(name), "SELECT 1;").unwrap();
+        }
+
+        let adapter = FlywayAdapter;
+        let names = |files: Vec<MigrationFile>| -> Vec<String> {
+            files
+                .iter()
+                .map(|f| f.path.file_name().unwrap().to_string())
+                .collect()
+        };
+
+        assert_eq!(
+            names(adapter.collect_migration_files(dir, None, false).unwrap()),
+            [
+                "V1__a.sql",
+                "V1_9__b.sql",
+                "V1_10__c.sql",
+                "R__functions.sql",
+                "R__views.sql"
+            ]
+        );
+        assert_eq!(
+            names(
+                adapter
+                    .collect_migration_files(dir, Some("V1_2"), true)
+                    .unwrap()
+            ),
+            [
+                "V1_9__b.sql",
+                "U1_9__b.sql",
+                "V1_10__c.sql",
+                "R__functions.sql",
+                "R__views.sql"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_conf_file_disables_transaction() {
+        let temp_dir = tempdir().expect("Failed to create temp dir");
+        let dir = Utf8Path::from_path(temp_dir.path()).unwrap();
+        let path = dir.join("V1__index.sql");
+        fs::write(&path, "CREATE INDEX idx ON users(email);\n").unwrap();
+
+        let adapter = FlywayAdapter;
+        assert!(adapter.extract_migration_metadata(&path).run_in_transaction);
+
+        fs::write(
+            dir.join("V1__index.sql.conf"),
+            "executeInTransaction = false\n",
+        )
+        .unwrap();
+        assert!(!adapter.extract_migration_metadata(&path).run_in_transaction);
+    }
+
+    #[test]
+    fn test_concurrent_statements_are_detected() {
+        let temp_dir = tempdir().expect("Failed to create temp dir");
+        let dir = Utf8Path::from_path(temp_dir.path()).unwrap();
+        let only = dir.join("V1__index.sql");
+        let mixed = dir.join("V2__index.sql");
+        fs::write(&only, "CREATE INDEX CONCURRENTLY idx ON users(email);\n").unwrap();
+        fs::write(
+            &mixed,
+            "CREATE INDEX CONCURRENTLY idx ON users(email);\nALTER TABLE users ADD COLUMN x int;\n",
+        )
+        .unwrap();
+
+        let adapter = FlywayAdapter;
+        assert!(!adapter.extract_migration_metadata(&only).run_in_transaction);
+        assert!(
+            adapter
+                .extract_migration_metadata(&mixed)
+                .run_in_transaction
+        );
+    }
+
+    #[test]
+    fn test_transaction_opt_out_writes_conf_file() {
+        let temp_dir = tempdir().expect("Failed to create temp dir");
+        let dir = Utf8Path::from_path(temp_dir.path()).unwrap();
+        let path = dir.join("V1__index.sql");
+        fs::write(dir.join("V1__index.sql.conf"), "mixed=true").unwrap();
+
+        let TransactionOptOut::MetadataFile {
+            path: conf,
+            original,
+            contents,
+        } = FlywayAdapter.transaction_opt_out(&path)
+        else {
+            panic!("expected a metadata file");
+        };
+        assert_eq!(conf, dir.join("V1__index.sql.conf"));
+        assert_eq!(original.as_deref(), Some("mixed=true"));
+        assert_eq!(contents, "mixed=true\nexecuteInTransaction=false\n");
+    }
+
+    #[test]
+    fn test_transaction_opt_out_replaces_existing_setting() {
+        let temp_dir = tempdir().expect("Failed to create temp dir");
+        let dir = Utf8Path::from_path(temp_dir.path()).unwrap();
+        let path = dir.join("V1__index.sql");
+        fs::write(
+            dir.join("V1__index.sql.conf"),
+            "mixed=true\nexecuteInTransaction = true\nencoding=UTF-8\n",
+        )
+        .unwrap();
+
+        let TransactionOptOut::MetadataFile { contents, .. } =
+            FlywayAdapter.transaction_opt_out(&path)
+        else {
+            panic!("expected a metadata file");
+        };
+        assert_eq!(
+            contents,
+            "mixed=true\nexecuteInTransaction=false\nencoding=UTF-8\n"
+        );
+    }
+}
@@ -0,0 +1,202 @@
+//! golang-migrate migration adapter.
+//!
+//! Supports golang-migrate's file pairs: `<VERSION>_<TITLE>.up.sql` / `<VERSION>_<TITLE>.down.sql`.
+//!
+//! golang-migrate never wraps a migration in a transaction. The PostgreSQL driver
+//! sends the whole file as one query, though, and PostgreSQL runs a query with
+//! several statements in an implicit transaction. A file therefore only runs
+//! outside a transaction when it holds a single statement.
+
+use super::{
+    MigrationAdapter, MigrationContext, MigrationFile, Result, TransactionOptOut,
+    collect_and_sort_entries, should_check_migration, sort_by_numeric_version,
+};
+use crate::parser;
+use camino::Utf8Path;
+use regex::Regex;
+use std::sync::LazyLock;
+
+/// Regex for golang-migrate file names: version, title, direction.
+static GOLANG_MIGRATE_NAME_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(\d+)_(.*)\.(up|down)\.sql$").expect("valid regex pattern"));
+
+/// Regex for `start_after` values: a plain version number.
+static GOLANG_MIGRATE_START_AFTER_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\d+$").expect("valid regex pattern"));
+
+const NO_TRANSACTION_HINT: &str = "Move the statement into a migration file of its own: \
+     PostgreSQL runs a file with several statements in an implicit transaction \
+     (unless the database URL sets `x-multi-statement=true`).";
+
+/// golang-migrate migration adapter.
+pub struct GolangMigrateAdapter;
+
+impl MigrationAdapter for GolangMigrateAdapter {
+    fn collect_migration_files(
+        &self,
+        dir: &Utf8Path,
+        start_after: Option<&str>,
+        check_down: bool,
+    ) -> Result<Vec<MigrationFile>> {
+        let mut files = Vec::new();
+
+        for entry in collect_and_sort_entries(dir)? {
+            let Some(path) = Utf8Path::from_path(entry.path()) else {
+                continue;
+            };
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let Some(cap) = GOLANG_MIGRATE_NAME_REGEX.captures(path.file_name().unwrap_or(""))
+            else {
+                continue;
+            };
+            if &cap[3] == "down" && !check_down {
+                continue;
+            }
+
+            let timestamp = cap[1].to_string();
+            if should_check_migration(start_after, &timestamp) {
+                files.push(MigrationFile::new(path.to_owned(), timestamp));
+            }
+        }
+
+        // golang-migrate compares versions as numbers (10 after 9)
+        sort_by_numeric_version(&mut files);
+        Ok(files)
+    }
+
+    fn parse_timestamp(&self, name: &str) -> Option<String> {
+        GOLANG_MIGRATE_NAME_REGEX
+            .captures(name)
+            .and_then(|cap| cap.get(1))
+            .map(|m| m.as_str().to_string())
+    }
+
+    fn validate_timestamp(&self, timestamp: &str) -> Result<()> {
+        if GOLANG_MIGRATE_START_AFTER_REGEX.is_match(timestamp) {
+            Ok(())
+        } else {
+            Err(format!(
+                "Invalid golang-migrate version format: {timestamp}. Expected: a version number (e.g. 3 or 20240101000000)"
+            )
+            .into())
+        }
+    }
+
+    fn extract_migration_metadata(&self, file_path: &Utf8Path) -> MigrationContext {
+        // Unparsable files are reported by the checker, not here
+        let multi_statement = std::fs::read_to_string(file_path)
+            .ok()
+            .and_then(|sql| parser::parse(&sql).ok())
+            .is_some_and(|stmts| stmts.len() > 1);
+
+        MigrationContext {
+            run_in_transaction: multi_statement,
+            no_transaction_hint: NO_TRANSACTION_HINT,
+            ..MigrationContext::default()
+        }
+    }
+
+    fn transaction_opt_out(&self, _file_path: &Utf8Path) -> TransactionOptOut {
+        TransactionOptOut::Unsupported
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_parse_timestamp() {
+        let adapter = GolangMigrateAdapter;
+        assert_eq!(
+            adapter.parse_timestamp("1_init.up.sql"),
+            Some("1".to_string())
+        );
+        assert_eq!(
+            adapter.parse_timestamp("20240101120000_add_users.down.sql"),
+            Some("20240101120000".to_string())
+        );
+        assert_eq!(adapter.parse_timestamp("1_init.sql"), None);
+        assert_eq!(adapter.parse_timestamp("init.up.sql"), None);
+    }
+
+    #[test]
+    fn test_validate_timestamp() {
+        let adapter = GolangMigrateAdapter;
+        assert!(adapter.validate_timestamp("3").is_ok());
+        assert!(adapter.validate_timestamp("20240101120000").is_ok());
+        assert!(adapter.validate_timestamp("V3").is_err());
+        assert!(adapter.validate_timestamp("2024_01_01").is_err());
+    }
+
+    #[test]
+    fn test_collect_orders_by_version_and_skips_down_files() {
+        let temp_dir = tempdir().expect("Failed to create temp dir");
+        let dir = Utf8Path::from_path(temp_dir.path()).unwrap();
+        for name in [
+            "1_init.up.sql",
+            "1_init.down.sql",
+            "2_users.up.sql",
+            "2_users.down.sql",
+            "10_posts.up.sql",
+            "3_notes.sql",
+        ] {
+            fs::write(dir.join(name), "SELECT 1;").unwrap();
+        }
+
+        let adapter = GolangMigrateAdapter;
+        let names = |files: Vec<MigrationFile>| -> Vec<String> {
+            files
+                .iter()
+                .map(|f| f.path.file_name().unwrap().to_string())
+                .collect()
+        };
+
+        assert_eq!(
+            names(adapter.collect_migration_files(dir, None, false).unwrap()),
+            ["1_init.up.sql", "2_users.up.sql", "10_posts.up.sql"]
+        );
+        assert_eq!(
+            names(
+                adapter
+                    .collect_migration_files(dir, Some("1"), true)
+                    .unwrap()
+            ),
+            ["2_users.down.sql", "2_users.up.sql", "10_posts.up.sql"]
+        );
+    }
+
+    #[test]
+    fn test_single_statement_runs_outside_transaction() {
+        let temp_dir = tempdir().expect("Failed to create temp dir");
+        let dir = Utf8Path::from_path(temp_dir.path()).unwrap();
+        let single = dir.join("1_index.up.sql");
+        let multi = dir.join("2_index.up.sql");
+        fs::write(
+            &single,
+            "-- comment\nCREATE INDEX CONCURRENTLY idx ON users(email);\n",
+        )
+        .unwrap();
+        fs::write(
+            &multi,
+            "CREATE INDEX CONCURRENTLY idx ON users(email);\nSELECT 1;\n",
+        )
+        .unwrap();
+
+        let adapter = GolangMigrateAdapter;
+        assert!(
+            !adapter
+                .extract_migration_metadata(&single)
+                .run_in_transaction
+        );
+        assert!(
+            adapter
+                .extract_migration_metadata(&multi)
+                .run_in_transaction
+        );
+    }
+}
@@ -1,19 +1,34 @@
 //! Migration framework adapters.
 //!
-//! This module provides abstractions for different migration frameworks (Diesel, SQLx, etc.).
+//! This module provides abstractions for different migration frameworks (Diesel, SQLx, refinery, etc.).
 //! Each framework implements the `MigrationAdapter` trait to handle framework-specific
 //! file discovery, timestamp parsing, and validation.
 //!
-//! The framework is explicitly configured via the `framework` field in `diesel-guard.toml`.
+//! The framework is configured via the `framework` field in `diesel-guard.toml`, or
+//! detected from the project when it is not set (see [`crate::detect`]).
 
+use crate::schema::{SchemaCatalog, relation_key};
+use crate::violation::TextEdit;
 use camino::{Utf8Path, Utf8PathBuf};
+use std::collections::BTreeMap;
 use std::error::Error;
+use std::sync::Arc;
 use walkdir::{DirEntry, WalkDir};
 
+mod dbmate;
 mod diesel;
+mod flyway;
+mod golang_migrate;
+mod refinery;
+mod seaorm;
 mod sqlx;
 
+pub use dbmate::DbmateAdapter;
 pub use diesel::DieselAdapter;
+pub use flyway::FlywayAdapter;
+pub use golang_migrate::GolangMigrateAdapter;
+pub use refinery::RefineryAdapter;
+pub use seaorm::SeaOrmAdapter;
 pub use sqlx::SqlxAdapter;
 
 /// Result type for adapter operations.
@@ -30,6 +45,34 @@ pub struct MigrationContext {
     pub no_transaction_hint: &'static str,
     /// Check names disabled for this specific migration.
     pub disabled_checks: Vec<String>,
+    /// Schema produced by the migrations that run before this one.
+    /// Empty when checking a single file or SQL string.
+    #[serde(serialize_with = "serialize_schema")]
+    pub schema: Arc<SchemaCatalog>,
+    /// Tables created by earlier statements of the same migration.
+    /// Filled in by the registry while it walks the statements.
+    pub created_tables: Vec<String>,
+    /// Settings changed by earlier `SET` statements of the same migration,
+    /// keyed by lowercase name. Filled in by the registry like `created_tables`.
+    pub settings: BTreeMap<String, Setting>,
+    /// Whether the migration opened a transaction with `BEGIN` that is still open.
+    pub in_transaction_block: bool,
+}
+
+/// A setting changed by a `SET` statement.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct Setting {
+    /// The value as written, e.g. `5s` or `1000`.
+    pub value: String,
+    /// Set with `SET LOCAL`, so it ends with the current transaction.
+    pub local: bool,
+}
+
+fn serialize_schema<S: serde::Serializer>(
+    schema: &Arc<SchemaCatalog>,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    serde::Serialize::serialize(schema.as_ref(), serializer)
 }
 
 impl Default for MigrationContext {
@@ -38,6 +81,10 @@ impl Default for MigrationContext {
             run_in_transaction: true,
             no_transaction_hint: "",
             disabled_checks: Vec::new(),
+            schema: Arc::default(),
+            created_tables: Vec::new(),
+            settings: BTreeMap::new(),
+            in_transaction_block: false,
         }
     }
 }
@@ -64,6 +111,40 @@ impl MigrationContext {
         }
     }
 
+    /// Return a copy of this context that sees the given schema.
+    #[must_use]
+    pub fn with_schema(&self, schema: Arc<SchemaCatalog>) -> Self {
+        Self {
+            schema,
+            ..self.clone()
+        }
+    }
+
+    /// Return true when the table was created earlier in this migration.
+    ///
+    /// Lock-based checks skip such tables on the assumption that they are
+    /// still empty and that nothing else uses them yet: a new table has no rows
+    /// and no application code referring to it. `CREATE TABLE AS` and
+    /// materialized views only count when created `WITH NO DATA` inside a
+    /// transaction, since otherwise they hold rows or are visible to others.
+    pub fn is_new_table(&self, name: &str) -> bool {
+        let name = relation_key(name);
+        self.created_tables.contains(&name)
+    }
+
+    /// Return true when statements run inside a transaction: the one the
+    /// framework wraps the migration in, or one opened with `BEGIN`.
+    pub fn in_transaction(&self) -> bool {
+        self.run_in_transaction || self.in_transaction_block
+    }
+
+    /// Value of a setting changed earlier in this migration, e.g. `lock_timeout`.
+    pub fn setting(&self, name: &str) -> Option<&str> {
+        self.settings
+            .get(&name.to_ascii_lowercase())
+            .map(|setting| setting.value.as_str())
+    }
+
     /// Return true when this migration disables a specific check.
     pub fn disables_check(&self, check_name: &str) -> bool {
         self.disabled_checks.iter().any(|name| name == check_name)
@@ -77,11 +158,36 @@ pub struct MigrationFile {
     pub path: Utf8PathBuf,
     /// Timestamp extracted from migration name.
     pub timestamp: String,
+    /// The rollback section of a file that holds both directions (dbmate).
+    pub down_section: bool,
+    /// Re-run whenever its content changes (Flyway `R__`), so editing it is expected.
+    pub repeatable: bool,
 }
 
 impl MigrationFile {
     pub fn new(path: Utf8PathBuf, timestamp: String) -> Self {
-        Self { path, timestamp }
+        Self {
+            path,
+            timestamp,
+            down_section: false,
+            repeatable: false,
+        }
+    }
+
+    /// The rollback section of a file whose up section is `new(path, timestamp)`.
+    pub fn down_section(path: Utf8PathBuf, timestamp: String) -> Self {
+        Self {
+            down_section: true,
+            ..Self::new(path, timestamp)
+        }
+    }
+
+    /// A migration that runs again whenever it changes.
+    pub fn repeatable(path: Utf8PathBuf, timestamp: String) -> Self {
+        Self {
+            repeatable: true,
+            ..Self::new(path, timestamp)
+        }
     }
 }
 
@@ -124,6 +230,68 @@ pub trait MigrationAdapter: Send + Sync {
     /// for Diesel, `-- no-transaction` directive for SQLx) and return it as
     /// a `MigrationContext`.
     fn extract_migration_metadata(&self, file_path: &Utf8Path) -> MigrationContext;
+
+    /// Describe the change that makes a migration run outside a transaction.
+    ///
+    /// Used by `diesel-guard fix` after it rewrites a statement to use CONCURRENTLY.
+    fn transaction_opt_out(&self, file_path: &Utf8Path) -> TransactionOptOut;
+
+    /// Context for one migration file or section.
+    ///
+    /// Defaults to [`Self::extract_migration_metadata`]; adapters that keep both
+    /// directions in one file override it to read the down section's options.
+    fn migration_context(&self, file: &MigrationFile) -> MigrationContext {
+        self.extract_migration_metadata(&file.path)
+    }
+
+    /// Read the SQL of a migration file.
+    ///
+    /// Adapters that keep both directions in one file return only the section
+    /// `file` refers to, with the rest blanked so byte offsets still match the file.
+    /// Adapters for migrations written in another language return the SQL they
+    /// embed instead, laid out on the lines it occupies in the source file.
+    fn read_sql(&self, file: &MigrationFile) -> std::io::Result<String> {
+        std::fs::read_to_string(&file.path)
+    }
+
+    /// Whether `read_sql` extracts the SQL of `file_path` from another language.
+    ///
+    /// Byte offsets into such SQL don't point into the migration file, so
+    /// violations carry no fixes and `diesel-guard fix` leaves the file alone.
+    fn embeds_sql(&self, _file_path: &Utf8Path) -> bool {
+        false
+    }
+}
+
+/// Framework-specific way to run a migration outside a transaction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TransactionOptOut {
+    /// Line to add at the top of the migration file (SQLx).
+    Directive(&'static str),
+    /// File to write next to the migration (Diesel's `metadata.toml`).
+    /// `original` is `None` when the file does not exist yet.
+    MetadataFile {
+        path: Utf8PathBuf,
+        original: Option<String>,
+        contents: String,
+    },
+    /// Edit to the migration file itself (dbmate's `transaction:false` option).
+    Edit(TextEdit),
+    /// The framework has no per-migration switch (SeaORM, golang-migrate).
+    Unsupported,
+}
+
+/// Sort migrations by numeric version (`10` after `9`), then by path.
+///
+/// Non-numeric versions sort last.
+pub(crate) fn sort_by_numeric_version(files: &mut [MigrationFile]) {
+    let version = |file: &MigrationFile| file.timestamp.parse::<u64>().unwrap_or(u64::MAX);
+    files.sort_by(|a, b| {
+        version(a)
+            .cmp(&version(b))
+            .then_with(|| a.path.cmp(&b.path))
+            .then_with(|| a.down_section.cmp(&b.down_section))
+    });
 }
 
 pub(crate) fn normalize_timestamp(ts: &str) -> String {
@@ -0,0 +1,403 @@
+//! Refinery migration adapter.
+//!
+//! Supports refinery's migration files, anywhere under the migrations directory:
+//! 1. Versioned: `V<VERSION>__<NAME>.sql`
+//! 2. Unversioned: `U<VERSION>__<NAME>.sql`
+//!
+//! Both kinds are forward migrations (refinery has no down migrations).
+//! Rust migrations (`V<VERSION>__<NAME>.rs`) are checked too: string literals in
+//! `fn migration` that parse as SQL are extracted like SeaORM's raw SQL. SQL built
+//! at runtime, e.g. with barrel, can't be checked.
+
+use super::seaorm::{StrLiteral, Token, fn_body, layout_literals, lex};
+use super::{
+    MigrationAdapter, MigrationContext, MigrationFile, Result, TransactionOptOut,
+    should_check_migration, sort_by_numeric_version,
+};
+use camino::Utf8Path;
+use regex::Regex;
+use std::sync::LazyLock;
+use walkdir::WalkDir;
+
+/// Regex for refinery migration file stems, as refinery itself matches them.
+static REFINERY_NAME_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^[UV](\d+)__\w+$").expect("valid regex pattern"));
+
+/// Regex for `start_after` values: a plain version number, optionally prefixed with `V`/`U`.
+static REFINERY_START_AFTER_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^[UV]?\d+$").expect("valid regex pattern"));
+
+/// Regex for refinery's no-transaction directive, which may be on any line.
+static NO_TRANSACTION_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^-{2,}\s?refinery:noTransaction$").expect("valid regex pattern"));
+
+const NO_TRANSACTION_HINT: &str =
+    "Add `-- refinery:noTransaction` on its own line in the migration.";
+
+const RUST_NO_TRANSACTION_HINT: &str =
+    "Add a `-- refinery:noTransaction` line to the SQL string that `migration()` returns.";
+
+/// Refinery migration adapter.
+pub struct RefineryAdapter;
+
+impl MigrationAdapter for RefineryAdapter {
+    fn collect_migration_files(
+        &self,
+        dir: &Utf8Path,
+        start_after: Option<&str>,
+        _check_down: bool,
+    ) -> Result<Vec<MigrationFile>> {
+        let start_after = start_after.map(|s| s.trim_start_matches(['U', 'V']));
+        let mut files = Vec::new();
+
+        // Refinery picks up migrations in nested directories too
+        for entry in WalkDir::new(dir).min_depth(1) {
+            let entry = entry?;
+            let Some(path) = Utf8Path::from_path(entry.path()) else {
+                continue;
+            };
+            if !entry.file_type().is_file() || !matches!(path.extension(), Some("sql" | "rs")) {
+                continue;
+            }
+
+            let Some(timestamp) = self.parse_timestamp(path.file_stem().unwrap_or("")) else {
+                continue;
+            };
+            if should_check_migration(start_after, &timestamp) {
+                files.push(MigrationFile::new(path.to_owned(), timestamp));
+            }
+        }
+
+        // Refinery runs migrations by numeric version, not by file name (V10 after V9)
+        sort_by_numeric_version(&mut files);
+        Ok(files)
+    }
+
+    fn parse_timestamp(&self, name: &str) -> Option<String> {
+        let stem = name
+            .strip_suffix(".sql")
+            .or_else(|| name.strip_suffix(".rs"))
+            .unwrap_or(name);
+        REFINERY_NAME_REGEX
+            .captures(stem)
+            .and_then(|cap| cap.get(1))
+            .map(|m| m.as_str().to_string())
+    }
+
+    fn validate_timestamp(&self, timestamp: &str) -> Result<()> {
+        if REFINERY_START_AFTER_REGEX.is_match(timestamp) {
+            Ok(())
+        } else {
+            Err(format!(
+                "Invalid refinery version format: {timestamp}. Expected: a version number (e.g. 3 or V3)"
+            )
+            .into())
+        }
+    }
+
+    fn extract_migration_metadata(&self, file_path: &Utf8Path) -> MigrationContext {
+        let embeds_sql = self.embeds_sql(file_path);
+        let has_no_transaction = std::fs::read_to_string(file_path).is_ok_and(|content| {
+            // In a Rust migration the directive lives in the returned SQL string
+            let lines = if embeds_sql {
+                rust_sql_lines(&content)
+            } else {
+                content.lines().map(str::to_string).collect()
+            };
+            lines
+                .iter()
+                .any(|line| NO_TRANSACTION_REGEX.is_match(line.trim()))
+        });
+
+        MigrationContext {
+            run_in_transaction: !has_no_transaction,
+            no_transaction_hint: if embeds_sql {
+                RUST_NO_TRANSACTION_HINT
+            } else {
+                NO_TRANSACTION_HINT
+            },
+            ..MigrationContext::default()
+        }
+    }
+
+    fn transaction_opt_out(&self, file_path: &Utf8Path) -> TransactionOptOut {
+        if self.embeds_sql(file_path) {
+            TransactionOptOut::Unsupported
+        } else {
+            TransactionOptOut::Directive("-- refinery:noTransaction")
+        }
+    }
+
+    fn read_sql(&self, file: &MigrationFile) -> std::io::Result<String> {
+        let source = std::fs::read_to_string(&file.path)?;
+        if self.embeds_sql(&file.path) {
+            Ok(extract_sql(&source))
+        } else {
+            Ok(source)
+        }
+    }
+
+    fn embeds_sql(&self, file_path: &Utf8Path) -> bool {
+        file_path.extension() == Some("rs")
+    }
+}
+
+/// String literals inside `fn migration`, or anywhere if there is none.
+fn migration_literals<'a>(tokens: &'a [Token]) -> impl Iterator<Item = &'a StrLiteral> {
+    let body = fn_body(tokens, "migration");
+    tokens.iter().filter_map(move |token| match token {
+        Token::Str(literal)
+            if body
+                .as_ref()
+                .is_none_or(|b| b.contains(&literal.content.start)) =>
+        {
+            Some(literal)
+        }
+        _ => None,
+    })
+}
+
+/// Extract the SQL string literals from a refinery Rust migration.
+///
+/// Only literals that parse as at least one statement count, so table and
+/// column names passed to barrel are left out.
+fn extract_sql(source: &str) -> String {
+    let tokens = lex(source);
+    let literals = migration_literals(&tokens).filter(|literal| {
+        pg_query::parse(&literal.text(source)).is_ok_and(|parsed| !parsed.protobuf.stmts.is_empty())
+    });
+    layout_literals(source, literals)
+}
+
+/// Lines of the migration's string literals, split at real and escaped newlines.
+fn rust_sql_lines(source: &str) -> Vec<String> {
+    let tokens = lex(source);
+    migration_literals(&tokens)
+        .flat_map(|literal| {
+            let content = &source[literal.content.clone()];
+            content
+                .split('\n')
+                .flat_map(|line| {
+                    if literal.raw {
+                        vec![line]
+                    } else {
+                        line.split("\\n").collect()
+                    }
+                })
+                .map(|line| line.trim_end_matches("\\r").to_string())
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_parse_timestamp() {
+        let adapter = RefineryAdapter;
+        assert_eq!(
+            adapter.parse_timestamp("V1__initial"),
+            Some("1".to_string())
+        );
+        assert_eq!(
+            adapter.parse_timestamp("U42__add_users.sql"),
+            Some("42".to_string())
+        );
+        assert_eq!(
+            adapter.parse_timestamp("V20240101000000__create_users"),
+            Some("20240101000000".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_timestamp_invalid() {
+        let adapter = RefineryAdapter;
+        assert_eq!(adapter.parse_timestamp("1__initial"), None);
+        assert_eq!(adapter.parse_timestamp("V1_initial"), None);
+        assert_eq!(adapter.parse_timestamp("D1__initial"), None);
+        assert_eq!(adapter.parse_timestamp("V1__with-dash"), None);
+    }
+
+    #[test]
+    fn test_validate_timestamp() {
+        let adapter = RefineryAdapter;
+        assert!(adapter.validate_timestamp("3").is_ok());
+        assert!(adapter.validate_timestamp("V3").is_ok());
+        assert!(adapter.validate_timestamp("U12").is_ok());
+        assert!(adapter.validate_timestamp("").is_err());
+        assert!(adapter.validate_timestamp("V").is_err());
+        assert!(adapter.validate_timestamp("2024_01_01_000000").is_err());
+    }
+
+    #[test]
+    fn test_collect_orders_by_version_and_filters_start_after() {
+        let temp_dir = tempdir().expect("Failed to create temp dir");
+        let dir = Utf8Path::from_path(temp_dir.path()).unwrap();
+        fs::create_dir(dir.join("nested")).unwrap();
+        for name in [
+            "V1__init.sql",
+            "V2__users.sql",
+            "nested/V10__posts.sql",
+            "U3__seed.sql",
+            "V4__rust.rs",
+            "mod.rs",
+            "README.md",
+        ] {
+            fs::write(dir.join(name), "SELECT 1;").unwrap();
+        }
+
+        let adapter = RefineryAdapter;
+        let names = |files: Vec<MigrationFile>| -> Vec<String> {
+            files
+                .iter()
+                .map(|f| f.path.file_name().unwrap().to_string())
+                .collect()
+        };
+
+        assert_eq!(
+            names(adapter.collect_migration_files(dir, None, false).unwrap()),
+            [
+                "V1__init.sql",
+                "V2__users.sql",
+                "U3__seed.sql",
+                "V4__rust.rs",
+                "V10__posts.sql"
+            ]
+        );
+        assert_eq!(
+            names(
+                adapter
+                    .collect_migration_files(dir, Some("V2"), false)
+                    .unwrap()
+            ),
+            ["U3__seed.sql", "V4__rust.rs", "V10__posts.sql"]
+        );
+    }
+
+    #[test]
+    fn test_extract_metadata_no_directive_defaults_to_in_transaction() {
+        let temp_dir = tempdir().expect("Failed to create temp dir");
+        let sql_file = temp_dir.path().join("V1__add_index.sql");
+        fs::write(&sql_file, "CREATE INDEX idx ON users(email);\n").unwrap();
+
+        let adapter = RefineryAdapter;
+        let meta = adapter.extract_migration_metadata(Utf8Path::from_path(&sql_file).unwrap());
+        assert!(meta.run_in_transaction);
+    }
+
+    #[test]
+    fn test_extract_metadata_with_no_transaction_header() {
+        let temp_dir = tempdir().expect("Failed to create temp dir");
+        let sql_file = temp_dir.path().join("V1__add_index.sql");
+        fs::write(
+            &sql_file,
+            "-- refinery:noTransaction\nCREATE INDEX CONCURRENTLY idx ON users(email);\n",
+        )
+        .unwrap();
+
+        let adapter = RefineryAdapter;
+        let meta = adapter.extract_migration_metadata(Utf8Path::from_path(&sql_file).unwrap());
+        assert!(!meta.run_in_transaction);
+    }
+
+    #[test]
+    fn test_extract_metadata_directive_on_any_line() {
+        let temp_dir = tempdir().expect("Failed to create temp dir");
+        let sql_file = temp_dir.path().join("V1__add_index.sql");
+        fs::write(
+            &sql_file,
+            "CREATE INDEX CONCURRENTLY idx ON users(email);\n-- refinery:noTransaction\n",
+        )
+        .unwrap();
+
+        let adapter = RefineryAdapter;
+        let meta = adapter.extract_migration_metadata(Utf8Path::from_path(&sql_file).unwrap());
+        assert!(!meta.run_in_transaction);
+    }
+
+    #[test]
+    fn test_extract_metadata_header_is_case_sensitive() {
+        let temp_dir = tempdir().expect("Failed to create temp dir");
+        let sql_file = temp_dir.path().join("V1__add_index.sql");
+        fs::write(&sql_file, "-- refinery:notransaction\nSELECT 1;\n").unwrap();
+
+        let adapter = RefineryAdapter;
+        let meta = adapter.extract_migration_metadata(Utf8Path::from_path(&sql_file).unwrap());
+        assert!(meta.run_in_transaction);
+    }
+
+    #[test]
+    fn test_extract_sql_from_rust_migration() {
+        let source = r#"use barrel::{Migration, backend::Pg, types};
+
+pub fn migration() -> String {
+    let sql = "CREATE INDEX idx_users_email ON users(email);";
+    let mut m = Migration::new();
+    m.create_table("users", |t| t.add_column("id", types::primary()));
+    format!("{sql}{}", m.make::<Pg>())
+}
+"#;
+        let sql = extract_sql(source);
+        assert_eq!(
+            sql.lines().nth(3),
+            Some("               CREATE INDEX idx_users_email ON users(email);")
+        );
+        assert_eq!(sql.matches("users").count(), 1, "barrel names are not SQL");
+        assert!(!sql.contains("{sql}"), "format strings are not SQL");
+    }
+
+    #[test]
+    fn test_rust_migration_directive_in_returned_sql() {
+        let temp_dir = tempdir().expect("Failed to create temp dir");
+        let rs_file = temp_dir.path().join("V2__add_index.rs");
+        fs::write(
+            &rs_file,
+            "pub fn migration() -> String {\n    \
+             \"-- refinery:noTransaction\\nCREATE INDEX CONCURRENTLY idx ON users(email);\".into()\n}\n",
+        )
+        .unwrap();
+        let path = Utf8Path::from_path(&rs_file).unwrap();
+
+        let adapter = RefineryAdapter;
+        let meta = adapter.extract_migration_metadata(path);
+        assert!(!meta.run_in_transaction);
+        assert_eq!(meta.no_transaction_hint, RUST_NO_TRANSACTION_HINT);
+        assert!(matches!(
+            adapter.transaction_opt_out(path),
+            TransactionOptOut::Unsupported
+        ));
+
+        let sql = extract_sql(&fs::read_to_string(path).unwrap());
+        assert!(
+            sql.contains("CREATE INDEX CONCURRENTLY idx ON users(email);"),
+            "the directive comment must not swallow the statement: {sql:?}"
+        );
+    }
+
+    #[test]
+    fn test_rust_migration_directive_outside_migration_is_ignored() {
+        let temp_dir = tempdir().expect("Failed to create temp dir");
+        let rs_file = temp_dir.path().join("V2__add_index.rs");
+        fs::write(
+            &rs_file,
+            "const NOTE: &str = \"-- refinery:noTransaction\";\n\
+             pub fn migration() -> String {\n    \"CREATE INDEX CONCURRENTLY idx ON users(email);\".into()\n}\n",
+        )
+        .unwrap();
+
+        let adapter = RefineryAdapter;
+        let meta = adapter.extract_migration_metadata(Utf8Path::from_path(&rs_file).unwrap());
+        assert!(meta.run_in_transaction);
+    }
+
+    #[test]
+    fn test_only_rust_migrations_embed_sql() {
+        let adapter = RefineryAdapter;
+        assert!(adapter.embeds_sql(Utf8Path::new("V4__backfill.rs")));
+        assert!(!adapter.embeds_sql(Utf8Path::new("V1__init.sql")));
+    }
+}
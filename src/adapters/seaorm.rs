@@ -0,0 +1,574 @@
+//! SeaORM migration adapter.
+//!
+//! SeaORM migrations are Rust modules named `m<YYYYMMDD>_<HHMMSS>_<NAME>.rs`
+//! (e.g. `m20220101_000001_create_table.rs`), usually in `migration/src/`.
+//! Schema changes written with the query builder can't be checked, but raw SQL
+//! passed as a string literal to one of these APIs can:
+//!
+//! - `manager.get_connection().execute_unprepared("...")`
+//! - `Statement::from_string(backend, "...")`
+//! - `Statement::from_sql_and_values(backend, "...", values)`
+//!
+//! The adapter extracts those literals and places each one on the line and
+//! column it starts at in the Rust file, so violations point into the source.
+//! Literals inside `fn down` are skipped: SeaORM keeps both directions in one
+//! file, and only the up migration is checked.
+
+use super::{
+    MigrationAdapter, MigrationContext, MigrationFile, Result, TransactionOptOut,
+    collect_and_sort_entries, should_check_migration,
+};
+use camino::Utf8Path;
+use regex::Regex;
+use std::ops::Range;
+use std::sync::LazyLock;
+
+/// Regex for SeaORM migration module names: `m<YYYYMMDD>_<HHMMSS>_<NAME>`.
+static SEAORM_NAME_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^m(\d{8})_(\d{6})_\w+").expect("valid regex pattern"));
+
+/// Regex for `start_after` values: the module timestamp, with or without `m` and `_`.
+static SEAORM_START_AFTER_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^m?\d{8}_?\d{6}$").expect("valid regex pattern"));
+
+/// Functions and methods whose string literal arguments are raw SQL.
+const RAW_SQL_APIS: &[&str] = &["execute_unprepared", "from_string", "from_sql_and_values"];
+
+const NO_TRANSACTION_HINT: &str = "SeaORM runs each Postgres migration in a transaction. \
+     Run this statement outside the migrator, e.g. in a separate deployment step.";
+
+/// SeaORM migration adapter.
+pub struct SeaOrmAdapter;
+
+impl MigrationAdapter for SeaOrmAdapter {
+    fn collect_migration_files(
+        &self,
+        dir: &Utf8Path,
+        start_after: Option<&str>,
+        _check_down: bool,
+    ) -> Result<Vec<MigrationFile>> {
+        // Accept the migration crate itself as well as its `src/` directory
+        let src = dir.join("src");
+        let dir = if src.is_dir() { src.as_path() } else { dir };
+        let start_after = start_after.map(|s| s.trim_start_matches('m'));
+
+        let mut files = Vec::new();
+        for entry in collect_and_sort_entries(dir)? {
+            let Some(path) = Utf8Path::from_path(entry.path()) else {
+                continue;
+            };
+            if !entry.file_type().is_file() || path.extension() != Some("rs") {
+                continue;
+            }
+            let Some(timestamp) = self.parse_timestamp(path.file_name().unwrap_or("")) else {
+                continue;
+            };
+            if should_check_migration(start_after, &timestamp) {
+                files.push(MigrationFile::new(path.to_owned(), timestamp));
+            }
+        }
+
+        Ok(files)
+    }
+
+    fn parse_timestamp(&self, name: &str) -> Option<String> {
+        let cap = SEAORM_NAME_REGEX.captures(name)?;
+        Some(format!("{}{}", &cap[1], &cap[2]))
+    }
+
+    fn validate_timestamp(&self, timestamp: &str) -> Result<()> {
+        if SEAORM_START_AFTER_REGEX.is_match(timestamp) {
+            Ok(())
+        } else {
+            Err(format!(
+                "Invalid SeaORM timestamp format: {timestamp}. Expected: YYYYMMDD_HHMMSS (e.g. m20220101_000001)"
+            )
+            .into())
+        }
+    }
+
+    fn extract_migration_metadata(&self, _file_path: &Utf8Path) -> MigrationContext {
+        MigrationContext {
+            run_in_transaction: true,
+            no_transaction_hint: NO_TRANSACTION_HINT,
+            ..MigrationContext::default()
+        }
+    }
+
+    fn transaction_opt_out(&self, _file_path: &Utf8Path) -> TransactionOptOut {
+        TransactionOptOut::Unsupported
+    }
+
+    fn read_sql(&self, file: &MigrationFile) -> std::io::Result<String> {
+        Ok(extract_sql(&std::fs::read_to_string(&file.path)?))
+    }
+
+    fn embeds_sql(&self, _file_path: &Utf8Path) -> bool {
+        true
+    }
+}
+
+/// Extract the raw SQL literals from a SeaORM migration module.
+pub(crate) fn extract_sql(source: &str) -> String {
+    let tokens = lex(source);
+    let down = fn_body(&tokens, "down");
+    let literals = raw_sql_literals(&tokens).into_iter().filter(|lit| {
+        !down
+            .as_ref()
+            .is_some_and(|d| d.contains(&lit.content.start))
+    });
+    layout_literals(source, literals)
+}
+
+/// Lay out string literals of a Rust file as SQL.
+///
+/// The result has the same line structure as `source`: each literal starts on
+/// its own line and column, everything else is blank. A `;` is added after
+/// literals that don't end with one, so consecutive literals stay separate statements.
+pub(super) fn layout_literals<'a>(
+    source: &str,
+    literals: impl IntoIterator<Item = &'a StrLiteral>,
+) -> String {
+    let mut sql = String::new();
+    let mut line = 0;
+    let mut column = 0;
+    for literal in literals {
+        let before = &source[..literal.content.start];
+        let target_line = before.matches('\n').count();
+        let target_column = before[before.rfind('\n').map_or(0, |i| i + 1)..]
+            .chars()
+            .count();
+
+        if target_line > line {
+            sql.push_str(&"\n".repeat(target_line - line));
+            line = target_line;
+            column = 0;
+        }
+        if target_column > column {
+            sql.push_str(&" ".repeat(target_column - column));
+        } else if column > 0 {
+            sql.push(' ');
+        }
+
+        let text = literal.text(source);
+        sql.push_str(&text);
+        if !text.trim_end().ends_with(';') {
+            sql.push(';');
+        }
+
+        line += text.matches('\n').count();
+        let last_line = sql.rsplit('\n').next().unwrap_or_default();
+        column = last_line.chars().count();
+    }
+    sql
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) enum Token {
+    Ident(String),
+    /// A punctuation character and its byte offset.
+    Punct(char, usize),
+    Str(StrLiteral),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct StrLiteral {
+    /// Byte range of the literal's contents, without quotes and hashes.
+    pub(super) content: Range<usize>,
+    pub(super) raw: bool,
+}
+
+impl StrLiteral {
+    /// The literal's value, with escapes resolved.
+    ///
+    /// Escaped newlines become spaces and line continuations keep their newline,
+    /// so the text spans as many lines as the literal does in the source. A `--`
+    /// comment ended by an escaped newline is blanked, so it doesn't swallow the
+    /// SQL after it.
+    pub(super) fn text(&self, source: &str) -> String {
+        let content = &source[self.content.clone()];
+        if self.raw {
+            return content.to_string();
+        }
+
+        let mut text = SqlText::default();
+        let mut chars = content.chars();
+        while let Some(c) = chars.next() {
+            if c != '\\' {
+                text.push(c);
+                continue;
+            }
+            match chars.next() {
+                Some('n' | 'r') => text.escaped_line_break(),
+                Some('t' | '0') => text.push(' '),
+                Some('\n') => text.push('\n'),
+                Some('x') => {
+                    let hex: String = chars.by_ref().take(2).collect();
+                    if let Some(c) = u8::from_str_radix(&hex, 16).ok().map(char::from) {
+                        text.push(c);
+                    }
+                }
+                Some('u') => {
+                    let code: String = chars
+                        .by_ref()
+                        .skip_while(|c| *c == '{')
+                        .take_while(|c| *c != '}')
+                        .collect();
+                    if let Some(c) = u32::from_str_radix(&code, 16).ok().and_then(char::from_u32) {
+                        text.push(c);
+                    }
+                }
+                Some(other) => text.push(other),
+                None => {}
+            }
+        }
+        text.text
+    }
+}
+
+/// SQL text being built from a literal, tracking quotes and `--` comments.
+#[derive(Default)]
+struct SqlText {
+    text: String,
+    quoted: bool,
+    comment: bool,
+}
+
+impl SqlText {
+    fn push(&mut self, c: char) {
+        if self.comment {
+            self.comment = c != '\n';
+            self.text.push(if c == '\n' { c } else { ' ' });
+            return;
+        }
+        if c == '\'' {
+            self.quoted = !self.quoted;
+        } else if c == '-' && !self.quoted && self.text.ends_with('-') {
+            self.text.pop();
+            self.text.push_str("  ");
+            self.comment = true;
+            return;
+        }
+        self.text.push(c);
+    }
+
+    fn escaped_line_break(&mut self) {
+        self.comment = false;
+        self.text.push(' ');
+    }
+}
+
+/// String literals passed directly (not through a macro) to a raw SQL API.
+fn raw_sql_literals(tokens: &[Token]) -> Vec<&StrLiteral> {
+    let mut literals = Vec::new();
+    for (i, window) in tokens.windows(2).enumerate() {
+        let [Token::Ident(name), Token::Punct('(', _)] = window else {
+            continue;
+        };
+        if !RAW_SQL_APIS.contains(&name.as_str()) {
+            continue;
+        }
+
+        let mut depth = 0;
+        for token in &tokens[i + 1..] {
+            match token {
+                Token::Punct('(' | '[' | '{', _) => depth += 1,
+                Token::Punct(')' | ']' | '}', _) => {
+                    depth -= 1;
+                    if depth == 0 {
+                        break;
+                    }
+                }
+                Token::Str(literal) if depth == 1 => literals.push(literal),
+                _ => {}
+            }
+        }
+    }
+    literals
+}
+
+/// Byte range of the body of `fn <name>`, if the module has one.
+pub(super) fn fn_body(tokens: &[Token], name: &str) -> Option<Range<usize>> {
+    let start = tokens
+        .windows(2)
+        .position(|w| matches!(w, [Token::Ident(a), Token::Ident(b)] if a == "fn" && b == name))?;
+
+    let mut depth = 0;
+    let mut open = None;
+    for token in &tokens[start..] {
+        match token {
+            Token::Punct('{', offset) => {
+                open.get_or_insert(*offset);
+                depth += 1;
+            }
+            Token::Punct('}', offset) => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(open?..*offset);
+                }
+            }
+            _ => {}
+        }
+    }
+    open.map(|open| open..usize::MAX)
+}
+
+/// Minimal Rust lexer: identifiers, punctuation and string literals.
+/// Comments, char literals, lifetimes and numbers are skipped.
+pub(super) fn lex(source: &str) -> Vec<Token> {
+    let bytes = source.as_bytes();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let rest = &source[i..];
+        let c = rest.chars().next().unwrap_or_default();
+
+        if c.is_whitespace() {
+            i += c.len_utf8();
+        } else if rest.starts_with("//") {
+            i += rest.find('\n').unwrap_or(rest.len());
+        } else if rest.starts_with("/*") {
+            i += block_comment_len(rest);
+        } else if let Some((literal, len)) = raw_string(rest, i) {
+            tokens.push(Token::Str(literal));
+            i += len;
+        } else if c == '"' || rest.starts_with("b\"") {
+            let open = if c == '"' { 1 } else { 2 };
+            let len = quoted_len(&rest[open..]);
+            tokens.push(Token::Str(StrLiteral {
+                content: i + open..i + open + len,
+                raw: false,
+            }));
+            i += open + len + 1;
+        } else if c == '\'' {
+            i += char_or_lifetime_len(rest);
+        } else if c.is_alphabetic() || c == '_' {
+            let rest = rest.strip_prefix("r#").unwrap_or(rest);
+            let prefix = source.len() - i - rest.len();
+            let len = rest
+                .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+                .unwrap_or(rest.len());
+            tokens.push(Token::Ident(rest[..len].to_string()));
+            i += prefix + len;
+        } else if c.is_ascii_digit() {
+            i += rest
+                .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+                .unwrap_or(rest.len());
+        } else {
+            tokens.push(Token::Punct(c, i));
+            i += c.len_utf8();
+        }
+    }
+    tokens
+}
+
+/// Length of a (possibly nested) block comment at the start of `rest`.
+fn block_comment_len(rest: &str) -> usize {
+    let mut depth = 0;
+    let mut i = 0;
+    while i < rest.len() {
+        if rest[i..].starts_with("/*") {
+            depth += 1;
+            i += 2;
+        } else if rest[i..].starts_with("*/") {
+            depth -= 1;
+            i += 2;
+            if depth == 0 {
+                return i;
+            }
+        } else {
+            i += rest[i..].chars().next().map_or(1, char::len_utf8);
+        }
+    }
+    rest.len()
+}
+
+/// A raw string (`r"..."`, `r#"..."#`, `br"..."`) at the start of `rest`, and its length.
+fn raw_string(rest: &str, offset: usize) -> Option<(StrLiteral, usize)> {
+    let after_prefix = rest.strip_prefix("br").or_else(|| rest.strip_prefix('r'))?;
+    let hashes = after_prefix.len() - after_prefix.trim_start_matches('#').len();
+    let body = after_prefix[hashes..].strip_prefix('"')?;
+    let closing = format!("\"{}", "#".repeat(hashes));
+    let len = body.find(&closing).unwrap_or(body.len());
+
+    let content_start = rest.len() - body.len();
+    Some((
+        StrLiteral {
+            content: offset + content_start..offset + content_start + len,
+            raw: true,
+        },
+        (content_start + len + closing.len()).min(rest.len()),
+    ))
+}
+
+/// Length of a quoted string's contents, up to the closing unescaped `"`.
+fn quoted_len(body: &str) -> usize {
+    let mut escaped = false;
+    for (i, c) in body.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '"' => return i,
+            _ => {}
+        }
+    }
+    body.len()
+}
+
+/// Length of a char literal (`'a'`, `'\n'`) or lifetime (`'a`) at the start of `rest`.
+fn char_or_lifetime_len(rest: &str) -> usize {
+    let mut chars = rest.char_indices().skip(1);
+    match chars.next() {
+        Some((_, '\\')) => rest[2..].find('\'').map_or(rest.len(), |i| i + 3),
+        Some((_, c)) => {
+            let after = 1 + c.len_utf8();
+            if rest[after..].starts_with('\'') {
+                after + 1
+            } else {
+                // Lifetime: the quote and the identifier that follows
+                after
+                    + rest[after..]
+                        .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+                        .unwrap_or(rest.len() - after)
+            }
+        }
+        None => 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    const MIGRATION: &str = r##"use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        // execute_unprepared("SELECT 'commented out'")
+        db.execute_unprepared("CREATE INDEX idx_users_email ON users(email)")
+            .await?;
+        db.execute_unprepared(
+            r#"
+            ALTER TABLE users
+                ADD COLUMN admin BOOLEAN;
+            "#,
+        )
+        .await?;
+        db.execute_unprepared(&format!("SELECT {}", 1)).await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute_unprepared("DROP INDEX idx_users_email")
+            .await?;
+        Ok(())
+    }
+}
+"##;
+
+    #[test]
+    fn test_parse_timestamp() {
+        let adapter = SeaOrmAdapter;
+        assert_eq!(
+            adapter.parse_timestamp("m20220101_000001_create_table.rs"),
+            Some("20220101000001".to_string())
+        );
+        assert_eq!(adapter.parse_timestamp("lib.rs"), None);
+        assert_eq!(adapter.parse_timestamp("main.rs"), None);
+        assert_eq!(adapter.parse_timestamp("m2022_create.rs"), None);
+    }
+
+    #[test]
+    fn test_validate_timestamp() {
+        let adapter = SeaOrmAdapter;
+        assert!(adapter.validate_timestamp("m20220101_000001").is_ok());
+        assert!(adapter.validate_timestamp("20220101_000001").is_ok());
+        assert!(adapter.validate_timestamp("20220101000001").is_ok());
+        assert!(adapter.validate_timestamp("42").is_err());
+        assert!(adapter.validate_timestamp("").is_err());
+    }
+
+    #[test]
+    fn test_collect_finds_modules_in_src_and_filters_start_after() {
+        let temp_dir = tempdir().expect("Failed to create temp dir");
+        let dir = Utf8Path::from_path(temp_dir.path()).unwrap();
+        fs::create_dir(dir.join("src")).unwrap();
+        for name in [
+            "lib.rs",
+            "main.rs",
+            "m20220101_000001_create_table.rs",
+            "m20220201_000001_add_index.rs",
+        ] {
+            fs::write(dir.join("src").join(name), "").unwrap();
+        }
+
+        let adapter = SeaOrmAdapter;
+        let files = adapter
+            .collect_migration_files(dir, Some("m20220101_000001"), false)
+            .unwrap();
+        assert_eq!(files.len(), 1);
+        assert!(files[0].path.ends_with("src/m20220201_000001_add_index.rs"));
+        assert_eq!(files[0].timestamp, "20220201000001");
+    }
+
+    #[test]
+    fn test_extract_sql_keeps_source_lines_and_columns() {
+        let sql = extract_sql(MIGRATION);
+        let lines: Vec<&str> = sql.lines().collect();
+
+        assert_eq!(
+            lines[10],
+            "                               CREATE INDEX idx_users_email ON users(email);"
+        );
+        assert_eq!(lines[14].trim(), "ALTER TABLE users");
+        assert_eq!(lines[15].trim(), "ADD COLUMN admin BOOLEAN;");
+        assert!(!sql.contains("commented out"));
+        assert!(!sql.contains("SELECT"), "format! strings are not raw SQL");
+        assert!(!sql.contains("DROP INDEX"), "fn down is not checked");
+    }
+
+    #[test]
+    fn test_extract_sql_statement_apis() {
+        let source = "let stmt = Statement::from_string(backend, \"SELECT 1\");\n\
+                      let stmt = Statement::from_sql_and_values(backend, \"SELECT $1\", [1.into()]);\n";
+        assert_eq!(
+            extract_sql(source),
+            format!("{}SELECT 1;\n{}SELECT $1;", " ".repeat(44), " ".repeat(52))
+        );
+    }
+
+    #[test]
+    fn test_extract_sql_resolves_escapes() {
+        let source =
+            "db.execute_unprepared(\"COMMENT ON TABLE t IS \\\"it\\'s\\\";\\n\\\n    SELECT 1\")";
+        assert_eq!(
+            extract_sql(source),
+            "                       COMMENT ON TABLE t IS \"it's\"; \n    SELECT 1;"
+        );
+    }
+
+    #[test]
+    fn test_extract_sql_blanks_comments_ended_by_escaped_newlines() {
+        let source = "db.execute_unprepared(\"-- add index\\nSELECT '--';\")";
+        assert_eq!(
+            extract_sql(source),
+            format!("{}SELECT '--';", " ".repeat(36))
+        );
+    }
+
+    #[test]
+    fn test_lex_skips_char_literals_and_lifetimes() {
+        let source = "fn f<'a>(x: &'a str) -> char { '\"' }\ndb.execute_unprepared(\"SELECT 1\")";
+        assert_eq!(extract_sql(source).trim(), "SELECT 1;");
+    }
+}
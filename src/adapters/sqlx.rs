@@ -6,8 +6,8 @@
 //!
 
 use super::{
-    MigrationAdapter, MigrationContext, MigrationFile, Result, collect_and_sort_entries,
-    should_check_migration,
+    MigrationAdapter, MigrationContext, MigrationFile, Result, TransactionOptOut,
+    collect_and_sort_entries, should_check_migration,
 };
 use camino::Utf8Path;
 use regex::Regex;
@@ -93,6 +93,10 @@ impl MigrationAdapter for SqlxAdapter {
             ..MigrationContext::default()
         }
     }
+
+    fn transaction_opt_out(&self, _file_path: &Utf8Path) -> TransactionOptOut {
+        TransactionOptOut::Directive("-- no-transaction")
+    }
 }
 
 impl SqlxAdapter {
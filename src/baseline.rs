@@ -0,0 +1,222 @@
+//! Baseline of known violations.
+//!
+//! `diesel-guard baseline` records every current violation in
+//! `diesel-guard-baseline.json`, keyed by file, check name and a hash of the
+//! statement that produced it. `diesel-guard check` then reports only
+//! violations that are not in the baseline, so new checks can be adopted
+//! without touching old migrations.
+//!
+//! The statement hash ignores whitespace, comments and keyword case, so
+//! reformatting a baselined statement keeps it baselined. Changing what the
+//! statement does (a table name, a column type) makes it a new violation.
+
+use crate::error::{DieselGuardError, Result};
+use crate::git;
+use camino::{Utf8Component, Utf8Path, Utf8PathBuf};
+use pg_query::protobuf::{KeywordKind, Token};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+
+/// Baseline file read by `check` and written by `baseline` by default.
+pub const DEFAULT_BASELINE_PATH: &str = "diesel-guard-baseline.json";
+
+const BASELINE_VERSION: u32 = 1;
+
+/// One grandfathered violation.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct BaselineEntry {
+    pub file: String,
+    pub check_name: String,
+    pub statement_hash: String,
+}
+
+/// Contents of a baseline file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Baseline {
+    version: u32,
+    pub entries: Vec<BaselineEntry>,
+}
+
+impl Baseline {
+    pub fn new(mut entries: Vec<BaselineEntry>) -> Self {
+        entries.sort();
+        Self {
+            version: BASELINE_VERSION,
+            entries,
+        }
+    }
+
+    /// Read a baseline file.
+    pub fn load(path: &Utf8Path) -> Result<Self> {
+        let contents = fs::read_to_string(path)?;
+        let invalid = |msg: String| DieselGuardError::BaselineError {
+            path: path.to_string(),
+            msg,
+        };
+        let baseline: Self = serde_json::from_str(&contents).map_err(|e| invalid(e.to_string()))?;
+        if baseline.version != BASELINE_VERSION {
+            return Err(invalid(format!(
+                "unsupported version {} (expected {BASELINE_VERSION})",
+                baseline.version
+            )));
+        }
+        Ok(baseline)
+    }
+
+    /// Write the baseline as pretty-printed JSON, one entry per object.
+    pub fn write(&self, path: &Utf8Path) -> Result<()> {
+        let mut json = serde_json::to_string_pretty(self).unwrap_or_default();
+        json.push('\n');
+        fs::write(path, json)?;
+        Ok(())
+    }
+
+    /// Entries recorded for one file.
+    pub(crate) fn entries_for(&self, file: &str) -> Vec<&BaselineEntry> {
+        self.entries.iter().filter(|e| e.file == file).collect()
+    }
+}
+
+/// Key a migration path the way baseline and lock entries store it: relative to
+/// `base` (the directory of the config file, empty for the current directory)
+/// when possible, with `/` separators and no `./`.
+///
+/// Keys stay the same whichever directory diesel-guard runs from.
+pub fn file_key(path: &Utf8Path, base: &Utf8Path) -> String {
+    let base = if base.as_str().is_empty() {
+        Utf8Path::new(".")
+    } else {
+        base
+    };
+    let relative = match (camino::absolute_utf8(base), camino::absolute_utf8(path)) {
+        (Ok(base), Ok(absolute)) => absolute
+            .strip_prefix(&base)
+            .ok()
+            .map(Utf8Path::to_owned)
+            // `..` in either path only disappears once both are resolved
+            .or_else(|| {
+                git::canonical(&absolute)
+                    .strip_prefix(git::canonical(&base))
+                    .ok()
+                    .map(Utf8Path::to_owned)
+            }),
+        _ => None,
+    }
+    .unwrap_or_else(|| path.to_owned());
+    relative
+        .components()
+        .filter(|c| *c != Utf8Component::CurDir)
+        .collect::<Utf8PathBuf>()
+        .as_str()
+        .replace('\\', "/")
+}
+
+/// Hash of a statement's tokens, ignoring whitespace, comments and keyword case.
+pub fn statement_hash(stmt_sql: &str) -> String {
+    let mut hasher = Sha256::new();
+    if let Ok(scan) = pg_query::scan(stmt_sql) {
+        for token in scan
+            .tokens
+            .iter()
+            .filter(|t| t.token != Token::SqlComment as i32 && t.token != Token::CComment as i32)
+        {
+            let start = usize::try_from(token.start).unwrap_or(0);
+            let end = usize::try_from(token.end).unwrap_or(0);
+            let text = stmt_sql.get(start..end).unwrap_or_default();
+            if token.keyword_kind == KeywordKind::NoKeyword as i32 {
+                hasher.update(text.as_bytes());
+            } else {
+                hasher.update(text.to_ascii_lowercase().as_bytes());
+            }
+            hasher.update([0]);
+        }
+    } else {
+        hasher.update(stmt_sql.trim().as_bytes());
+    }
+    // 64 bits is plenty to tell statements in one file apart
+    format!("{:x}", hasher.finalize())[..16].to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_statement_hash_ignores_formatting() {
+        assert_eq!(
+            statement_hash("CREATE INDEX idx ON users(email);"),
+            statement_hash("create   index idx\n  ON users (email) -- note\n;")
+        );
+    }
+
+    #[test]
+    fn test_statement_hash_changes_with_identifiers() {
+        assert_ne!(
+            statement_hash("CREATE INDEX idx ON users(email);"),
+            statement_hash("CREATE INDEX idx ON users(name);")
+        );
+        // Quoted identifiers and literals keep their case
+        assert_ne!(
+            statement_hash("ALTER TABLE \"Users\" ADD COLUMN a INT DEFAULT 'X';"),
+            statement_hash("ALTER TABLE \"users\" ADD COLUMN a INT DEFAULT 'x';")
+        );
+    }
+
+    #[test]
+    fn test_file_key_normalizes_path() {
+        let here = Utf8Path::new("");
+        assert_eq!(
+            file_key(Utf8Path::new("./migrations/001/up.sql"), here),
+            "migrations/001/up.sql"
+        );
+        let cwd = Utf8PathBuf::from_path_buf(std::env::current_dir().unwrap()).unwrap();
+        assert_eq!(
+            file_key(&cwd.join("migrations/up.sql"), here),
+            "migrations/up.sql"
+        );
+    }
+
+    #[test]
+    fn test_file_key_is_relative_to_base() {
+        let dir = tempdir().unwrap();
+        let root = Utf8PathBuf::from_path_buf(dir.path().to_path_buf()).unwrap();
+        fs::create_dir_all(root.join("api/src")).unwrap();
+        fs::create_dir_all(root.join("migrations/001")).unwrap();
+        fs::write(root.join("migrations/001/up.sql"), "SELECT 1;").unwrap();
+
+        // As seen from a subcrate, with the config in the parent directory
+        let path = root.join("api/src/../../migrations/001/up.sql");
+        assert_eq!(
+            file_key(&path, &root.join("api/src/../..")),
+            "migrations/001/up.sql"
+        );
+    }
+
+    #[test]
+    fn test_write_and_load_round_trip() {
+        let dir = tempdir().unwrap();
+        let path = Utf8PathBuf::from_path_buf(dir.path().join("baseline.json")).unwrap();
+        let entry = |file: &str| BaselineEntry {
+            file: file.to_string(),
+            check_name: "AddIndexCheck".to_string(),
+            statement_hash: "0123456789abcdef".to_string(),
+        };
+        let baseline = Baseline::new(vec![entry("b.sql"), entry("a.sql")]);
+        baseline.write(&path).unwrap();
+
+        let loaded = Baseline::load(&path).unwrap();
+        assert_eq!(loaded, baseline);
+        assert_eq!(loaded.entries[0].file, "a.sql");
+    }
+
+    #[test]
+    fn test_load_rejects_unknown_version() {
+        let dir = tempdir().unwrap();
+        let path = Utf8PathBuf::from_path_buf(dir.path().join("baseline.json")).unwrap();
+        fs::write(&path, r#"{"version": 2, "entries": []}"#).unwrap();
+        let err = Baseline::load(&path).unwrap_err();
+        assert!(err.to_string().contains("unsupported version 2"));
+    }
+}
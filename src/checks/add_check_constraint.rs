@@ -1,5 +1,5 @@
 use crate::checks::pg_helpers::{alter_table_cmds, cmd_def_as_constraint, constraint_display_name};
-use crate::checks::{Check, CheckDoc, impl_check_doc};
+use crate::checks::{Check, CheckDoc, impl_check_doc, lock_violation};
 use crate::{Config, MigrationContext, Violation};
 use pg_query::NodeEnum;
 use pg_query::protobuf::ConstrType;
@@ -8,10 +8,15 @@ pub struct AddCheckConstraintCheck;
 impl_check_doc!(AddCheckConstraintCheck, "add-check-constraint");
 
 impl Check for AddCheckConstraintCheck {
-    fn check(&self, node: &NodeEnum, _config: &Config, _ctx: &MigrationContext) -> Vec<Violation> {
+    fn check(&self, node: &NodeEnum, config: &Config, _ctx: &MigrationContext) -> Vec<Violation> {
         let Some((table_name, cmds)) = alter_table_cmds(node) else {
             return vec![];
         };
+
+        let Some(severity) = config.lock_severity(&table_name) else {
+            return vec![];
+        };
+
         cmds.iter().filter_map(|cmd| {
             let constraint = cmd_def_as_constraint(cmd)?;
             if constraint.contype != ConstrType::ConstrCheck as i32 {
@@ -43,7 +48,9 @@ Benefits:
 - Safe for production deployments on large tables
 ",
                 )))
-        }).collect()
+        })
+        .map(|v| lock_violation(v, &table_name, severity, config))
+        .collect()
     }
 }
 
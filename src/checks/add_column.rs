@@ -15,7 +15,7 @@ use crate::checks::pg_helpers::{
     ConstrType, NodeEnum, alter_table_cmds, cmd_def_as_column_def, column_has_constraint,
     column_type_name,
 };
-use crate::checks::{Check, CheckDoc, Config, MigrationContext, impl_check_doc};
+use crate::checks::{Check, CheckDoc, Config, MigrationContext, impl_check_doc, lock_violation};
 use crate::violation::Violation;
 use pg_query::protobuf::ColumnDef;
 
@@ -28,6 +28,10 @@ impl Check for AddColumnCheck {
             return vec![];
         };
 
+        let Some(severity) = config.lock_severity(&table_name) else {
+            return vec![];
+        };
+
         cmds.iter()
             .filter_map(|cmd| {
                 let col = cmd_def_as_column_def(cmd)?;
@@ -64,6 +68,7 @@ Note: For Postgres 11+, this is safe if the default is a constant value."
                     ),
                 ))
             })
+            .map(|v| lock_violation(v, &table_name, severity, config))
             .collect()
     }
 }
@@ -161,6 +166,25 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_small_tables_are_skipped() {
+        let config = Config {
+            small_tables: vec!["countries".to_string()],
+            ..pg_config(10)
+        };
+        assert_allows_with_config!(
+            AddColumnCheck,
+            "ALTER TABLE countries ADD COLUMN active BOOLEAN DEFAULT TRUE;",
+            &config
+        );
+        assert_detects_violation_with_config!(
+            AddColumnCheck,
+            "ALTER TABLE users ADD COLUMN admin BOOLEAN DEFAULT FALSE;",
+            "ADD COLUMN with DEFAULT",
+            &config
+        );
+    }
+
     #[test]
     fn test_detects_constant_default_on_pg10() {
         assert_detects_violation_with_config!(
@@ -1,18 +1,22 @@
 use crate::checks::pg_helpers::{
     ConstrType, NodeEnum, alter_table_cmds, cmd_def_as_constraint, constraint_display_name,
 };
-use crate::checks::{Check, CheckDoc, Config, MigrationContext, impl_check_doc};
+use crate::checks::{Check, CheckDoc, Config, MigrationContext, impl_check_doc, lock_violation};
 use crate::violation::Violation;
 
 pub struct AddExcludeConstraintCheck;
 impl_check_doc!(AddExcludeConstraintCheck, "add-exclude-constraint");
 
 impl Check for AddExcludeConstraintCheck {
-    fn check(&self, node: &NodeEnum, _config: &Config, _ctx: &MigrationContext) -> Vec<Violation> {
+    fn check(&self, node: &NodeEnum, config: &Config, _ctx: &MigrationContext) -> Vec<Violation> {
         let Some((table_name, cmds)) = alter_table_cmds(node) else {
             return vec![];
         };
 
+        let Some(severity) = config.lock_severity(&table_name) else {
+            return vec![];
+        };
+
         cmds.iter()
             .filter_map(|cmd| {
                 let c = cmd_def_as_constraint(cmd)?;
@@ -42,6 +46,7 @@ Options:
                     ),
                 ))
             })
+            .map(|v| lock_violation(v, &table_name, severity, config))
             .collect()
     }
 }
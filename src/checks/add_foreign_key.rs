@@ -2,7 +2,7 @@ use crate::checks::pg_helpers::{
     alter_table_cmds, cmd_def_as_constraint, constraint_display_name, fk_cols_constraint,
     ref_columns_constraint, ref_table_constraint,
 };
-use crate::checks::{Check, CheckDoc, impl_check_doc};
+use crate::checks::{Check, CheckDoc, impl_check_doc, lock_violation};
 use crate::{Config, MigrationContext, Violation};
 use pg_query::NodeEnum;
 use pg_query::protobuf::ConstrType;
@@ -11,10 +11,20 @@ pub struct AddForeignKeyCheck;
 impl_check_doc!(AddForeignKeyCheck, "add-foreign-key");
 
 impl Check for AddForeignKeyCheck {
-    fn check(&self, node: &NodeEnum, _config: &Config, _ctx: &MigrationContext) -> Vec<Violation> {
+    fn check(&self, node: &NodeEnum, config: &Config, ctx: &MigrationContext) -> Vec<Violation> {
         let Some((table_name, cmds)) = alter_table_cmds(node) else {
             return vec![];
         };
+
+        // A table created earlier in this migration is empty and not yet visible to others
+        if ctx.is_new_table(&table_name) {
+            return vec![];
+        }
+
+        let Some(severity) = config.lock_severity(&table_name) else {
+            return vec![];
+        };
+
         cmds.iter().filter_map(|cmd| {
             let constraint = cmd_def_as_constraint(cmd)?;
             if constraint.contype != ConstrType::ConstrForeign as i32 {
@@ -53,7 +63,9 @@ Benefits:
 - Safe for production deployments on large tables
 ",
                 )))
-        }).collect()
+        })
+        .map(|v| lock_violation(v, &table_name, severity, config))
+        .collect()
     }
 }
 
@@ -61,7 +73,10 @@ Benefits:
 mod tests {
     use super::*;
     use crate::checks::test_utils::parse_sql;
-    use crate::{assert_allows, assert_detects_violation, assert_detects_violation_containing};
+    use crate::{
+        assert_allows, assert_allows_with_context, assert_detects_violation,
+        assert_detects_violation_containing,
+    };
 
     #[test]
     fn test_detects_add_foreign_key() {
@@ -152,4 +167,16 @@ mod tests {
             "Expected VALIDATE CONSTRAINT in safe_alternative"
         );
     }
+
+    #[test]
+    fn test_allows_table_created_in_same_migration() {
+        assert_allows_with_context!(
+            AddForeignKeyCheck,
+            "ALTER TABLE posts ADD FOREIGN KEY (user_id) REFERENCES users(id);",
+            MigrationContext {
+                created_tables: vec!["posts".to_string()],
+                ..MigrationContext::default()
+            }
+        );
+    }
 }
@@ -19,18 +19,22 @@
 use crate::checks::pg_helpers::{
     NodeEnum, alter_table_cmds, cmd_def_as_column_def, column_type_name, is_identity_pattern,
 };
-use crate::checks::{Check, CheckDoc, Config, MigrationContext, impl_check_doc};
+use crate::checks::{Check, CheckDoc, Config, MigrationContext, impl_check_doc, lock_violation};
 use crate::violation::Violation;
 
 pub struct AddIdentityColumnCheck;
 impl_check_doc!(AddIdentityColumnCheck, "add-identity-column");
 
 impl Check for AddIdentityColumnCheck {
-    fn check(&self, node: &NodeEnum, _config: &Config, _ctx: &MigrationContext) -> Vec<Violation> {
+    fn check(&self, node: &NodeEnum, config: &Config, _ctx: &MigrationContext) -> Vec<Violation> {
         let Some((table_name, cmds)) = alter_table_cmds(node) else {
             return vec![];
         };
 
+        let Some(severity) = config.lock_severity(&table_name) else {
+            return vec![];
+        };
+
         cmds.iter()
             .filter_map(|cmd| {
                 let col = cmd_def_as_column_def(cmd)?;
@@ -74,6 +78,7 @@ impl Check for AddIdentityColumnCheck {
                     ),
                 ))
             })
+            .map(|v| lock_violation(v, &table_name, severity, config))
             .collect()
     }
 }
@@ -11,14 +11,16 @@
 //! though it takes longer and cannot be run inside a transaction block.
 
 use crate::checks::pg_helpers::{NodeEnum, concurrent_safe_alternative, range_var_name};
-use crate::checks::{Check, CheckDoc, Config, MigrationContext, impl_check_doc, unique_prefix};
+use crate::checks::{
+    Check, CheckDoc, Config, MigrationContext, impl_check_doc, lock_violation, unique_prefix,
+};
 use crate::violation::Violation;
 
 pub struct AddIndexCheck;
 impl_check_doc!(AddIndexCheck, "add-index");
 
 impl Check for AddIndexCheck {
-    fn check(&self, node: &NodeEnum, _config: &Config, ctx: &MigrationContext) -> Vec<Violation> {
+    fn check(&self, node: &NodeEnum, config: &Config, ctx: &MigrationContext) -> Vec<Violation> {
         let NodeEnum::IndexStmt(index_stmt) = node else {
             return vec![];
         };
@@ -36,7 +38,15 @@ impl Check for AddIndexCheck {
         let unique_str = unique_prefix(index_stmt.unique);
 
         if !index_stmt.concurrent {
-            // CREATE INDEX without CONCURRENTLY — always a violation
+            // Indexing a table created earlier in this migration blocks nobody
+            if ctx.is_new_table(&table_name) {
+                return vec![];
+            }
+            let Some(severity) = config.lock_severity(&table_name) else {
+                return vec![];
+            };
+
+            // CREATE INDEX without CONCURRENTLY on an existing table
             let suggestion = format!(
                 r#"Use CONCURRENTLY to build the index without blocking writes:
    CREATE {unique_str}INDEX CONCURRENTLY {index_name} ON {table_name};
@@ -50,14 +60,15 @@ Considerations:
 
             let safe_alternative = concurrent_safe_alternative(suggestion, ctx);
 
-            return vec![Violation::new(
+            let violation = Violation::new(
                 "ADD INDEX without CONCURRENTLY",
                 format!(
                     "Creating {unique_str}index '{index_name}' on table '{table_name}' without CONCURRENTLY acquires a SHARE lock, blocking writes \
                     (INSERT, UPDATE, DELETE). Duration depends on table size. Reads are still allowed."
                 ),
                 safe_alternative,
-            )];
+            );
+            return vec![lock_violation(violation, &table_name, severity, config)];
         }
 
         // CREATE INDEX CONCURRENTLY — safe only if migration runs outside a transaction
@@ -257,4 +268,51 @@ mod tests {
             "<unnamed>"
         );
     }
+
+    #[test]
+    fn test_allows_create_index_on_table_created_in_same_migration() {
+        assert_allows_with_context!(
+            AddIndexCheck,
+            "CREATE INDEX idx_users_email ON users(email);",
+            MigrationContext {
+                created_tables: vec!["users".to_string()],
+                ..MigrationContext::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_detects_concurrently_in_transaction_on_table_created_in_same_migration() {
+        assert_detects_violation_with_context!(
+            AddIndexCheck,
+            "CREATE INDEX CONCURRENTLY idx_users_email ON users(email);",
+            "CREATE INDEX CONCURRENTLY inside a transaction",
+            MigrationContext {
+                created_tables: vec!["users".to_string()],
+                ..MigrationContext::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_small_table_violation_is_a_warning_in_warn_mode() {
+        use crate::config::SmallTablesMode;
+        use crate::violation::Severity;
+
+        let config = Config {
+            small_tables: vec!["countries".to_string()],
+            small_tables_mode: SmallTablesMode::Warn,
+            ..Default::default()
+        };
+        let check =
+            |sql| AddIndexCheck.check(&parse_sql(sql), &config, &MigrationContext::default());
+
+        let violations = check("CREATE INDEX idx_countries_name ON countries(name);");
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].severity, Severity::Warning);
+
+        let violations = check("CREATE INDEX idx_orders_user_id ON orders(user_id);");
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].severity, Severity::Error);
+    }
 }
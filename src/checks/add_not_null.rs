@@ -11,18 +11,27 @@
 //! separately, then add the NOT NULL constraint.
 
 use crate::checks::pg_helpers::{AlterTableType, NodeEnum, alter_table_cmds};
-use crate::checks::{Check, CheckDoc, Config, MigrationContext, impl_check_doc};
+use crate::checks::{Check, CheckDoc, Config, MigrationContext, impl_check_doc, lock_violation};
 use crate::violation::Violation;
 
 pub struct AddNotNullCheck;
 impl_check_doc!(AddNotNullCheck, "set-not-null");
 
 impl Check for AddNotNullCheck {
-    fn check(&self, node: &NodeEnum, _config: &Config, _ctx: &MigrationContext) -> Vec<Violation> {
+    fn check(&self, node: &NodeEnum, config: &Config, ctx: &MigrationContext) -> Vec<Violation> {
         let Some((table_name, cmds)) = alter_table_cmds(node) else {
             return vec![];
         };
 
+        // A table created earlier in this migration is empty and not yet visible to others
+        if ctx.is_new_table(&table_name) {
+            return vec![];
+        }
+
+        let Some(severity) = config.lock_severity(&table_name) else {
+            return vec![];
+        };
+
         cmds.iter()
             .filter_map(|cmd| {
                 if cmd.subtype != AlterTableType::AtSetNotNull as i32 {
@@ -56,6 +65,7 @@ Note: The VALIDATE step allows concurrent reads and writes, only blocking other
                     ),
                 ))
             })
+            .map(|v| lock_violation(v, &table_name, severity, config))
             .collect()
     }
 }
@@ -63,7 +73,10 @@ Note: The VALIDATE step allows concurrent reads and writes, only blocking other
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{assert_allows, assert_detects_violation};
+    use crate::{
+        assert_allows, assert_allows_with_config, assert_allows_with_context,
+        assert_detects_violation, assert_detects_violation_with_config,
+    };
 
     #[test]
     fn test_detects_add_not_null() {
@@ -105,4 +118,35 @@ mod tests {
             "CREATE TABLE users (id SERIAL PRIMARY KEY);"
         );
     }
+
+    #[test]
+    fn test_allows_table_created_in_same_migration() {
+        assert_allows_with_context!(
+            AddNotNullCheck,
+            "ALTER TABLE users ALTER COLUMN email SET NOT NULL;",
+            MigrationContext {
+                created_tables: vec!["users".to_string()],
+                ..MigrationContext::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_small_tables_are_skipped() {
+        let config = Config {
+            small_tables: vec!["countries".to_string()],
+            ..Default::default()
+        };
+        assert_allows_with_config!(
+            AddNotNullCheck,
+            "ALTER TABLE countries ALTER COLUMN code SET NOT NULL;",
+            &config
+        );
+        assert_detects_violation_with_config!(
+            AddNotNullCheck,
+            "ALTER TABLE users ALTER COLUMN email SET NOT NULL;",
+            "ADD NOT NULL constraint",
+            &config
+        );
+    }
 }
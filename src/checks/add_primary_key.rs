@@ -14,18 +14,22 @@
 use crate::checks::pg_helpers::{
     ConstrType, NodeEnum, alter_table_cmds, cmd_def_as_constraint, constraint_columns_str,
 };
-use crate::checks::{Check, CheckDoc, Config, MigrationContext, impl_check_doc};
+use crate::checks::{Check, CheckDoc, Config, MigrationContext, impl_check_doc, lock_violation};
 use crate::violation::Violation;
 
 pub struct AddPrimaryKeyCheck;
 impl_check_doc!(AddPrimaryKeyCheck, "add-primary-key");
 
 impl Check for AddPrimaryKeyCheck {
-    fn check(&self, node: &NodeEnum, _config: &Config, _ctx: &MigrationContext) -> Vec<Violation> {
+    fn check(&self, node: &NodeEnum, config: &Config, _ctx: &MigrationContext) -> Vec<Violation> {
         let Some((table_name, cmds)) = alter_table_cmds(node) else {
             return vec![];
         };
 
+        let Some(severity) = config.lock_severity(&table_name) else {
+            return vec![];
+        };
+
         cmds.iter()
             .filter_map(|cmd| {
                 let c = cmd_def_as_constraint(cmd)?;
@@ -82,6 +86,7 @@ Note: Ensure all columns in the primary key have NOT NULL constraints before cre
                     ),
                 ))
             })
+            .map(|v| lock_violation(v, &table_name, severity, config))
             .collect()
     }
 }
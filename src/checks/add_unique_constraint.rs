@@ -13,18 +13,27 @@ use crate::checks::pg_helpers::{
     ConstrType, NodeEnum, alter_table_cmds, cmd_def_as_constraint, constraint_columns_str,
     constraint_display_name,
 };
-use crate::checks::{Check, CheckDoc, Config, MigrationContext, impl_check_doc};
+use crate::checks::{Check, CheckDoc, Config, MigrationContext, impl_check_doc, lock_violation};
 use crate::violation::Violation;
 
 pub struct AddUniqueConstraintCheck;
 impl_check_doc!(AddUniqueConstraintCheck, "add-unique-constraint");
 
 impl Check for AddUniqueConstraintCheck {
-    fn check(&self, node: &NodeEnum, _config: &Config, _ctx: &MigrationContext) -> Vec<Violation> {
+    fn check(&self, node: &NodeEnum, config: &Config, ctx: &MigrationContext) -> Vec<Violation> {
         let Some((table_name, cmds)) = alter_table_cmds(node) else {
             return vec![];
         };
 
+        // A table created earlier in this migration is empty and not yet visible to others
+        if ctx.is_new_table(&table_name) {
+            return vec![];
+        }
+
+        let Some(severity) = config.lock_severity(&table_name) else {
+            return vec![];
+        };
+
         cmds.iter()
             .filter_map(|cmd| {
                 let c = cmd_def_as_constraint(cmd)?;
@@ -85,6 +94,7 @@ Considerations:
                     ),
                 ))
             })
+            .map(|v| lock_violation(v, &table_name, severity, config))
             .collect()
     }
 }
@@ -92,7 +102,7 @@ Considerations:
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{assert_allows, assert_detects_violation};
+    use crate::{assert_allows, assert_allows_with_context, assert_detects_violation};
 
     #[test]
     fn test_detects_add_unique_constraint_named() {
@@ -178,4 +188,16 @@ mod tests {
             "CREATE TABLE users (id SERIAL PRIMARY KEY);"
         );
     }
+
+    #[test]
+    fn test_allows_table_created_in_same_migration() {
+        assert_allows_with_context!(
+            AddUniqueConstraintCheck,
+            "ALTER TABLE public.users ADD CONSTRAINT users_email_key UNIQUE (email);",
+            MigrationContext {
+                created_tables: vec!["users".to_string()],
+                ..MigrationContext::default()
+            }
+        );
+    }
 }
@@ -12,18 +12,22 @@
 use crate::checks::pg_helpers::{
     AlterTableType, NodeEnum, alter_table_cmds, cmd_def_as_column_def, column_type_name,
 };
-use crate::checks::{Check, CheckDoc, Config, MigrationContext, impl_check_doc};
+use crate::checks::{Check, CheckDoc, Config, MigrationContext, impl_check_doc, lock_violation};
 use crate::violation::Violation;
 
 pub struct AlterColumnTypeCheck;
 impl_check_doc!(AlterColumnTypeCheck, "alter-column-type");
 
 impl Check for AlterColumnTypeCheck {
-    fn check(&self, node: &NodeEnum, _config: &Config, _ctx: &MigrationContext) -> Vec<Violation> {
+    fn check(&self, node: &NodeEnum, config: &Config, _ctx: &MigrationContext) -> Vec<Violation> {
         let Some((table_name, cmds)) = alter_table_cmds(node) else {
             return vec![];
         };
 
+        let Some(severity) = config.lock_severity(&table_name) else {
+            return vec![];
+        };
+
         cmds.iter()
             .filter_map(|cmd| {
                 if cmd.subtype != AlterTableType::AtAlterColumnType as i32 {
@@ -68,6 +72,7 @@ Always test on a production-sized dataset to verify the impact."
                     ),
                 ))
             })
+            .map(|v| lock_violation(v, &table_name, severity, config))
             .collect()
     }
 }
@@ -75,7 +80,10 @@ Always test on a production-sized dataset to verify the impact."
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{assert_allows, assert_detects_violation};
+    use crate::{
+        assert_allows, assert_allows_with_config, assert_detects_violation,
+        assert_detects_violation_with_config,
+    };
 
     #[test]
     fn test_detects_alter_column_type() {
@@ -128,4 +136,29 @@ mod tests {
             "CREATE TABLE users (id SERIAL PRIMARY KEY);"
         );
     }
+
+    #[test]
+    fn test_small_tables_are_skipped_unless_listed_as_large() {
+        let config = Config {
+            small_tables: vec!["countries".to_string(), "lookup_*".to_string()],
+            large_tables: vec!["lookup_events".to_string()],
+            ..Default::default()
+        };
+        assert_allows_with_config!(
+            AlterColumnTypeCheck,
+            "ALTER TABLE countries ALTER COLUMN code TYPE TEXT;",
+            &config
+        );
+        assert_allows_with_config!(
+            AlterColumnTypeCheck,
+            "ALTER TABLE lookup_colors ALTER COLUMN hex TYPE TEXT;",
+            &config
+        );
+        assert_detects_violation_with_config!(
+            AlterColumnTypeCheck,
+            "ALTER TABLE lookup_events ALTER COLUMN id TYPE BIGINT;",
+            "ALTER COLUMN TYPE",
+            &config
+        );
+    }
 }
@@ -9,6 +9,9 @@
 //! - Affects DISTINCT, GROUP BY, and joins unexpectedly
 //! - Provides no performance benefit over VARCHAR or TEXT in Postgres
 //!
+//! Tables listed in `allowed_tables`, or in a schema listed in `ignore_schemas`,
+//! under `[checks.CharTypeCheck]` are skipped.
+//!
 //! ## Lock type
 //! None - this is a best practices check, not a locking concern.
 //!
@@ -18,6 +21,7 @@
 //! ## Postgres version specifics
 //! Applies to all Postgres versions.
 
+use crate::checks::options::{ALLOWED_TABLES, CheckOption, IGNORE_SCHEMAS};
 use crate::checks::pg_helpers::{
     ColumnDef, NodeEnum, alter_table_cmds, cmd_def_as_column_def, column_type_name,
     for_each_column_def, is_char_type,
@@ -29,13 +33,19 @@ pub struct CharTypeCheck;
 impl_check_doc!(CharTypeCheck, "char-type");
 
 impl Check for CharTypeCheck {
-    fn check(&self, node: &NodeEnum, _config: &Config, _ctx: &MigrationContext) -> Vec<Violation> {
+    fn options(&self) -> &'static [CheckOption] {
+        &[ALLOWED_TABLES, IGNORE_SCHEMAS]
+    }
+
+    fn check(&self, node: &NodeEnum, config: &Config, _ctx: &MigrationContext) -> Vec<Violation> {
+        let options = config.check_options(self.name());
+
         // Handle CREATE TABLE via for_each_column_def
         if let NodeEnum::CreateStmt(_) = node {
             return for_each_column_def(node)
                 .into_iter()
                 .filter_map(|(table, col)| {
-                    if !is_char_type(&column_type_name(col)) {
+                    if !is_char_type(&column_type_name(col)) || options.exempts_table(&table) {
                         return None;
                     }
                     let length = get_char_length(col);
@@ -49,6 +59,9 @@ impl Check for CharTypeCheck {
             let Some((table_name, cmds)) = alter_table_cmds(node) else {
                 return vec![];
             };
+            if options.exempts_table(&table_name) {
+                return vec![];
+            }
 
             return cmds
                 .iter()
@@ -159,9 +172,11 @@ If this is intentional, use a safety-assured block:
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::checks::test_utils::config_with_options;
     use crate::{
-        assert_allows, assert_detects_n_violations_any_containing, assert_detects_violation,
-        assert_detects_violation_containing,
+        assert_allows, assert_allows_with_config, assert_detects_n_violations_any_containing,
+        assert_detects_violation, assert_detects_violation_containing,
+        assert_detects_violation_with_config,
     };
 
     // === Detection tests ===
@@ -272,4 +287,31 @@ mod tests {
     fn test_ignores_other_statements() {
         assert_allows!(CharTypeCheck, "SELECT * FROM users;");
     }
+
+    #[test]
+    fn test_allowed_tables_and_ignored_schemas() {
+        let config = config_with_options(
+            "CharTypeCheck",
+            r#"
+allowed_tables = ["countries"]
+ignore_schemas = ["legacy"]
+"#,
+        );
+        assert_allows_with_config!(
+            CharTypeCheck,
+            "CREATE TABLE countries (code CHAR(2));",
+            &config
+        );
+        assert_allows_with_config!(
+            CharTypeCheck,
+            "ALTER TABLE legacy.users ADD COLUMN flag CHAR(1);",
+            &config
+        );
+        assert_detects_violation_with_config!(
+            CharTypeCheck,
+            "ALTER TABLE users ADD COLUMN flag CHAR(1);",
+            "ADD COLUMN with CHAR type",
+            &config
+        );
+    }
 }
@@ -5,7 +5,11 @@
 //! (FULL or USING INDEX). They are also a general anti-pattern — without a PK
 //! there is no guaranteed way to uniquely identify a row for updates, deletes, or
 //! foreign key references.
+//!
+//! TEMP tables are exempt, as are tables listed in `allowed_tables` or in a schema
+//! listed in `ignore_schemas` under `[checks.CreateTableWithoutPkCheck]`.
 
+use crate::checks::options::{ALLOWED_TABLES, CheckOption, IGNORE_SCHEMAS};
 use crate::checks::pg_helpers::{ConstrType, NodeEnum, column_has_constraint, range_var_name};
 use crate::checks::{Check, CheckDoc, Config, MigrationContext, impl_check_doc};
 use crate::violation::Violation;
@@ -16,7 +20,11 @@ pub struct CreateTableWithoutPkCheck;
 impl_check_doc!(CreateTableWithoutPkCheck, "create-table-without-pk");
 
 impl Check for CreateTableWithoutPkCheck {
-    fn check(&self, node: &NodeEnum, _config: &Config, _ctx: &MigrationContext) -> Vec<Violation> {
+    fn options(&self) -> &'static [CheckOption] {
+        &[ALLOWED_TABLES, IGNORE_SCHEMAS]
+    }
+
+    fn check(&self, node: &NodeEnum, config: &Config, _ctx: &MigrationContext) -> Vec<Violation> {
         let NodeEnum::CreateStmt(stmt) = node else {
             return vec![];
         };
@@ -56,6 +64,9 @@ impl Check for CreateTableWithoutPkCheck {
             .as_ref()
             .map(range_var_name)
             .unwrap_or_default();
+        if config.check_options(self.name()).exempts_table(&table_name) {
+            return vec![];
+        }
 
         vec![Violation::new(
             "CREATE TABLE without PRIMARY KEY",
@@ -98,7 +109,11 @@ will set REPLICA IDENTITY FULL), use a safety-assured block to bypass this check
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{assert_allows, assert_detects_violation};
+    use crate::checks::test_utils::config_with_options;
+    use crate::{
+        assert_allows, assert_allows_with_config, assert_detects_violation,
+        assert_detects_violation_with_config,
+    };
 
     #[test]
     fn test_detects_table_without_pk() {
@@ -173,4 +188,31 @@ mod tests {
             "ALTER TABLE users ADD COLUMN age INT;"
         );
     }
+
+    #[test]
+    fn test_allowed_tables_and_ignored_schemas() {
+        let config = config_with_options(
+            "CreateTableWithoutPkCheck",
+            r#"
+allowed_tables = ["audit_log"]
+ignore_schemas = ["staging"]
+"#,
+        );
+        assert_allows_with_config!(
+            CreateTableWithoutPkCheck,
+            "CREATE TABLE audit_log (event TEXT);",
+            &config
+        );
+        assert_allows_with_config!(
+            CreateTableWithoutPkCheck,
+            "CREATE TABLE staging.imports (line TEXT);",
+            &config
+        );
+        assert_detects_violation_with_config!(
+            CreateTableWithoutPkCheck,
+            "CREATE TABLE events (event TEXT);",
+            "CREATE TABLE without PRIMARY KEY",
+            &config
+        );
+    }
 }
@@ -0,0 +1,446 @@
+//! Detection for transactional migrations that hold heavy locks on many tables.
+//!
+//! A migration that runs in a transaction keeps every lock it takes until it
+//! commits. When it ALTERs `users`, then `orders`, then `payments`, all three
+//! tables stay blocked until the last statement finishes, and a session that
+//! locks the same tables in another order can deadlock with it.
+//!
+//! This file-level check collects the strongest lock each statement takes on
+//! each table and reports migrations that hold locks blocking writes (SHARE or
+//! stronger) on more than `max_tables` tables (default 2), or that run a large
+//! data change (UPDATE/DELETE without WHERE, INSERT ... SELECT, or DML on a
+//! table `table_stats` shows as large) while holding such a lock.
+//!
+//! Tables created in the same migration and tables skipped through
+//! `small_tables` don't count. The check is opt-in.
+
+use crate::ViolationList;
+use crate::checks::options::{CheckOption, OptionKind};
+use crate::checks::pg_helpers::{
+    AlterTableType, ConstrType, Node, NodeEnum, ObjectType, cmd_def_as_constraint,
+    drop_object_names, range_var_name, ref_table_constraint,
+};
+use crate::checks::{Check, CheckDoc, Config, MigrationContext, MigrationStmt, impl_check_doc};
+use crate::schema::relation_key;
+use crate::violation::Violation;
+
+/// Tables a migration may hold heavy locks on before it is reported.
+const MAX_TABLES: usize = 2;
+
+const OPTIONS: &[CheckOption] = &[CheckOption {
+    name: "max_tables",
+    kind: OptionKind::Integer,
+    description: "Most tables a transactional migration may hold write-blocking locks on (default: 2).",
+}];
+
+/// Table lock modes that block writes, weakest first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum LockMode {
+    Share,
+    ShareRowExclusive,
+    Exclusive,
+    AccessExclusive,
+}
+
+impl LockMode {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Share => "SHARE",
+            Self::ShareRowExclusive => "SHARE ROW EXCLUSIVE",
+            Self::Exclusive => "EXCLUSIVE",
+            Self::AccessExclusive => "ACCESS EXCLUSIVE",
+        }
+    }
+
+    /// Mode of a `LOCK TABLE` statement (Postgres' LOCKMODE numbers).
+    fn from_lock_stmt(mode: i32) -> Option<Self> {
+        match mode {
+            5 => Some(Self::Share),
+            6 => Some(Self::ShareRowExclusive),
+            7 => Some(Self::Exclusive),
+            8 => Some(Self::AccessExclusive),
+            _ => None,
+        }
+    }
+}
+
+/// A table locked by the migration, with the strongest mode taken so far and
+/// the line of the statement that took it first.
+struct HeldLock {
+    table: String,
+    mode: LockMode,
+    line: usize,
+}
+
+pub struct LockAccumulationCheck;
+impl_check_doc!(LockAccumulationCheck, "lock-accumulation");
+
+impl Check for LockAccumulationCheck {
+    fn options(&self) -> &'static [CheckOption] {
+        OPTIONS
+    }
+
+    fn check(&self, _node: &NodeEnum, _config: &Config, _ctx: &MigrationContext) -> Vec<Violation> {
+        vec![]
+    }
+
+    fn check_file(
+        &self,
+        stmts: &[MigrationStmt<'_>],
+        config: &Config,
+        ctx: &MigrationContext,
+    ) -> ViolationList {
+        // Without a transaction each statement releases its locks when it ends
+        if !ctx.run_in_transaction {
+            return vec![];
+        }
+        let max_tables = config
+            .check_options(self.name())
+            .integer("max_tables")
+            .unwrap_or(MAX_TABLES);
+
+        let mut held: Vec<HeldLock> = vec![];
+        let mut violations = vec![];
+        let (mut reported_tables, mut reported_dml) = (false, false);
+
+        for stmt in stmts {
+            // Data changes run while the locks of earlier statements are held
+            if !reported_dml
+                && let Some(lock) = held.first()
+                && let Some(dml) = large_dml(stmt.node, config)
+            {
+                reported_dml = true;
+                violations.push((stmt.line, dml_violation(lock, &dml)));
+            }
+
+            for (table, mode) in statement_locks(stmt.node) {
+                // Nobody else can see new tables; small ones are cheap to lock
+                if ctx.is_new_table(&table) || config.lock_severity(&table).is_none() {
+                    continue;
+                }
+                let key = relation_key(&table);
+                match held.iter_mut().find(|h| relation_key(&h.table) == key) {
+                    Some(lock) => lock.mode = lock.mode.max(mode),
+                    None => held.push(HeldLock {
+                        table,
+                        mode,
+                        line: stmt.line,
+                    }),
+                }
+            }
+
+            if !reported_tables && held.len() > max_tables {
+                reported_tables = true;
+                violations.push((stmt.line, tables_violation(&held, max_tables)));
+            }
+        }
+        violations
+    }
+}
+
+fn tables_violation(held: &[HeldLock], max_tables: usize) -> Violation {
+    let tables = held
+        .iter()
+        .map(|h| format!("'{}' ({})", h.table, h.mode.name()))
+        .collect::<Vec<_>>()
+        .join(", ");
+    Violation::new(
+        "Write-blocking locks on many tables in one transaction",
+        format!(
+            "This migration runs in a transaction and takes locks that block writes on {count} tables: {tables}. \
+            Every lock is held until the migration commits, so each table stays blocked until the last statement finishes, \
+            and sessions that lock the same tables in a different order can deadlock with the migration.",
+            count = held.len(),
+        ),
+        format!(
+            r"Split the changes into separate migrations that lock at most {max_tables} tables each.
+
+If the changes must be atomic, keep them together, set a short lock_timeout at the top of the
+migration so it fails fast instead of blocking traffic, and retry it when the tables are less busy:
+   SET LOCAL lock_timeout = '5s';"
+        ),
+    )
+}
+
+fn dml_violation(lock: &HeldLock, dml: &str) -> Violation {
+    Violation::new(
+        "Large data change while holding write-blocking locks",
+        format!(
+            "{dml} runs in the same transaction as the statement on line {line}, which locks '{table}' ({mode}). \
+            That lock is held until the migration commits, so '{table}' stays blocked for the whole data change.",
+            line = lock.line,
+            table = lock.table,
+            mode = lock.mode.name(),
+        ),
+        "Move the data change into its own migration, run before or after the schema change. \
+        For large tables, update in batches from a migration that runs outside a transaction.",
+    )
+}
+
+/// Tables a statement locks with a mode that blocks writes.
+fn statement_locks(node: &NodeEnum) -> Vec<(String, LockMode)> {
+    use LockMode::{AccessExclusive, Exclusive, Share, ShareRowExclusive};
+
+    match node {
+        NodeEnum::AlterTableStmt(alter) => {
+            let Some(relation) = alter.relation.as_ref() else {
+                return vec![];
+            };
+            let table = range_var_name(relation);
+            let mut locks = vec![];
+            for cmd in alter.cmds.iter().filter_map(|n| match &n.node {
+                Some(NodeEnum::AlterTableCmd(cmd)) => Some(cmd),
+                _ => None,
+            }) {
+                match cmd.subtype {
+                    // SHARE UPDATE EXCLUSIVE: reads and writes go on
+                    x if x == AlterTableType::AtValidateConstraint as i32
+                        || x == AlterTableType::AtSetStatistics as i32
+                        || x == AlterTableType::AtClusterOn as i32
+                        || x == AlterTableType::AtDropCluster as i32
+                        || x == AlterTableType::AtSetRelOptions as i32
+                        || x == AlterTableType::AtResetRelOptions as i32
+                        || x == AlterTableType::AtAttachPartition as i32 => {}
+                    x if (AlterTableType::AtEnableTrig as i32
+                        ..=AlterTableType::AtDisableTrigUser as i32)
+                        .contains(&x) =>
+                    {
+                        locks.push((table.clone(), ShareRowExclusive));
+                    }
+                    x if x == AlterTableType::AtAddConstraint as i32
+                        && cmd_def_as_constraint(cmd)
+                            .is_some_and(|c| c.contype == ConstrType::ConstrForeign as i32) =>
+                    {
+                        // Both sides of a foreign key
+                        locks.push((table.clone(), ShareRowExclusive));
+                        if let Some(c) = cmd_def_as_constraint(cmd) {
+                            locks.push((ref_table_constraint(c), ShareRowExclusive));
+                        }
+                    }
+                    _ => locks.push((table.clone(), AccessExclusive)),
+                }
+            }
+            locks
+        }
+        NodeEnum::RenameStmt(rename) => rename
+            .relation
+            .as_ref()
+            .map(|rel| vec![(range_var_name(rel), AccessExclusive)])
+            .unwrap_or_default(),
+        NodeEnum::IndexStmt(index) if !index.concurrent => index
+            .relation
+            .as_ref()
+            .map(|rel| vec![(range_var_name(rel), Share)])
+            .unwrap_or_default(),
+        NodeEnum::CreateTrigStmt(trigger) => trigger
+            .relation
+            .as_ref()
+            .map(|rel| vec![(range_var_name(rel), ShareRowExclusive)])
+            .unwrap_or_default(),
+        NodeEnum::RefreshMatViewStmt(refresh) => {
+            let mode = if refresh.concurrent {
+                Exclusive
+            } else {
+                AccessExclusive
+            };
+            refresh
+                .relation
+                .as_ref()
+                .map(|rel| vec![(range_var_name(rel), mode)])
+                .unwrap_or_default()
+        }
+        NodeEnum::DropStmt(drop) => {
+            let dropped = drop.remove_type == ObjectType::ObjectTable as i32
+                || drop.remove_type == ObjectType::ObjectView as i32
+                || drop.remove_type == ObjectType::ObjectMatview as i32
+                || drop.remove_type == ObjectType::ObjectForeignTable as i32
+                || (drop.remove_type == ObjectType::ObjectIndex as i32 && !drop.concurrent);
+            if !dropped {
+                return vec![];
+            }
+            drop_object_names(&drop.objects)
+                .into_iter()
+                .map(|name| (name, AccessExclusive))
+                .collect()
+        }
+        NodeEnum::TruncateStmt(truncate) => relation_names(&truncate.relations)
+            .map(|name| (name, AccessExclusive))
+            .collect(),
+        NodeEnum::LockStmt(lock) => match LockMode::from_lock_stmt(lock.mode) {
+            Some(mode) => relation_names(&lock.relations)
+                .map(|name| (name, mode))
+                .collect(),
+            None => vec![],
+        },
+        _ => vec![],
+    }
+}
+
+fn relation_names(relations: &[Node]) -> impl Iterator<Item = String> + '_ {
+    relations.iter().filter_map(|n| match &n.node {
+        Some(NodeEnum::RangeVar(rv)) => Some(range_var_name(rv)),
+        _ => None,
+    })
+}
+
+/// Describe a data change that can run for a long time, e.g.
+/// "UPDATE on 'users' without WHERE"; `None` for other statements.
+fn large_dml(node: &NodeEnum, config: &Config) -> Option<String> {
+    let (statement, relation, unbounded) = match node {
+        NodeEnum::UpdateStmt(update) => (
+            "UPDATE",
+            update.relation.as_ref()?,
+            update.where_clause.is_none(),
+        ),
+        NodeEnum::DeleteStmt(delete) => (
+            "DELETE",
+            delete.relation.as_ref()?,
+            delete.where_clause.is_none(),
+        ),
+        NodeEnum::InsertStmt(insert) => {
+            // INSERT ... SELECT copies an unknown number of rows; VALUES doesn't
+            let from_select = matches!(
+                insert.select_stmt.as_deref().and_then(|n| n.node.as_ref()),
+                Some(NodeEnum::SelectStmt(select)) if select.values_lists.is_empty()
+            );
+            ("INSERT", insert.relation.as_ref()?, from_select)
+        }
+        _ => return None,
+    };
+    let table = range_var_name(relation);
+    if unbounded {
+        return Some(match statement {
+            "INSERT" => format!("INSERT ... SELECT into '{table}'"),
+            _ => format!("{statement} on '{table}' without WHERE"),
+        });
+    }
+    // A filtered UPDATE or DELETE is only large when table_stats says so
+    (statement != "INSERT" && config.is_large_table(&table))
+        .then(|| format!("{statement} on large table '{table}'"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checks::Registry;
+    use crate::checks::test_utils::config_with_options;
+
+    /// Run the check over a whole migration, like a migration file.
+    fn check_file(sql: &str, config: &Config, ctx: &MigrationContext) -> Vec<(usize, Violation)> {
+        let config = Config {
+            enable_checks: vec!["LockAccumulationCheck".to_string()],
+            ..config.clone()
+        };
+        let registry = Registry::with_config(&config);
+        let stmts = pg_query::parse(sql).unwrap().protobuf.stmts;
+        registry.check_stmts_with_context(&stmts, sql, &[], &config, ctx)
+    }
+
+    const THREE_TABLES: &str = "ALTER TABLE users ADD COLUMN admin BOOLEAN;
+ALTER TABLE orders ADD COLUMN note TEXT;
+ALTER TABLE payments ADD COLUMN note TEXT;";
+
+    #[test]
+    fn test_detects_locks_on_more_than_max_tables() {
+        let violations = check_file(
+            THREE_TABLES,
+            &Config::default(),
+            &MigrationContext::default(),
+        );
+        assert_eq!(violations.len(), 1);
+        let (line, violation) = &violations[0];
+        assert_eq!(*line, 3);
+        assert_eq!(
+            violation.operation,
+            "Write-blocking locks on many tables in one transaction"
+        );
+        assert!(violation.problem.contains(
+            "'users' (ACCESS EXCLUSIVE), 'orders' (ACCESS EXCLUSIVE), 'payments' (ACCESS EXCLUSIVE)"
+        ));
+    }
+
+    #[test]
+    fn test_max_tables_option() {
+        let config = config_with_options("LockAccumulationCheck", "max_tables = 3");
+        assert!(check_file(THREE_TABLES, &config, &MigrationContext::default()).is_empty());
+    }
+
+    #[test]
+    fn test_allows_migrations_outside_a_transaction() {
+        let ctx = MigrationContext {
+            run_in_transaction: false,
+            ..MigrationContext::default()
+        };
+        assert!(check_file(THREE_TABLES, &Config::default(), &ctx).is_empty());
+    }
+
+    #[test]
+    fn test_skips_new_and_small_tables() {
+        let sql = "CREATE TABLE posts (id BIGINT PRIMARY KEY);
+ALTER TABLE posts ADD COLUMN title TEXT;
+ALTER TABLE users ADD COLUMN admin BOOLEAN;
+ALTER TABLE orders ADD COLUMN note TEXT;
+ALTER TABLE countries ADD COLUMN note TEXT;";
+        let config = Config {
+            small_tables: vec!["countries".to_string()],
+            ..Config::default()
+        };
+        assert!(check_file(sql, &config, &MigrationContext::default()).is_empty());
+    }
+
+    #[test]
+    fn test_counts_both_sides_of_a_foreign_key_with_the_strongest_lock() {
+        let sql = "ALTER TABLE orders ADD CONSTRAINT fk_orders_user FOREIGN KEY (user_id) REFERENCES users(id) NOT VALID;
+ALTER TABLE orders VALIDATE CONSTRAINT fk_orders_user;
+CREATE INDEX idx_payments_order_id ON payments(order_id);
+ALTER TABLE orders DROP COLUMN legacy_id;";
+        let violations = check_file(sql, &Config::default(), &MigrationContext::default());
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].0, 3);
+        assert!(violations[0].1.problem.contains(
+            "'orders' (SHARE ROW EXCLUSIVE), 'users' (SHARE ROW EXCLUSIVE), 'payments' (SHARE)"
+        ));
+    }
+
+    #[test]
+    fn test_detects_large_dml_after_a_heavy_lock() {
+        let sql = "ALTER TABLE users ADD COLUMN admin BOOLEAN;
+UPDATE users SET admin = false;";
+        let violations = check_file(sql, &Config::default(), &MigrationContext::default());
+        assert_eq!(violations.len(), 1);
+        let (line, violation) = &violations[0];
+        assert_eq!(*line, 2);
+        assert_eq!(
+            violation.operation,
+            "Large data change while holding write-blocking locks"
+        );
+        assert!(violation.problem.starts_with(
+            "UPDATE on 'users' without WHERE runs in the same transaction as the statement on line 1"
+        ));
+    }
+
+    #[test]
+    fn test_allows_small_or_earlier_dml() {
+        let sql = "UPDATE users SET admin = false;
+INSERT INTO audit_log (event) SELECT 'backfill';
+ALTER TABLE users ADD COLUMN admin BOOLEAN;
+UPDATE users SET admin = true WHERE id = 1;
+INSERT INTO audit_log (event) VALUES ('done');";
+        let violations = check_file(sql, &Config::default(), &MigrationContext::default());
+        assert!(violations.is_empty(), "{violations:?}");
+    }
+
+    #[test]
+    fn test_insert_select_after_a_heavy_lock() {
+        let sql = "CREATE INDEX idx_users_email ON users(email);
+INSERT INTO users_archive SELECT * FROM users;";
+        let violations = check_file(sql, &Config::default(), &MigrationContext::default());
+        assert_eq!(violations.len(), 1);
+        assert!(
+            violations[0]
+                .1
+                .problem
+                .starts_with("INSERT ... SELECT into 'users_archive'")
+        );
+    }
+}
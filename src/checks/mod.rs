@@ -26,17 +26,24 @@ mod idempotency_alter;
 mod idempotency_create;
 mod idempotency_drop;
 mod idempotency_index;
+mod lock_accumulation;
 mod mutation_without_where;
+pub mod options;
 pub mod pg_helpers;
 mod refresh_matview;
 mod reindex;
 mod rename_column;
 mod rename_schema;
 mod rename_table;
+mod require_lock_timeout;
+mod set_access_method;
+mod set_logged;
+mod set_tablespace;
 mod short_int_primary_key;
 mod timestamp_type;
 mod truncate_table;
 mod unnamed_constraint;
+mod validate_in_same_transaction;
 mod wide_index;
 
 #[cfg(test)]
@@ -69,22 +76,32 @@ pub use idempotency_alter::IdempotencyAlterCheck;
 pub use idempotency_create::IdempotencyCreateCheck;
 pub use idempotency_drop::IdempotencyDropCheck;
 pub use idempotency_index::IdempotencyIndexCheck;
+pub use lock_accumulation::LockAccumulationCheck;
 pub use mutation_without_where::MutationWithoutWhereCheck;
 pub use refresh_matview::RefreshMatViewCheck;
 pub use reindex::ReindexCheck;
 pub use rename_column::RenameColumnCheck;
 pub use rename_schema::RenameSchemaCheck;
 pub use rename_table::RenameTableCheck;
+pub use require_lock_timeout::RequireLockTimeoutCheck;
+pub use set_access_method::SetAccessMethodCheck;
+pub use set_logged::SetLoggedCheck;
+pub use set_tablespace::SetTablespaceCheck;
 pub use short_int_primary_key::ShortIntegerPrimaryKeyCheck;
 pub use timestamp_type::TimestampTypeCheck;
 pub use truncate_table::TruncateTableCheck;
 pub use unnamed_constraint::UnnamedConstraintCheck;
+pub use validate_in_same_transaction::ValidateInSameTransactionCheck;
 pub use wide_index::WideIndexCheck;
 
 pub use crate::config::Config;
+pub use options::{CheckOption, CheckOptions, OptionKind};
 
 /// Helper functions for check implementations
 mod helpers {
+    use crate::config::Config;
+    use crate::violation::{Severity, Violation};
+
     /// Get prefix string for unique indexes
     pub fn unique_prefix(is_unique: bool) -> &'static str {
         if is_unique { "UNIQUE " } else { "" }
@@ -94,10 +111,28 @@ mod helpers {
     pub fn if_exists_clause(if_exists: bool) -> &'static str {
         if if_exists { " IF EXISTS" } else { "" }
     }
+
+    /// Finish a lock, rewrite or scan violation on `table`: set the severity
+    /// from [`Config::lock_severity`] and, when `table_stats` lists the table,
+    /// record its size in [`Violation::table_size`].
+    pub fn lock_violation(
+        violation: Violation,
+        table: &str,
+        severity: Severity,
+        config: &Config,
+    ) -> Violation {
+        Violation {
+            table_size: config
+                .table_size(table)
+                .map(|size| format!("Table '{table}' is {}.", size.describe())),
+            ..violation.with_severity(severity)
+        }
+    }
 }
 
 use crate::ViolationList;
 use crate::parser::IgnoreRange;
+use crate::schema::relation_key;
 use crate::violation::Violation;
 pub use helpers::*;
 use pg_helpers::{NodeEnum, extract_node};
@@ -114,6 +149,20 @@ static BUILTIN_CHECK_NAMES: LazyLock<Vec<&'static str>> = LazyLock::new(|| {
     registry.checks.iter().map(|c| c.name()).collect()
 });
 
+/// Options each built-in check declares, by check name.
+static BUILTIN_CHECK_OPTIONS: LazyLock<Vec<(&'static str, &'static [CheckOption])>> =
+    LazyLock::new(|| {
+        let registry = Registry::new();
+        registry
+            .checks
+            .iter()
+            .map(|c| (c.name(), c.options()))
+            .collect()
+    });
+
+/// Built-in checks that only run when `opt_in_checks` or `enable_checks` names them.
+pub const OPT_IN_CHECKS: &[&str] = &["LockAccumulationCheck", "RequireLockTimeoutCheck"];
+
 /// Associates a check struct with its static markdown documentation.
 pub trait CheckDoc {
     fn doc(&self) -> Option<&'static str> {
@@ -156,8 +205,28 @@ pub trait Check: Send + Sync + CheckDoc {
         None
     }
 
+    /// Options the check reads from its `[checks.<CheckName>]` table.
+    fn options(&self) -> &'static [CheckOption] {
+        &[]
+    }
+
     /// Run the check on a pg_query AST node and return any violations found
     fn check(&self, node: &NodeEnum, config: &Config, ctx: &MigrationContext) -> Vec<Violation>;
+
+    /// Run the check on all statements of a migration at once, for rules that
+    /// span statements. Returns `(line, violation)` pairs, where `line` is the
+    /// `line` of the statement the violation belongs to.
+    ///
+    /// `ctx` is the context after the last statement: `created_tables` lists
+    /// every table the migration creates.
+    fn check_file(
+        &self,
+        _stmts: &[MigrationStmt<'_>],
+        _config: &Config,
+        _ctx: &MigrationContext,
+    ) -> ViolationList {
+        vec![]
+    }
 }
 
 /// Registry of all available checks
@@ -208,16 +277,22 @@ impl Registry {
         self.register_check(config, IdempotencyCreateCheck);
         self.register_check(config, IdempotencyDropCheck);
         self.register_check(config, IdempotencyIndexCheck);
+        self.register_check(config, LockAccumulationCheck);
         self.register_check(config, MutationWithoutWhereCheck);
         self.register_check(config, RefreshMatViewCheck);
         self.register_check(config, ReindexCheck);
         self.register_check(config, RenameColumnCheck);
         self.register_check(config, RenameSchemaCheck);
         self.register_check(config, RenameTableCheck);
+        self.register_check(config, RequireLockTimeoutCheck);
+        self.register_check(config, SetAccessMethodCheck);
+        self.register_check(config, SetLoggedCheck);
+        self.register_check(config, SetTablespaceCheck);
         self.register_check(config, ShortIntegerPrimaryKeyCheck);
         self.register_check(config, TimestampTypeCheck);
         self.register_check(config, TruncateTableCheck);
         self.register_check(config, UnnamedConstraintCheck);
+        self.register_check(config, ValidateInSameTransactionCheck);
         self.register_check(config, WideIndexCheck);
     }
 
@@ -236,39 +311,66 @@ impl Registry {
         self.checks.iter().map(Box::as_ref)
     }
 
-    /// Register a check if it's enabled in configuration
+    /// Register a check if it's enabled in configuration, at the top level or
+    /// for some migrations
     fn register_check(&mut self, config: &Config, check: impl Check + 'static) {
-        if !config.is_check_enabled(check.name()) {
+        if !config.may_enable_check(check.name()) {
             return;
         }
         self.checks.push(Box::new(check));
     }
 
-    /// Check a single AST node against all registered checks
+    /// Check a single AST node against the registered checks enabled in `config`
+    ///
+    /// `config` is the one for the migration being checked (see
+    /// [`Config::for_file`]), so `[[overrides]]` apply per file.
     pub fn check_node(
         &self,
         node: &NodeEnum,
         config: &Config,
         ctx: &MigrationContext,
     ) -> Vec<Violation> {
-        use crate::violation::Severity;
-        self.checks
-            .iter()
-            .filter(|check| !ctx.disables_check(check.name()))
+        self.enabled_checks(config, ctx)
             .flat_map(|check| {
-                let severity = if config.is_check_warning(check.name()) {
-                    Severity::Warning
-                } else {
-                    Severity::Error
-                };
                 check
                     .check(node, config, ctx)
                     .into_iter()
-                    .map(move |v| v.with_severity(severity).with_check_name(check.name()))
+                    .map(move |v| finish_violation(v, check, config))
+            })
+            .collect()
+    }
+
+    /// Run [`Check::check_file`] of the registered checks enabled in `config`
+    /// over all statements of a migration.
+    pub fn check_file(
+        &self,
+        stmts: &[MigrationStmt<'_>],
+        config: &Config,
+        ctx: &MigrationContext,
+    ) -> ViolationList {
+        self.enabled_checks(config, ctx)
+            .flat_map(|check| {
+                check
+                    .check_file(stmts, config, ctx)
+                    .into_iter()
+                    .map(move |(line, v)| (line, finish_violation(v, check, config)))
             })
             .collect()
     }
 
+    /// Registered checks that `config` enables and the migration doesn't disable.
+    fn enabled_checks<'a>(
+        &'a self,
+        config: &'a Config,
+        ctx: &'a MigrationContext,
+    ) -> impl Iterator<Item = &'a dyn Check> + 'a {
+        self.checks
+            .iter()
+            .map(Box::as_ref)
+            .filter(|check| config.is_check_enabled(check.name()))
+            .filter(|check| !ctx.disables_check(check.name()))
+    }
+
     /// Check statements with safety-assured context.
     ///
     /// Returns `(line, violation)` pairs where `line` is the 1-indexed line number of
@@ -284,6 +386,27 @@ impl Registry {
         config: &Config,
         ctx: &MigrationContext,
     ) -> ViolationList {
+        self.check_each_stmt(stmts, sql, ignore_ranges, config, ctx)
+            .into_iter()
+            .flat_map(|checked| {
+                let line = checked.line;
+                checked.violations.into_iter().map(move |v| (line, v))
+            })
+            .collect()
+    }
+
+    /// Check statements one by one, keeping each statement's location.
+    ///
+    /// Same rules as [`Registry::check_stmts_with_context`]; statements inside
+    /// safety-assured blocks are returned with no violations.
+    pub fn check_each_stmt<'a>(
+        &self,
+        stmts: &'a [RawStmt],
+        sql: &str,
+        ignore_ranges: &[IgnoreRange],
+        config: &Config,
+        ctx: &MigrationContext,
+    ) -> Vec<CheckedStmt<'a>> {
         // Build set of all ignored line numbers for fast lookup
         let ignored_lines: std::collections::HashSet<usize> = ignore_ranges
             .iter()
@@ -294,35 +417,240 @@ impl Registry {
         // statement. Use the scanner to get accurate token positions.
         let token_starts = non_comment_token_starts(sql);
 
-        let mut violations = Vec::new();
+        let located: Vec<(&NodeEnum, usize, usize)> = stmts
+            .iter()
+            .filter_map(|raw_stmt| {
+                let node = extract_node(raw_stmt)?;
+                let base = usize::try_from(raw_stmt.stmt_location).unwrap_or(0);
+                let offset = first_token_at_or_after(&token_starts, base);
+                Some((node, base, offset))
+            })
+            .collect();
 
-        for raw_stmt in stmts {
-            let Some(node) = extract_node(raw_stmt) else {
-                continue;
+        let mut checked = Vec::with_capacity(located.len());
+        let mut ctx = ctx.clone();
+
+        for (i, &(node, base, start)) in located.iter().enumerate() {
+            let end = located.get(i + 1).map_or(sql.len(), |&(_, _, next)| next);
+            let line = byte_offset_to_line(sql, start);
+            let column = byte_offset_to_column(sql, start);
+
+            let violations = if ignored_lines.contains(&line) {
+                vec![]
+            } else {
+                let mut violations = self.check_node(node, config, &ctx);
+                for v in &mut violations {
+                    v.column = Some(column);
+                }
+                // Checks report fix offsets relative to the statement, like node locations
+                for edit in violations
+                    .iter_mut()
+                    .filter_map(|v| v.fix.as_mut())
+                    .flat_map(|fix| &mut fix.edits)
+                {
+                    edit.start += base;
+                    edit.end += base;
+                }
+                violations
             };
 
-            let offset = first_token_at_or_after(
-                &token_starts,
-                usize::try_from(raw_stmt.stmt_location).unwrap_or(0),
-            );
-            let stmt_line = byte_offset_to_line(sql, offset);
-
-            if !ignored_lines.contains(&stmt_line) {
-                violations.extend(
-                    self.check_node(node, config, ctx)
-                        .into_iter()
-                        .map(|v| (stmt_line, v)),
-                );
+            checked.push(CheckedStmt {
+                node,
+                span: start..end,
+                line,
+                violations,
+            });
+
+            track_created_tables(&mut ctx, node);
+            track_settings(&mut ctx, node);
+        }
+
+        // Violations of file-level checks belong to the statement on their line
+        let stmts: Vec<MigrationStmt> = checked
+            .iter()
+            .map(|c| MigrationStmt {
+                node: c.node,
+                line: c.line,
+            })
+            .collect();
+        for (line, mut violation) in self.check_file(&stmts, config, &ctx) {
+            if ignored_lines.contains(&line) {
+                continue;
             }
+            let index = checked
+                .iter()
+                .position(|c| c.line == line)
+                .or_else(|| checked.iter().rposition(|c| c.line < line));
+            let Some(stmt) = index.map(|i| &mut checked[i]) else {
+                continue;
+            };
+            violation.column = Some(byte_offset_to_column(sql, stmt.span.start));
+            stmt.violations.push(violation);
         }
 
-        violations
+        crate::fix::attach_fixes(sql, &mut checked, ctx.run_in_transaction);
+        for stmt in &mut checked {
+            crate::fix::reject_invalid_custom_fixes(sql, stmt);
+        }
+        checked
     }
 
     /// Get all built-in check names (regardless of which are enabled).
     pub fn builtin_check_names() -> &'static [&'static str] {
         &BUILTIN_CHECK_NAMES
     }
+
+    /// Options the built-in check `name` supports; `None` for other names.
+    pub fn builtin_check_options(name: &str) -> Option<&'static [CheckOption]> {
+        BUILTIN_CHECK_OPTIONS
+            .iter()
+            .find(|(check, _)| *check == name)
+            .map(|(_, options)| *options)
+    }
+}
+
+/// A statement together with its location and the violations it produced.
+pub struct CheckedStmt<'a> {
+    pub node: &'a NodeEnum,
+    /// Byte range from the statement's first token up to the next statement.
+    pub span: std::ops::Range<usize>,
+    /// 1-indexed line of the statement's first token.
+    pub line: usize,
+    pub violations: Vec<Violation>,
+}
+
+/// A statement as [`Check::check_file`] sees it. Rhai scripts get these as
+/// `#{ node, line }` maps.
+#[derive(Debug, Clone, Copy, serde::Serialize)]
+pub struct MigrationStmt<'a> {
+    pub node: &'a NodeEnum,
+    /// 1-indexed line of the statement's first token.
+    pub line: usize,
+}
+
+/// Set the severity and check name of a violation `check` reported.
+fn finish_violation(violation: Violation, check: &dyn Check, config: &Config) -> Violation {
+    use crate::violation::Severity;
+    // warn_checks downgrades everything; checks may downgrade single violations
+    let severity = if config.is_check_warning(check.name()) {
+        Severity::Warning
+    } else {
+        violation.severity
+    };
+    violation
+        .with_severity(severity)
+        .with_check_name(check.name())
+}
+
+/// Keep `ctx.created_tables` in sync with a statement that just ran.
+fn track_created_tables(ctx: &mut MigrationContext, node: &NodeEnum) {
+    use pg_helpers::{ObjectType, drop_object_names, range_var_name};
+
+    match node {
+        NodeEnum::CreateStmt(create) => {
+            let Some(rel) = create.relation.as_ref() else {
+                return;
+            };
+            let name = range_var_name(rel);
+            // IF NOT EXISTS on a table from an earlier migration is a no-op
+            if !(create.if_not_exists && ctx.schema.has_table(&name)) {
+                ctx.created_tables.push(relation_key(&name));
+            }
+        }
+        // CREATE TABLE AS and materialized views hold the query's rows, and
+        // outside a transaction other sessions see them right away
+        NodeEnum::CreateTableAsStmt(create) => {
+            let Some(into) = create.into.as_ref() else {
+                return;
+            };
+            if into.skip_data
+                && ctx.in_transaction()
+                && let Some(rel) = into.rel.as_ref()
+            {
+                ctx.created_tables.push(relation_key(&range_var_name(rel)));
+            }
+        }
+        NodeEnum::RenameStmt(rename) if rename.rename_type == ObjectType::ObjectTable as i32 => {
+            let Some(rel) = rename.relation.as_ref() else {
+                return;
+            };
+            let old = relation_key(&range_var_name(rel));
+            if let Some(table) = ctx.created_tables.iter_mut().find(|t| **t == old) {
+                *table = match old.rsplit_once('.') {
+                    Some((schema, _)) => format!("{schema}.{}", rename.newname),
+                    None => rename.newname.clone(),
+                };
+            }
+        }
+        NodeEnum::DropStmt(drop) if drop.remove_type == ObjectType::ObjectTable as i32 => {
+            for name in drop_object_names(&drop.objects) {
+                let name = relation_key(&name);
+                ctx.created_tables.retain(|t| *t != name);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Keep `ctx.settings` and `ctx.in_transaction_block` in sync with a
+/// statement that just ran.
+fn track_settings(ctx: &mut MigrationContext, node: &NodeEnum) {
+    use crate::adapters::Setting;
+    use pg_query::protobuf::{TransactionStmtKind, VariableSetKind, a_const::Val};
+
+    match node {
+        NodeEnum::VariableSetStmt(set) => {
+            let name = set.name.to_ascii_lowercase();
+            match set.kind {
+                // SET LOCAL outside a transaction has no effect
+                x if x == VariableSetKind::VarSetValue as i32
+                    && (!set.is_local || ctx.in_transaction()) =>
+                {
+                    let value = set
+                        .args
+                        .iter()
+                        .filter_map(|arg| match &arg.node {
+                            Some(NodeEnum::AConst(c)) => match &c.val {
+                                Some(Val::Sval(s)) => Some(s.sval.clone()),
+                                Some(Val::Ival(i)) => Some(i.ival.to_string()),
+                                Some(Val::Fval(f)) => Some(f.fval.clone()),
+                                _ => None,
+                            },
+                            _ => None,
+                        })
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    let local = set.is_local;
+                    ctx.settings.insert(name, Setting { value, local });
+                }
+                x if x == VariableSetKind::VarSetDefault as i32
+                    || x == VariableSetKind::VarReset as i32 =>
+                {
+                    ctx.settings.remove(&name);
+                }
+                x if x == VariableSetKind::VarResetAll as i32 => ctx.settings.clear(),
+                _ => {}
+            }
+        }
+        NodeEnum::TransactionStmt(transaction) => match transaction.kind {
+            x if x == TransactionStmtKind::TransStmtBegin as i32
+                || x == TransactionStmtKind::TransStmtStart as i32 =>
+            {
+                ctx.in_transaction_block = true;
+            }
+            x if x == TransactionStmtKind::TransStmtCommit as i32
+                || x == TransactionStmtKind::TransStmtRollback as i32 =>
+            {
+                ctx.in_transaction_block = false;
+                // SET LOCAL ends with the transaction
+                if !ctx.run_in_transaction {
+                    ctx.settings.retain(|_, setting| !setting.local);
+                }
+            }
+            _ => {}
+        },
+        _ => {}
+    }
 }
 
 /// Convert a byte offset to a 1-indexed line number.
@@ -331,6 +659,13 @@ fn byte_offset_to_line(sql: &str, byte_offset: usize) -> usize {
     sql[..offset].bytes().filter(|&b| b == b'\n').count() + 1
 }
 
+/// Convert a byte offset to a 1-indexed column, counted in characters.
+fn byte_offset_to_column(sql: &str, byte_offset: usize) -> usize {
+    let offset = byte_offset.min(sql.len());
+    let line_start = sql[..offset].rfind('\n').map_or(0, |i| i + 1);
+    sql[line_start..offset].chars().count() + 1
+}
+
 /// Sorted byte positions of all non-comment tokens, via pg_query's scanner.
 fn non_comment_token_starts(sql: &str) -> Vec<usize> {
     use pg_query::protobuf::Token;
@@ -484,6 +819,104 @@ ALTER TABLE users DROP COLUMN email;
         assert_eq!(violations.len(), 1);
     }
 
+    #[test]
+    fn test_file_level_violations_honour_safety_assured_blocks_and_disabled_checks() {
+        let (config, registry) = registry_with_enabled_checks(&["LockAccumulationCheck"]);
+        let sql = "ALTER TABLE users ADD COLUMN admin BOOLEAN;
+ALTER TABLE orders ADD COLUMN note TEXT;
+-- safety-assured:start
+ALTER TABLE payments ADD COLUMN note TEXT;
+-- safety-assured:end
+";
+        let stmts = crate::parser::parse(sql).unwrap();
+        let check = |ignore_ranges: &[IgnoreRange], ctx: &MigrationContext| {
+            registry.check_stmts_with_context(&stmts, sql, ignore_ranges, &config, ctx)
+        };
+
+        let violations = check(&[], &MigrationContext::default());
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].0, 4);
+        assert_eq!(violations[0].1.check_name, "LockAccumulationCheck");
+
+        let block = [IgnoreRange {
+            start_line: 3,
+            end_line: 5,
+        }];
+        assert!(check(&block, &MigrationContext::default()).is_empty());
+
+        let ctx = MigrationContext::default()
+            .with_disabled_checks(&["LockAccumulationCheck".to_string()]);
+        assert!(check(&[], &ctx).is_empty());
+    }
+
+    // --- Tables created in the same migration ---
+
+    fn check_new_table_violations(sql: &str, ctx: &MigrationContext) -> ViolationList {
+        let (config, registry) =
+            registry_with_enabled_checks(&["AddIndexCheck", "AddForeignKeyCheck"]);
+        let stmts = crate::parser::parse(sql).unwrap();
+        registry.check_stmts_with_context(&stmts, sql, &[], &config, ctx)
+    }
+
+    #[test]
+    fn test_table_created_earlier_in_migration_is_exempt() {
+        let sql = "CREATE TABLE posts (id BIGINT, user_id BIGINT);
+CREATE INDEX idx_posts_user_id ON posts(user_id);
+ALTER TABLE public.posts ADD FOREIGN KEY (user_id) REFERENCES users(id);";
+        let violations = check_new_table_violations(sql, &MigrationContext::default());
+        assert!(violations.is_empty(), "{violations:?}");
+    }
+
+    #[test]
+    fn test_table_is_only_exempt_after_it_is_created() {
+        let sql = "CREATE INDEX idx_posts_user_id ON posts(user_id);
+CREATE TABLE posts (id BIGINT, user_id BIGINT);";
+        let violations = check_new_table_violations(sql, &MigrationContext::default());
+        assert_eq!(violations.len(), 1);
+    }
+
+    #[test]
+    fn test_renamed_new_table_stays_exempt_and_dropped_one_does_not() {
+        let sql = "CREATE TABLE posts_new (id BIGINT);
+ALTER TABLE posts_new RENAME TO posts;
+CREATE INDEX idx_posts_id ON posts(id);
+DROP TABLE posts;
+CREATE INDEX idx_posts_id ON posts(id);";
+        let violations = check_new_table_violations(sql, &MigrationContext::default());
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].0, 5);
+    }
+
+    #[test]
+    fn test_create_table_as_is_exempt_only_without_data_in_a_transaction() {
+        let sql = "CREATE TABLE posts_copy AS SELECT * FROM posts;
+CREATE INDEX idx_posts_copy_id ON posts_copy(id);
+CREATE TABLE posts_shape AS SELECT * FROM posts WITH NO DATA;
+CREATE INDEX idx_posts_shape_id ON posts_shape(id);";
+        let violations = check_new_table_violations(sql, &MigrationContext::default());
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].0, 2);
+
+        let outside_transaction = MigrationContext {
+            run_in_transaction: false,
+            ..MigrationContext::default()
+        };
+        let violations = check_new_table_violations(sql, &outside_transaction);
+        assert_eq!(violations.len(), 2);
+    }
+
+    #[test]
+    fn test_create_table_if_not_exists_on_existing_table_is_not_exempt() {
+        let mut schema = crate::schema::SchemaCatalog::default();
+        schema.apply_stmts(&crate::parser::parse("CREATE TABLE posts (id BIGINT);").unwrap());
+        let ctx = MigrationContext::default().with_schema(std::sync::Arc::new(schema));
+
+        let sql = "CREATE TABLE IF NOT EXISTS posts (id BIGINT);
+CREATE INDEX idx_posts_id ON posts(id);";
+        let violations = check_new_table_violations(sql, &ctx);
+        assert_eq!(violations.len(), 1);
+    }
+
     // --- Line number accuracy ---
 
     fn check_sql_violations(sql: &str) -> ViolationList {
@@ -530,6 +963,15 @@ ALTER TABLE users DROP COLUMN email;
         assert_eq!(violations[0].0, 2);
     }
 
+    #[test]
+    fn test_violation_column_counts_characters() {
+        let violations =
+            check_sql_violations("SELECT 'é';  /* ü */ ALTER TABLE users DROP COLUMN email;");
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].0, 1);
+        assert_eq!(violations[0].1.column, Some(22));
+    }
+
     #[test]
     fn test_violation_line_number_preceded_by_multiline_block_comment() {
         let violations =
@@ -0,0 +1,174 @@
+//! Per-check options from `[checks.<CheckName>]` tables in diesel-guard.toml.
+//!
+//! Built-in checks declare the options they read with [`Check::options`], which
+//! `explain` lists and config validation enforces. Custom Rhai checks get their
+//! table as-is, so they can use any keys.
+//!
+//! [`Check::options`]: crate::checks::Check::options
+
+use crate::schema::relation_key;
+
+/// Type of a check option's value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OptionKind {
+    /// A non-negative integer.
+    Integer,
+    /// An array of strings.
+    StringList,
+}
+
+impl OptionKind {
+    /// Name used in `explain` output and config errors.
+    pub fn type_name(self) -> &'static str {
+        match self {
+            Self::Integer => "integer",
+            Self::StringList => "list of strings",
+        }
+    }
+
+    /// What a value of this kind must be, for config errors.
+    fn expected(self) -> &'static str {
+        match self {
+            Self::Integer => "a non-negative integer",
+            Self::StringList => "a list of strings",
+        }
+    }
+
+    fn accepts(self, value: &toml::Value) -> bool {
+        match self {
+            Self::Integer => value.as_integer().is_some_and(|i| i >= 0),
+            Self::StringList => value
+                .as_array()
+                .is_some_and(|items| items.iter().all(toml::Value::is_str)),
+        }
+    }
+}
+
+/// An option a built-in check reads from its `[checks.<CheckName>]` table.
+#[derive(Debug, Clone, Copy)]
+pub struct CheckOption {
+    pub name: &'static str,
+    pub kind: OptionKind,
+    pub description: &'static str,
+}
+
+/// `allowed_tables`: tables the check never reports.
+pub const ALLOWED_TABLES: CheckOption = CheckOption {
+    name: "allowed_tables",
+    kind: OptionKind::StringList,
+    description: "Tables the check skips. Unqualified names match the table in any schema.",
+};
+
+/// `ignore_schemas`: schemas whose tables the check never reports.
+pub const IGNORE_SCHEMAS: CheckOption = CheckOption {
+    name: "ignore_schemas",
+    kind: OptionKind::StringList,
+    description: "Schemas whose tables the check skips. Unqualified tables are in \"public\".",
+};
+
+/// Why a `[checks.<CheckName>]` table doesn't fit the options the check declares.
+pub(crate) fn option_error(
+    supported: &[CheckOption],
+    key: &str,
+    value: &toml::Value,
+) -> Option<String> {
+    let Some(option) = supported.iter().find(|o| o.name == key) else {
+        return Some("unknown option".to_string());
+    };
+    (!option.kind.accepts(value)).then(|| format!("expected {}", option.kind.expected()))
+}
+
+/// The `[checks.<CheckName>]` table of one check; empty when the config has none.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CheckOptions<'a> {
+    table: Option<&'a toml::Table>,
+}
+
+impl<'a> CheckOptions<'a> {
+    pub(crate) fn new(table: Option<&'a toml::Table>) -> Self {
+        Self { table }
+    }
+
+    /// An integer option, if set.
+    pub fn integer(&self, key: &str) -> Option<usize> {
+        let value = self.table?.get(key)?.as_integer()?;
+        usize::try_from(value).ok()
+    }
+
+    /// A list-of-strings option; empty when unset.
+    pub fn strings(&self, key: &str) -> impl Iterator<Item = &'a str> + use<'a> {
+        self.table
+            .and_then(|t| t.get(key))
+            .and_then(toml::Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(toml::Value::as_str)
+    }
+
+    /// Whether `table` (as returned by `range_var_name`) is exempt through
+    /// `allowed_tables` or `ignore_schemas`.
+    pub fn exempts_table(&self, table: &str) -> bool {
+        let (schema, name) = table.rsplit_once('.').unwrap_or(("public", table));
+        // `public.users` and `users` are the same table on either side
+        let key = relation_key(table);
+        self.strings(IGNORE_SCHEMAS.name)
+            .any(|s| s.eq_ignore_ascii_case(schema))
+            || self.strings(ALLOWED_TABLES.name).any(|allowed| {
+                relation_key(allowed).eq_ignore_ascii_case(&key)
+                    || (!allowed.contains('.') && allowed.eq_ignore_ascii_case(name))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(toml: &str) -> toml::Table {
+        toml::from_str(toml).unwrap()
+    }
+
+    #[test]
+    fn test_exempts_allowed_tables_and_ignored_schemas() {
+        let table = table(
+            r#"
+allowed_tables = ["legacy_events", "audit.users", "public.accounts"]
+ignore_schemas = ["staging"]
+"#,
+        );
+        let options = CheckOptions::new(Some(&table));
+
+        assert!(options.exempts_table("accounts"));
+        assert!(options.exempts_table("public.accounts"));
+        assert!(!options.exempts_table("audit.accounts"));
+        assert!(options.exempts_table("legacy_events"));
+        assert!(options.exempts_table("public.legacy_events"));
+        assert!(options.exempts_table("AUDIT.users"));
+        assert!(options.exempts_table("staging.orders"));
+        assert!(!options.exempts_table("users"));
+        assert!(!options.exempts_table("orders"));
+    }
+
+    #[test]
+    fn test_missing_table_has_no_options() {
+        let options = CheckOptions::default();
+        assert_eq!(options.integer("max_columns"), None);
+        assert_eq!(options.strings("allowed_tables").count(), 0);
+        assert!(!options.exempts_table("users"));
+    }
+
+    #[test]
+    fn test_option_error() {
+        let supported = [ALLOWED_TABLES];
+        assert_eq!(
+            option_error(&supported, "allowed_tables", &toml::Value::from(3)),
+            Some("expected a list of strings".to_string())
+        );
+        assert_eq!(
+            option_error(&supported, "max_columns", &toml::Value::from(3)),
+            Some("unknown option".to_string())
+        );
+        let tables = toml::Value::Array(vec!["users".into()]);
+        assert_eq!(option_error(&supported, "allowed_tables", &tables), None);
+    }
+}
@@ -0,0 +1,379 @@
+//! Detection for lock-taking DDL that runs without a `lock_timeout`.
+//!
+//! This check tracks `SET lock_timeout` (and `SET statement_timeout`) statements
+//! across a migration and reports ALTER TABLE, CREATE INDEX, DROP, TRUNCATE and
+//! other statements that take a blocking lock before a timeout is set.
+//!
+//! A statement waiting for an ACCESS EXCLUSIVE lock behind a long-running query
+//! blocks every query on the table that arrives after it, which turns a slow
+//! migration into an outage. With a `lock_timeout` the statement fails instead,
+//! and the migration can be retried.
+//!
+//! The check is opt-in: it runs only when `opt_in_checks` or `enable_checks`
+//! names it. `max_lock_timeout_ms` caps the accepted `lock_timeout`, and
+//! `max_statement_timeout_ms` also requires a `statement_timeout`.
+
+use crate::checks::options::{CheckOption, OptionKind};
+use crate::checks::pg_helpers::{Node, NodeEnum, ObjectType, drop_object_names, range_var_name};
+use crate::checks::{Check, CheckDoc, Config, MigrationContext, impl_check_doc};
+use crate::violation::Violation;
+
+/// `lock_timeout` suggested when the config sets no maximum.
+const SUGGESTED_LOCK_TIMEOUT: &str = "5s";
+
+const OPTIONS: &[CheckOption] = &[
+    CheckOption {
+        name: "max_lock_timeout_ms",
+        kind: OptionKind::Integer,
+        description: "Longest lock_timeout accepted, in milliseconds (default: no limit).",
+    },
+    CheckOption {
+        name: "max_statement_timeout_ms",
+        kind: OptionKind::Integer,
+        description: "Also require a statement_timeout of at most this many milliseconds.",
+    },
+];
+
+pub struct RequireLockTimeoutCheck;
+impl_check_doc!(RequireLockTimeoutCheck, "require-lock-timeout");
+
+impl Check for RequireLockTimeoutCheck {
+    fn options(&self) -> &'static [CheckOption] {
+        OPTIONS
+    }
+
+    fn check(&self, node: &NodeEnum, config: &Config, ctx: &MigrationContext) -> Vec<Violation> {
+        let Some(statement) = lock_taking_statement(node, ctx) else {
+            return vec![];
+        };
+        let options = config.check_options(self.name());
+        let max_ms = |key: &str| options.integer(key).and_then(|ms| u64::try_from(ms).ok());
+        let set_local = if ctx.in_transaction() {
+            "SET LOCAL"
+        } else {
+            "SET"
+        };
+
+        let mut violations = vec![];
+        if let Some(problem) = timeout_problem(ctx, "lock_timeout", max_ms("max_lock_timeout_ms")) {
+            let timeout = max_ms("max_lock_timeout_ms").map_or_else(
+                || SUGGESTED_LOCK_TIMEOUT.to_string(),
+                |ms| format!("{ms}ms"),
+            );
+            violations.push(Violation::new(
+                format!("{statement} without lock_timeout"),
+                format!(
+                    "{statement} runs {problem}. If a long-running query holds a conflicting lock, \
+                    the statement waits for it, and every query that needs the table queues up behind the waiting statement."
+                ),
+                format!(
+                    r"Set a lock_timeout before the statement, so it gives up instead of blocking traffic:
+   {set_local} lock_timeout = '{timeout}';
+
+If the statement times out, retry the migration when the table is less busy."
+                ),
+            ));
+        }
+
+        if let Some(max) = max_ms("max_statement_timeout_ms")
+            && let Some(problem) = timeout_problem(ctx, "statement_timeout", Some(max))
+        {
+            violations.push(Violation::new(
+                format!("{statement} without statement_timeout"),
+                format!(
+                    "{statement} runs {problem}. A statement that holds its lock for longer than expected \
+                    blocks queries on the table until it finishes."
+                ),
+                format!(
+                    r"Set a statement_timeout before the statement:
+   {set_local} statement_timeout = '{max}ms';"
+                ),
+            ));
+        }
+        violations
+    }
+}
+
+/// Why `setting` doesn't protect the statement, or `None` when it does.
+fn timeout_problem(ctx: &MigrationContext, setting: &str, max_ms: Option<u64>) -> Option<String> {
+    let Some(value) = ctx.setting(setting) else {
+        return Some(format!("before any {setting} is set"));
+    };
+    // Values we can't read are given the benefit of the doubt
+    let ms = timeout_ms(value)?;
+    if ms == 0 {
+        return Some(format!("with {setting} disabled ('{value}')"));
+    }
+    max_ms
+        .filter(|max| ms > *max)
+        .map(|max| format!("with {setting} '{value}', above the maximum of {max}ms"))
+}
+
+/// Describe a statement that waits for a lock other queries queue behind,
+/// e.g. "ALTER TABLE on 'users'"; `None` for other statements.
+fn lock_taking_statement(node: &NodeEnum, ctx: &MigrationContext) -> Option<String> {
+    let on = |statement: &str, table: String| {
+        // Nothing else can see a table created earlier in the migration
+        (!ctx.is_new_table(&table)).then(|| format!("{statement} on '{table}'"))
+    };
+
+    match node {
+        NodeEnum::AlterTableStmt(alter) => {
+            let statement = if alter.objtype == ObjectType::ObjectIndex as i32 {
+                "ALTER INDEX"
+            } else {
+                "ALTER TABLE"
+            };
+            on(statement, range_var_name(alter.relation.as_ref()?))
+        }
+        NodeEnum::RenameStmt(rename) => on("RENAME", range_var_name(rename.relation.as_ref()?)),
+        NodeEnum::IndexStmt(index) if !index.concurrent => {
+            on("CREATE INDEX", range_var_name(index.relation.as_ref()?))
+        }
+        NodeEnum::CreateTrigStmt(trigger) => {
+            on("CREATE TRIGGER", range_var_name(trigger.relation.as_ref()?))
+        }
+        NodeEnum::RefreshMatViewStmt(refresh) if !refresh.concurrent => on(
+            "REFRESH MATERIALIZED VIEW",
+            range_var_name(refresh.relation.as_ref()?),
+        ),
+        NodeEnum::ReindexStmt(reindex) => {
+            let concurrent = reindex.params.iter().any(|p| {
+                matches!(&p.node, Some(NodeEnum::DefElem(elem)) if elem.defname == "concurrently")
+            });
+            if concurrent {
+                return None;
+            }
+            match reindex.relation.as_ref() {
+                Some(relation) => on("REINDEX", range_var_name(relation)),
+                None => Some("REINDEX".to_string()),
+            }
+        }
+        NodeEnum::DropStmt(drop) => {
+            let statement = match drop.remove_type {
+                x if x == ObjectType::ObjectTable as i32 => "DROP TABLE",
+                x if x == ObjectType::ObjectIndex as i32 && !drop.concurrent => "DROP INDEX",
+                x if x == ObjectType::ObjectView as i32 => "DROP VIEW",
+                x if x == ObjectType::ObjectMatview as i32 => "DROP MATERIALIZED VIEW",
+                x if x == ObjectType::ObjectForeignTable as i32 => "DROP FOREIGN TABLE",
+                _ => return None,
+            };
+            let names: Vec<String> = drop_object_names(&drop.objects)
+                .into_iter()
+                .filter(|name| !ctx.is_new_table(name))
+                .collect();
+            (!names.is_empty()).then(|| format!("{statement} on '{}'", names.join("', '")))
+        }
+        NodeEnum::TruncateStmt(truncate) => relations("TRUNCATE", &truncate.relations, ctx),
+        NodeEnum::LockStmt(lock) if !lock.nowait => relations("LOCK TABLE", &lock.relations, ctx),
+        _ => None,
+    }
+}
+
+/// "TRUNCATE on 'a', 'b'" for the tables in `relations` that existed before
+/// the migration.
+fn relations(statement: &str, relations: &[Node], ctx: &MigrationContext) -> Option<String> {
+    let names: Vec<String> = relations
+        .iter()
+        .filter_map(|n| match &n.node {
+            Some(NodeEnum::RangeVar(rv)) => Some(range_var_name(rv)),
+            _ => None,
+        })
+        .filter(|name| !ctx.is_new_table(name))
+        .collect();
+    (!names.is_empty()).then(|| format!("{statement} on '{}'", names.join("', '")))
+}
+
+/// A `lock_timeout` / `statement_timeout` value in milliseconds, e.g. 5000 for
+/// '5s'. Bare numbers are milliseconds; `None` when the value can't be read.
+fn timeout_ms(value: &str) -> Option<u64> {
+    let value = value.trim().to_ascii_lowercase();
+    let digits = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (amount, unit) = value.split_at(digits);
+    let amount: u64 = amount.parse().ok()?;
+    let scale: u64 = match unit.trim() {
+        "" | "ms" => 1,
+        "us" => return Some(amount.div_ceil(1000)),
+        "s" => 1000,
+        "min" => 60 * 1000,
+        "h" => 60 * 60 * 1000,
+        "d" => 24 * 60 * 60 * 1000,
+        _ => return None,
+    };
+    amount.checked_mul(scale)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checks::Registry;
+    use crate::checks::test_utils::config_with_options;
+
+    /// Run the check over every statement of `sql`, like a migration file.
+    fn check_file(sql: &str, config: &Config, ctx: &MigrationContext) -> Vec<Violation> {
+        let config = Config {
+            enable_checks: vec!["RequireLockTimeoutCheck".to_string()],
+            ..config.clone()
+        };
+        let registry = Registry::with_config(&config);
+        let stmts = pg_query::parse(sql).unwrap().protobuf.stmts;
+        registry
+            .check_stmts_with_context(&stmts, sql, &[], &config, ctx)
+            .into_iter()
+            .map(|(_, v)| v)
+            .collect()
+    }
+
+    fn operations(sql: &str, config: &Config, ctx: &MigrationContext) -> Vec<String> {
+        check_file(sql, config, ctx)
+            .into_iter()
+            .map(|v| v.operation)
+            .collect()
+    }
+
+    #[test]
+    fn test_detects_ddl_before_lock_timeout() {
+        let ops = operations(
+            "ALTER TABLE users ADD COLUMN admin BOOLEAN;
+SET lock_timeout = '5s';
+CREATE INDEX idx_users_email ON users(email);",
+            &Config::default(),
+            &MigrationContext::default(),
+        );
+        assert_eq!(ops, ["ALTER TABLE on 'users' without lock_timeout"]);
+    }
+
+    #[test]
+    fn test_detects_each_lock_taking_statement() {
+        let ops = operations(
+            "DROP TABLE old_users;
+TRUNCATE events;
+ALTER TABLE users RENAME TO accounts;
+REINDEX TABLE orders;
+LOCK TABLE payments;",
+            &Config::default(),
+            &MigrationContext::default(),
+        );
+        assert_eq!(ops.len(), 5);
+        assert_eq!(ops[0], "DROP TABLE on 'old_users' without lock_timeout");
+        assert_eq!(ops[4], "LOCK TABLE on 'payments' without lock_timeout");
+    }
+
+    #[test]
+    fn test_allows_non_blocking_statements() {
+        let ops = operations(
+            "CREATE TABLE posts (id BIGINT PRIMARY KEY);
+ALTER TABLE posts ADD COLUMN title TEXT;
+CREATE INDEX CONCURRENTLY idx_users_email ON users(email);
+LOCK TABLE users NOWAIT;
+INSERT INTO users (id) VALUES (1);",
+            &Config::default(),
+            &MigrationContext::default(),
+        );
+        assert!(ops.is_empty(), "{ops:?}");
+    }
+
+    #[test]
+    fn test_detects_disabled_or_reset_lock_timeout() {
+        let sql = "SET lock_timeout = 0;
+ALTER TABLE users ADD COLUMN admin BOOLEAN;
+SET lock_timeout TO '2s';
+RESET lock_timeout;
+DROP INDEX idx_users_email;";
+        let violations = check_file(sql, &Config::default(), &MigrationContext::default());
+        assert_eq!(violations.len(), 2);
+        assert!(violations[0].problem.contains("lock_timeout disabled"));
+        assert!(
+            violations[1]
+                .problem
+                .contains("before any lock_timeout is set")
+        );
+    }
+
+    #[test]
+    fn test_set_local_counts_only_inside_a_transaction() {
+        let sql = "SET LOCAL lock_timeout = '5s';
+ALTER TABLE users ADD COLUMN admin BOOLEAN;";
+        assert!(operations(sql, &Config::default(), &MigrationContext::default()).is_empty());
+
+        let outside = MigrationContext {
+            run_in_transaction: false,
+            ..MigrationContext::default()
+        };
+        assert_eq!(operations(sql, &Config::default(), &outside).len(), 1);
+
+        let explicit = "BEGIN;
+SET LOCAL lock_timeout = '5s';
+ALTER TABLE users ADD COLUMN admin BOOLEAN;
+COMMIT;
+ALTER TABLE users DROP COLUMN admin;";
+        assert_eq!(
+            operations(explicit, &Config::default(), &outside),
+            ["ALTER TABLE on 'users' without lock_timeout"]
+        );
+    }
+
+    #[test]
+    fn test_max_lock_timeout() {
+        let config = config_with_options("RequireLockTimeoutCheck", "max_lock_timeout_ms = 5000");
+        let sql = |timeout: &str| {
+            format!("SET lock_timeout = '{timeout}';\nALTER TABLE users ADD COLUMN admin BOOLEAN;")
+        };
+        let ctx = MigrationContext::default();
+
+        assert!(operations(&sql("5s"), &config, &ctx).is_empty());
+        assert!(operations(&sql("500ms"), &config, &ctx).is_empty());
+        let violations = check_file(&sql("1min"), &config, &ctx);
+        assert_eq!(violations.len(), 1);
+        assert!(
+            violations[0]
+                .problem
+                .contains("above the maximum of 5000ms")
+        );
+        assert!(
+            violations[0]
+                .safe_alternative
+                .contains("SET LOCAL lock_timeout = '5000ms';")
+        );
+    }
+
+    #[test]
+    fn test_max_statement_timeout_requires_statement_timeout() {
+        let config = config_with_options(
+            "RequireLockTimeoutCheck",
+            "max_statement_timeout_ms = 60000",
+        );
+        let ctx = MigrationContext::default();
+
+        assert_eq!(
+            operations(
+                "SET lock_timeout = '5s';\nALTER TABLE users ADD COLUMN admin BOOLEAN;",
+                &config,
+                &ctx
+            ),
+            ["ALTER TABLE on 'users' without statement_timeout"]
+        );
+        assert!(
+            operations(
+                "SET lock_timeout = '5s';\nSET statement_timeout = '1min';\nALTER TABLE users ADD COLUMN admin BOOLEAN;",
+                &config,
+                &ctx
+            )
+            .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_timeout_ms() {
+        assert_eq!(timeout_ms("5s"), Some(5000));
+        assert_eq!(timeout_ms("250"), Some(250));
+        assert_eq!(timeout_ms("2 min"), Some(120_000));
+        assert_eq!(timeout_ms("1h"), Some(3_600_000));
+        assert_eq!(timeout_ms("100us"), Some(1));
+        assert_eq!(timeout_ms("0"), Some(0));
+        assert_eq!(timeout_ms("1.5s"), None);
+        assert_eq!(timeout_ms("soon"), None);
+    }
+}
@@ -0,0 +1,128 @@
+//! Detection for ALTER TABLE ... SET ACCESS METHOD.
+//!
+//! Changing a table's access method (Postgres 15+) rewrites the whole table in the new
+//! storage format under an ACCESS EXCLUSIVE lock, blocking reads and writes for the
+//! duration. This applies to materialized views as well.
+//!
+//! Tables created in the same migration are skipped: rewriting an empty table is instant.
+
+use crate::checks::pg_helpers::{AlterTableType, NodeEnum, alter_table_cmds};
+use crate::checks::{Check, CheckDoc, Config, MigrationContext, impl_check_doc, lock_violation};
+use crate::violation::Violation;
+
+pub struct SetAccessMethodCheck;
+impl_check_doc!(SetAccessMethodCheck, "set-access-method");
+
+impl Check for SetAccessMethodCheck {
+    fn check(&self, node: &NodeEnum, config: &Config, ctx: &MigrationContext) -> Vec<Violation> {
+        let Some((table_name, cmds)) = alter_table_cmds(node) else {
+            return vec![];
+        };
+
+        // A table created earlier in this migration is empty and not yet visible to others
+        if ctx.is_new_table(&table_name) {
+            return vec![];
+        }
+
+        let Some(severity) = config.lock_severity(&table_name) else {
+            return vec![];
+        };
+
+        cmds.iter()
+            .filter(|cmd| cmd.subtype == AlterTableType::AtSetAccessMethod as i32)
+            .map(|cmd| {
+                // SET ACCESS METHOD DEFAULT leaves the name empty
+                let access_method = if cmd.name.is_empty() {
+                    "DEFAULT"
+                } else {
+                    cmd.name.as_str()
+                };
+                let violation = Violation::new(
+                    "SET ACCESS METHOD",
+                    format!(
+                        "Changing the access method of table '{table_name}' to '{access_method}' rewrites the whole table under an ACCESS EXCLUSIVE lock, \
+                        blocking all reads and writes. Duration depends on table size."
+                    ),
+                    format!(
+                        r"There is no way to change a table's access method without rewriting it. For large tables:
+
+1. Create a copy of the table with the new access method:
+   CREATE TABLE {table_name}_new (LIKE {table_name} INCLUDING ALL) USING {access_method};
+
+2. Copy the rows in batches (outside the migration), keeping the copy in sync with triggers
+   or by pausing writes.
+
+3. Swap the tables in one short transaction:
+   ALTER TABLE {table_name} RENAME TO {table_name}_old;
+   ALTER TABLE {table_name}_new RENAME TO {table_name};
+
+Or run the change in a maintenance window when the table can be locked for the whole rewrite."
+                    ),
+                );
+                lock_violation(violation, &table_name, severity, config)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        assert_allows, assert_allows_with_config, assert_allows_with_context,
+        assert_detects_violation_containing,
+    };
+
+    #[test]
+    fn test_detects_set_access_method() {
+        assert_detects_violation_containing!(
+            SetAccessMethodCheck,
+            "ALTER TABLE events SET ACCESS METHOD columnar;",
+            "SET ACCESS METHOD",
+            "table 'events' to 'columnar'"
+        );
+    }
+
+    #[test]
+    fn test_detects_set_access_method_on_materialized_view() {
+        assert_detects_violation_containing!(
+            SetAccessMethodCheck,
+            "ALTER MATERIALIZED VIEW daily_totals SET ACCESS METHOD heap;",
+            "SET ACCESS METHOD",
+            "'daily_totals'"
+        );
+    }
+
+    #[test]
+    fn test_ignores_other_alter_table_operations() {
+        assert_allows!(
+            SetAccessMethodCheck,
+            "ALTER TABLE events SET TABLESPACE archive;"
+        );
+    }
+
+    #[test]
+    fn test_allows_table_created_in_same_migration() {
+        assert_allows_with_context!(
+            SetAccessMethodCheck,
+            "ALTER TABLE events SET ACCESS METHOD columnar;",
+            MigrationContext {
+                created_tables: vec!["events".to_string()],
+                ..MigrationContext::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_small_tables_are_skipped() {
+        let config = Config {
+            small_tables: vec!["countries".to_string()],
+            ..Default::default()
+        };
+        assert_allows_with_config!(
+            SetAccessMethodCheck,
+            "ALTER TABLE countries SET ACCESS METHOD heap;",
+            &config
+        );
+    }
+}
@@ -0,0 +1,138 @@
+//! Detection for ALTER TABLE ... SET LOGGED / SET UNLOGGED.
+//!
+//! Changing whether a table is written to the WAL rewrites the whole table under an
+//! ACCESS EXCLUSIVE lock, blocking reads and writes for the duration. SET LOGGED also
+//! writes every row to the WAL, which can flood replicas; SET UNLOGGED drops the table
+//! from replicas and empties it after a crash.
+//!
+//! Tables created in the same migration are skipped: rewriting an empty table is instant.
+
+use crate::checks::pg_helpers::{AlterTableType, NodeEnum, ObjectType, alter_table_cmds};
+use crate::checks::{Check, CheckDoc, Config, MigrationContext, impl_check_doc, lock_violation};
+use crate::violation::Violation;
+
+pub struct SetLoggedCheck;
+impl_check_doc!(SetLoggedCheck, "set-logged");
+
+impl Check for SetLoggedCheck {
+    fn check(&self, node: &NodeEnum, config: &Config, ctx: &MigrationContext) -> Vec<Violation> {
+        // ALTER SEQUENCE ... SET LOGGED shares the subtype but rewrites nothing
+        let NodeEnum::AlterTableStmt(alter) = node else {
+            return vec![];
+        };
+        if alter.objtype != ObjectType::ObjectTable as i32 {
+            return vec![];
+        }
+        let Some((table_name, cmds)) = alter_table_cmds(node) else {
+            return vec![];
+        };
+
+        // A table created earlier in this migration is empty and not yet visible to others
+        if ctx.is_new_table(&table_name) {
+            return vec![];
+        }
+
+        let Some(severity) = config.lock_severity(&table_name) else {
+            return vec![];
+        };
+
+        cmds.iter()
+            .filter_map(|cmd| match cmd.subtype {
+                x if x == AlterTableType::AtSetLogged as i32 => Some(Violation::new(
+                    "SET LOGGED",
+                    format!(
+                        "Making table '{table_name}' logged rewrites the whole table under an ACCESS EXCLUSIVE lock, blocking all reads and writes. \
+                        Every row is also written to the WAL, which can flood replicas and fill the disk. Duration depends on table size."
+                    ),
+                    persistence_alternative(&table_name, ""),
+                )),
+                x if x == AlterTableType::AtSetUnLogged as i32 => Some(Violation::new(
+                    "SET UNLOGGED",
+                    format!(
+                        "Making table '{table_name}' unlogged rewrites the whole table under an ACCESS EXCLUSIVE lock, blocking all reads and writes. \
+                        Afterwards the table is no longer replicated and is emptied after a crash. Duration depends on table size."
+                    ),
+                    persistence_alternative(&table_name, "UNLOGGED "),
+                )),
+                _ => None,
+            })
+            .map(|v| lock_violation(v, &table_name, severity, config))
+            .collect()
+    }
+}
+
+fn persistence_alternative(table_name: &str, persistence: &str) -> String {
+    format!(
+        r"There is no way to change a table's persistence without rewriting it. For large tables:
+
+1. Create a copy of the table with the new persistence:
+   CREATE {persistence}TABLE {table_name}_new (LIKE {table_name} INCLUDING ALL);
+
+2. Copy the rows in batches (outside the migration), keeping the copy in sync with triggers
+   or by pausing writes.
+
+3. Swap the tables in one short transaction:
+   ALTER TABLE {table_name} RENAME TO {table_name}_old;
+   ALTER TABLE {table_name}_new RENAME TO {table_name};
+
+Or run the change in a maintenance window when the table can be locked for the whole rewrite."
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        assert_allows, assert_allows_with_config, assert_allows_with_context,
+        assert_detects_violation,
+    };
+
+    #[test]
+    fn test_detects_set_logged() {
+        assert_detects_violation!(
+            SetLoggedCheck,
+            "ALTER TABLE events SET LOGGED;",
+            "SET LOGGED"
+        );
+    }
+
+    #[test]
+    fn test_detects_set_unlogged() {
+        assert_detects_violation!(
+            SetLoggedCheck,
+            "ALTER TABLE events SET UNLOGGED;",
+            "SET UNLOGGED"
+        );
+    }
+
+    #[test]
+    fn test_ignores_sequences() {
+        assert_allows!(SetLoggedCheck, "ALTER SEQUENCE events_id_seq SET LOGGED;");
+    }
+
+    #[test]
+    fn test_ignores_other_alter_table_operations() {
+        assert_allows!(SetLoggedCheck, "ALTER TABLE events SET (fillfactor = 70);");
+    }
+
+    #[test]
+    fn test_allows_table_created_in_same_migration() {
+        assert_allows_with_context!(
+            SetLoggedCheck,
+            "ALTER TABLE events SET UNLOGGED;",
+            MigrationContext {
+                created_tables: vec!["events".to_string()],
+                ..MigrationContext::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_small_tables_are_skipped() {
+        let config = Config {
+            small_tables: vec!["countries".to_string()],
+            ..Default::default()
+        };
+        assert_allows_with_config!(SetLoggedCheck, "ALTER TABLE countries SET LOGGED;", &config);
+    }
+}
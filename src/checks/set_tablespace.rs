@@ -0,0 +1,249 @@
+//! Detection for ALTER TABLE / ALTER INDEX ... SET TABLESPACE.
+//!
+//! Moving a relation to another tablespace copies all of its data files while holding
+//! an ACCESS EXCLUSIVE lock, blocking reads and writes for the duration. This applies
+//! to tables, indexes and materialized views alike.
+//!
+//! Relations created in the same migration are skipped (see
+//! [`MigrationContext::is_new_table`]): they hold no data to copy yet. An index is judged by the table it belongs to, when the
+//! earlier migrations show which one that is; otherwise it is always reported.
+
+use crate::checks::pg_helpers::{AlterTableType, NodeEnum, ObjectType, alter_table_cmds};
+use crate::checks::{Check, CheckDoc, Config, MigrationContext, impl_check_doc, lock_violation};
+use crate::violation::{Severity, Violation};
+
+pub struct SetTablespaceCheck;
+impl_check_doc!(SetTablespaceCheck, "set-tablespace");
+
+impl Check for SetTablespaceCheck {
+    fn check(&self, node: &NodeEnum, config: &Config, ctx: &MigrationContext) -> Vec<Violation> {
+        let NodeEnum::AlterTableStmt(alter) = node else {
+            return vec![];
+        };
+        let kind = match alter.objtype {
+            x if x == ObjectType::ObjectTable as i32 => "table",
+            x if x == ObjectType::ObjectIndex as i32 => "index",
+            x if x == ObjectType::ObjectMatview as i32 => "materialized view",
+            _ => return vec![],
+        };
+        let Some((name, cmds)) = alter_table_cmds(node) else {
+            return vec![];
+        };
+
+        // Size and small_tables apply to the table an index belongs to
+        let table = if kind == "index" {
+            ctx.schema.index_table(&name).map(|t| t.name.clone())
+        } else {
+            Some(name.clone())
+        };
+        let severity = match &table {
+            // A relation created earlier in this migration has no data to copy yet
+            Some(table) if ctx.is_new_table(table) => return vec![],
+            Some(table) => match config.lock_severity(table) {
+                Some(severity) => severity,
+                None => return vec![],
+            },
+            None => Severity::Error,
+        };
+
+        cmds.iter()
+            .filter(|cmd| cmd.subtype == AlterTableType::AtSetTableSpace as i32)
+            .map(|cmd| {
+                let tablespace = &cmd.name;
+                let violation = Violation::new(
+                    "SET TABLESPACE",
+                    format!(
+                        "Moving {kind} '{name}' to tablespace '{tablespace}' copies all of its data while holding an ACCESS EXCLUSIVE lock, \
+                        blocking all reads and writes. Duration depends on the size of the {kind}."
+                    ),
+                    if kind == "index" {
+                        index_alternative(&name, tablespace)
+                    } else {
+                        table_alternative(kind, &name, tablespace)
+                    },
+                );
+                // The table's size says little about how long copying one of its indexes takes
+                if kind == "index" {
+                    violation.with_severity(severity)
+                } else {
+                    lock_violation(violation, &name, severity, config)
+                }
+            })
+            .collect()
+    }
+}
+
+fn index_alternative(index_name: &str, tablespace: &str) -> String {
+    format!(
+        r"Build a copy of the index in the new tablespace without blocking writes:
+
+1. Rebuild the index concurrently in the new tablespace (Postgres 14+, outside a transaction):
+   REINDEX (TABLESPACE {tablespace}) INDEX CONCURRENTLY {index_name};
+
+On older versions:
+
+1. Create a new index with the same definition concurrently:
+   CREATE INDEX CONCURRENTLY {index_name}_new ON ... TABLESPACE {tablespace};
+
+2. Drop the old index concurrently and rename the new one:
+   DROP INDEX CONCURRENTLY {index_name};
+   ALTER INDEX {index_name}_new RENAME TO {index_name};"
+    )
+}
+
+fn table_alternative(kind: &str, name: &str, tablespace: &str) -> String {
+    format!(
+        r"Postgres has no online way to move a {kind} to another tablespace. For large relations:
+
+- Use a tool that rebuilds the {kind} online, such as pg_repack:
+   pg_repack --table={name} --tablespace={tablespace}
+
+- Or run the move in a maintenance window when '{name}' can be locked for the whole copy.
+
+To place new relations in the tablespace instead, set default_tablespace or add TABLESPACE {tablespace}
+to CREATE TABLE / CREATE INDEX."
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        assert_allows, assert_allows_with_config, assert_allows_with_context,
+        assert_detects_violation, assert_detects_violation_containing,
+    };
+
+    #[test]
+    fn test_detects_table_set_tablespace() {
+        assert_detects_violation_containing!(
+            SetTablespaceCheck,
+            "ALTER TABLE events SET TABLESPACE archive;",
+            "SET TABLESPACE",
+            "table 'events'",
+            "tablespace 'archive'"
+        );
+    }
+
+    #[test]
+    fn test_detects_index_set_tablespace() {
+        assert_detects_violation_containing!(
+            SetTablespaceCheck,
+            "ALTER INDEX idx_events_created_at SET TABLESPACE archive;",
+            "SET TABLESPACE",
+            "index 'idx_events_created_at'"
+        );
+    }
+
+    #[test]
+    fn test_safe_alternative_depends_on_relation_kind() {
+        let alternative = |sql: &str| {
+            let stmt = crate::checks::test_utils::parse_sql(sql);
+            let violations =
+                SetTablespaceCheck.check(&stmt, &Config::default(), &MigrationContext::default());
+            violations[0].safe_alternative.clone()
+        };
+        assert!(
+            alternative("ALTER INDEX idx_events_created_at SET TABLESPACE archive;")
+                .contains("REINDEX (TABLESPACE archive) INDEX CONCURRENTLY idx_events_created_at;")
+        );
+        assert!(
+            alternative("ALTER TABLE events SET TABLESPACE archive;")
+                .contains("pg_repack --table=events --tablespace=archive")
+        );
+    }
+
+    #[test]
+    fn test_detects_materialized_view_set_tablespace() {
+        assert_detects_violation!(
+            SetTablespaceCheck,
+            "ALTER MATERIALIZED VIEW daily_totals SET TABLESPACE archive;",
+            "SET TABLESPACE"
+        );
+    }
+
+    #[test]
+    fn test_ignores_other_alter_table_operations() {
+        assert_allows!(
+            SetTablespaceCheck,
+            "ALTER TABLE events ADD COLUMN note TEXT;"
+        );
+    }
+
+    #[test]
+    fn test_allows_table_created_in_same_migration() {
+        assert_allows_with_context!(
+            SetTablespaceCheck,
+            "ALTER TABLE events SET TABLESPACE archive;",
+            MigrationContext {
+                created_tables: vec!["events".to_string()],
+                ..MigrationContext::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_index_is_judged_by_its_table() {
+        let schema = |sql: &str| {
+            let mut schema = crate::schema::SchemaCatalog::default();
+            schema.apply_stmts(&pg_query::parse(sql).unwrap().protobuf.stmts);
+            std::sync::Arc::new(schema)
+        };
+        let ctx = MigrationContext {
+            schema: schema(
+                "CREATE TABLE countries (id INT, name TEXT);
+CREATE INDEX idx_countries_name ON countries(name);",
+            ),
+            ..MigrationContext::default()
+        };
+        let config = Config {
+            small_tables: vec!["countries".to_string()],
+            ..Default::default()
+        };
+        let check = |sql: &str, config: &Config| {
+            SetTablespaceCheck.check(&crate::checks::test_utils::parse_sql(sql), config, &ctx)
+        };
+
+        assert!(
+            check(
+                "ALTER INDEX idx_countries_name SET TABLESPACE archive;",
+                &config
+            )
+            .is_empty()
+        );
+        // Index names are not matched against small_tables
+        let config = Config {
+            small_tables: vec!["idx_*".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(
+            check(
+                "ALTER INDEX idx_countries_name SET TABLESPACE archive;",
+                &config
+            )
+            .len(),
+            1
+        );
+        // An index whose table is unknown is always reported
+        assert_eq!(
+            check(
+                "ALTER INDEX idx_orders_total SET TABLESPACE archive;",
+                &config
+            )
+            .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_small_tables_are_skipped() {
+        let config = Config {
+            small_tables: vec!["countries".to_string()],
+            ..Default::default()
+        };
+        assert_allows_with_config!(
+            SetTablespaceCheck,
+            "ALTER TABLE countries SET TABLESPACE archive;",
+            &config
+        );
+    }
+}
@@ -203,3 +203,13 @@ pub fn parse_sql(sql: &str) -> crate::checks::pg_helpers::NodeEnum {
         .node
         .expect("No node")
 }
+
+/// Default config with `options` (TOML) as the `[checks.<check>]` table.
+#[cfg(test)]
+pub fn config_with_options(check: &str, options: &str) -> crate::config::Config {
+    let mut config = crate::config::Config::default();
+    config
+        .checks
+        .insert(check.to_string(), toml::from_str(options).unwrap());
+    config
+}
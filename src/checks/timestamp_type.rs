@@ -11,6 +11,9 @@
 //! TIMESTAMPTZ stores values in UTC internally and converts on input/output based
 //! on the session's timezone setting, providing consistent behavior across timezones.
 //!
+//! Tables listed in `allowed_tables`, or in a schema listed in `ignore_schemas`,
+//! under `[checks.TimestampTypeCheck]` are skipped.
+//!
 //! ## Lock type
 //! None - this is a best practices check, not a locking concern.
 //!
@@ -20,6 +23,7 @@
 //! ## Postgres version specifics
 //! Applies to all Postgres versions.
 
+use crate::checks::options::{ALLOWED_TABLES, CheckOption, IGNORE_SCHEMAS};
 use crate::checks::pg_helpers::{
     NodeEnum, alter_table_cmds, cmd_def_as_column_def, column_type_name, for_each_column_def,
     is_timestamp_without_tz,
@@ -31,7 +35,12 @@ pub struct TimestampTypeCheck;
 impl_check_doc!(TimestampTypeCheck, "timestamp-type");
 
 impl Check for TimestampTypeCheck {
-    fn check(&self, node: &NodeEnum, _config: &Config, _ctx: &MigrationContext) -> Vec<Violation> {
+    fn options(&self) -> &'static [CheckOption] {
+        &[ALLOWED_TABLES, IGNORE_SCHEMAS]
+    }
+
+    fn check(&self, node: &NodeEnum, config: &Config, _ctx: &MigrationContext) -> Vec<Violation> {
+        let options = config.check_options(self.name());
         let is_create = matches!(node, NodeEnum::CreateStmt(_));
 
         // Handle CREATE TABLE via for_each_column_def
@@ -39,7 +48,9 @@ impl Check for TimestampTypeCheck {
             return for_each_column_def(node)
                 .into_iter()
                 .filter_map(|(table, col)| {
-                    if !is_timestamp_without_tz(&column_type_name(col)) {
+                    if !is_timestamp_without_tz(&column_type_name(col))
+                        || options.exempts_table(&table)
+                    {
                         return None;
                     }
                     Some(create_create_table_violation(&table, &col.colname))
@@ -52,6 +63,9 @@ impl Check for TimestampTypeCheck {
             let Some((table_name, cmds)) = alter_table_cmds(node) else {
                 return vec![];
             };
+            if options.exempts_table(&table_name) {
+                return vec![];
+            }
 
             return cmds
                 .iter()
@@ -132,8 +146,10 @@ on the session's timezone setting, providing consistent behavior across timezone
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::checks::test_utils::config_with_options;
     use crate::{
-        assert_allows, assert_detects_n_violations_any_containing, assert_detects_violation,
+        assert_allows, assert_allows_with_config, assert_detects_n_violations_any_containing,
+        assert_detects_violation, assert_detects_violation_with_config,
     };
 
     // === Detection tests ===
@@ -258,4 +274,31 @@ mod tests {
     fn test_ignores_other_statements() {
         assert_allows!(TimestampTypeCheck, "SELECT * FROM users;");
     }
+
+    #[test]
+    fn test_allowed_tables_and_ignored_schemas() {
+        let config = config_with_options(
+            "TimestampTypeCheck",
+            r#"
+allowed_tables = ["legacy_events"]
+ignore_schemas = ["reporting"]
+"#,
+        );
+        assert_allows_with_config!(
+            TimestampTypeCheck,
+            "ALTER TABLE legacy_events ADD COLUMN created_at TIMESTAMP;",
+            &config
+        );
+        assert_allows_with_config!(
+            TimestampTypeCheck,
+            "CREATE TABLE reporting.daily (day TIMESTAMP);",
+            &config
+        );
+        assert_detects_violation_with_config!(
+            TimestampTypeCheck,
+            "ALTER TABLE events ADD COLUMN created_at TIMESTAMP;",
+            "ADD COLUMN with TIMESTAMP",
+            &config
+        );
+    }
 }
@@ -0,0 +1,203 @@
+//! Detection for VALIDATE CONSTRAINT in the same transaction as its NOT VALID add.
+//!
+//! `ADD CONSTRAINT ... NOT VALID` followed by `VALIDATE CONSTRAINT` is the safe way
+//! to add a foreign key or check constraint, but only when the two run in separate
+//! transactions. In one transaction the lock taken by ADD CONSTRAINT (ACCESS
+//! EXCLUSIVE for check constraints, SHARE ROW EXCLUSIVE for foreign keys) is still
+//! held while VALIDATE scans the table, which is the long lock NOT VALID avoids.
+//!
+//! This file-level check pairs each `AT_ValidateConstraint` with an earlier
+//! `AT_AddConstraint` of the same name on the same table that skips validation,
+//! in migrations that run in a transaction. Tables created in the same migration
+//! are skipped.
+
+use crate::ViolationList;
+use crate::checks::pg_helpers::{
+    AlterTableType, NodeEnum, alter_table_cmds, cmd_def_as_constraint,
+};
+use crate::checks::{
+    Check, CheckDoc, Config, MigrationContext, MigrationStmt, impl_check_doc, lock_violation,
+};
+use crate::schema::relation_key;
+use crate::violation::Violation;
+
+pub struct ValidateInSameTransactionCheck;
+impl_check_doc!(
+    ValidateInSameTransactionCheck,
+    "validate-in-same-transaction"
+);
+
+impl Check for ValidateInSameTransactionCheck {
+    fn check(&self, _node: &NodeEnum, _config: &Config, _ctx: &MigrationContext) -> Vec<Violation> {
+        vec![]
+    }
+
+    fn check_file(
+        &self,
+        stmts: &[MigrationStmt<'_>],
+        config: &Config,
+        ctx: &MigrationContext,
+    ) -> ViolationList {
+        // Without a transaction the ADD CONSTRAINT lock is released before VALIDATE
+        if !ctx.run_in_transaction {
+            return vec![];
+        }
+
+        // (table key, constraint name, line) of constraints added NOT VALID
+        let mut not_valid: Vec<(String, String, usize)> = vec![];
+        let mut violations = vec![];
+
+        for stmt in stmts {
+            let Some((table_name, cmds)) = alter_table_cmds(stmt.node) else {
+                continue;
+            };
+            let key = relation_key(&table_name);
+
+            for cmd in cmds {
+                match cmd.subtype {
+                    x if x == AlterTableType::AtAddConstraint as i32 => {
+                        if let Some(constraint) = cmd_def_as_constraint(cmd)
+                            && constraint.skip_validation
+                            && !constraint.conname.is_empty()
+                        {
+                            not_valid.push((key.clone(), constraint.conname.clone(), stmt.line));
+                        }
+                    }
+                    x if x == AlterTableType::AtValidateConstraint as i32 => {
+                        let Some(added_line) = not_valid
+                            .iter()
+                            .find(|(table, name, _)| *table == key && *name == cmd.name)
+                            .map(|(.., line)| *line)
+                        else {
+                            continue;
+                        };
+
+                        // A new table is empty and not yet visible to others
+                        if ctx.is_new_table(&table_name) {
+                            continue;
+                        }
+                        let Some(severity) = config.lock_severity(&table_name) else {
+                            continue;
+                        };
+
+                        let violation = validate_violation(&table_name, &cmd.name, added_line);
+                        violations.push((
+                            stmt.line,
+                            lock_violation(violation, &table_name, severity, config),
+                        ));
+                    }
+                    _ => {}
+                }
+            }
+        }
+        violations
+    }
+}
+
+fn validate_violation(table_name: &str, constraint_name: &str, added_line: usize) -> Violation {
+    Violation::new(
+        "VALIDATE CONSTRAINT in the same transaction as NOT VALID",
+        format!(
+            "Constraint '{constraint_name}' on table '{table_name}' is added NOT VALID on line {added_line} and validated in the same transaction. \
+            The lock taken by ADD CONSTRAINT is held until the migration commits, so the table stays locked while VALIDATE scans every row, \
+            the same long lock that NOT VALID is meant to avoid."
+        ),
+        format!(
+            r"Split the change into two migrations:
+
+1. Add the constraint without validating existing rows:
+   ALTER TABLE {table_name} ADD CONSTRAINT {constraint_name} ... NOT VALID;
+
+2. Validate it in a separate migration (acquires ShareUpdateExclusiveLock only):
+   ALTER TABLE {table_name} VALIDATE CONSTRAINT {constraint_name};"
+        ),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checks::Registry;
+
+    /// Run the check over a whole migration, like a migration file.
+    fn check_file(sql: &str, config: &Config, ctx: &MigrationContext) -> Vec<(usize, Violation)> {
+        let config = Config {
+            enable_checks: vec!["ValidateInSameTransactionCheck".to_string()],
+            ..config.clone()
+        };
+        let registry = Registry::with_config(&config);
+        let stmts = pg_query::parse(sql).unwrap().protobuf.stmts;
+        registry.check_stmts_with_context(&stmts, sql, &[], &config, ctx)
+    }
+
+    const FOREIGN_KEY: &str = "ALTER TABLE orders ADD CONSTRAINT fk_orders_user
+    FOREIGN KEY (user_id) REFERENCES users(id) NOT VALID;
+ALTER TABLE orders VALIDATE CONSTRAINT fk_orders_user;";
+
+    #[test]
+    fn test_detects_validate_after_not_valid_foreign_key() {
+        let violations = check_file(
+            FOREIGN_KEY,
+            &Config::default(),
+            &MigrationContext::default(),
+        );
+        assert_eq!(violations.len(), 1);
+        let (line, violation) = &violations[0];
+        assert_eq!(*line, 3);
+        assert_eq!(
+            violation.operation,
+            "VALIDATE CONSTRAINT in the same transaction as NOT VALID"
+        );
+        assert!(violation.problem.starts_with(
+            "Constraint 'fk_orders_user' on table 'orders' is added NOT VALID on line 1"
+        ));
+    }
+
+    #[test]
+    fn test_detects_validate_after_not_valid_check_constraint() {
+        let sql =
+            "ALTER TABLE public.orders ADD CONSTRAINT check_amount CHECK (amount > 0) NOT VALID;
+ALTER TABLE orders VALIDATE CONSTRAINT check_amount;";
+        let violations = check_file(sql, &Config::default(), &MigrationContext::default());
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].0, 2);
+    }
+
+    #[test]
+    fn test_allows_migrations_outside_a_transaction() {
+        let ctx = MigrationContext {
+            run_in_transaction: false,
+            ..MigrationContext::default()
+        };
+        assert!(check_file(FOREIGN_KEY, &Config::default(), &ctx).is_empty());
+    }
+
+    #[test]
+    fn test_allows_validate_of_a_constraint_added_elsewhere() {
+        let sql = "ALTER TABLE orders ADD CONSTRAINT check_amount CHECK (amount > 0) NOT VALID;
+ALTER TABLE orders VALIDATE CONSTRAINT fk_orders_user;
+ALTER TABLE payments VALIDATE CONSTRAINT check_amount;";
+        assert!(check_file(sql, &Config::default(), &MigrationContext::default()).is_empty());
+    }
+
+    #[test]
+    fn test_allows_validate_before_not_valid() {
+        let sql = "ALTER TABLE orders VALIDATE CONSTRAINT check_amount;
+ALTER TABLE orders ADD CONSTRAINT check_amount CHECK (amount > 0) NOT VALID;";
+        assert!(check_file(sql, &Config::default(), &MigrationContext::default()).is_empty());
+    }
+
+    #[test]
+    fn test_skips_new_and_small_tables() {
+        let sql = "CREATE TABLE orders (id BIGINT PRIMARY KEY, amount INT);
+ALTER TABLE orders ADD CONSTRAINT check_amount CHECK (amount > 0) NOT VALID;
+ALTER TABLE orders VALIDATE CONSTRAINT check_amount;
+ALTER TABLE countries ADD CONSTRAINT check_code CHECK (code <> '') NOT VALID;
+ALTER TABLE countries VALIDATE CONSTRAINT check_code;";
+        let config = Config {
+            small_tables: vec!["countries".to_string()],
+            ..Config::default()
+        };
+        assert!(check_file(sql, &config, &MigrationContext::default()).is_empty());
+    }
+}
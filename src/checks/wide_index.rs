@@ -1,6 +1,7 @@
 //! Detection for wide indexes (indexes with 4+ columns).
 //!
-//! This check identifies `CREATE INDEX` statements with more than 3 columns.
+//! This check identifies `CREATE INDEX` statements with more than 3 columns, or
+//! more than the `max_columns` option in `[checks.WideIndexCheck]`.
 //!
 //! Wide indexes (with 4+ columns) are often ineffective because Postgres can only use
 //! the index efficiently when filtering on the leftmost columns in order. They also
@@ -9,20 +10,36 @@
 //! Consider using partial indexes, separate narrower indexes, or rethinking your
 //! query patterns instead.
 
+use crate::checks::options::{ALLOWED_TABLES, CheckOption, OptionKind};
 use crate::checks::pg_helpers::{NodeEnum, range_var_name};
 use crate::checks::{Check, CheckDoc, Config, MigrationContext, impl_check_doc};
 use crate::violation::Violation;
 
 const MAX_COLUMNS: usize = 3;
 
+const OPTIONS: &[CheckOption] = &[
+    CheckOption {
+        name: "max_columns",
+        kind: OptionKind::Integer,
+        description: "Most columns an index may have before it is reported (default: 3).",
+    },
+    ALLOWED_TABLES,
+];
+
 pub struct WideIndexCheck;
 impl_check_doc!(WideIndexCheck, "wide-index");
 
 impl Check for WideIndexCheck {
-    fn check(&self, node: &NodeEnum, _config: &Config, _ctx: &MigrationContext) -> Vec<Violation> {
+    fn options(&self) -> &'static [CheckOption] {
+        OPTIONS
+    }
+
+    fn check(&self, node: &NodeEnum, config: &Config, _ctx: &MigrationContext) -> Vec<Violation> {
         let NodeEnum::IndexStmt(index_stmt) = node else {
             return vec![];
         };
+        let options = config.check_options(self.name());
+        let max_columns = options.integer("max_columns").unwrap_or(MAX_COLUMNS);
 
         let column_names: Vec<String> = index_stmt
             .index_params
@@ -41,7 +58,7 @@ impl Check for WideIndexCheck {
 
         let column_count = column_names.len();
 
-        if column_count <= MAX_COLUMNS {
+        if column_count <= max_columns {
             return vec![];
         }
 
@@ -50,6 +67,9 @@ impl Check for WideIndexCheck {
             .as_ref()
             .map(range_var_name)
             .unwrap_or_default();
+        if options.exempts_table(&table_name) {
+            return vec![];
+        }
         let index_name = if index_stmt.idxname.is_empty() {
             "<unnamed>".to_string()
         } else {
@@ -61,7 +81,7 @@ impl Check for WideIndexCheck {
             "CREATE INDEX with too many columns",
             format!(
                 "Index '{index_name}' on table '{table_name}' has {column_count} columns ({columns_list}). \
-                Wide indexes (4+ columns) are rarely effective because Postgres can only use them efficiently \
+                Wide indexes (more than {max_columns} columns) are rarely effective because Postgres can only use them efficiently \
                 when filtering on leftmost columns in order. They also increase storage costs and slow down writes."
             ),
             format!(
@@ -101,7 +121,11 @@ Note: Multi-column indexes are occasionally useful (e.g., for composite foreign
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{assert_allows, assert_detects_violation, assert_detects_violation_containing};
+    use crate::checks::test_utils::config_with_options;
+    use crate::{
+        assert_allows, assert_allows_with_config, assert_detects_violation,
+        assert_detects_violation_containing, assert_detects_violation_with_config,
+    };
 
     #[test]
     fn test_detects_index_with_four_columns() {
@@ -181,4 +205,38 @@ mod tests {
             "<expr>"
         );
     }
+
+    #[test]
+    fn test_max_columns_option() {
+        let config = config_with_options("WideIndexCheck", "max_columns = 4");
+        assert_allows_with_config!(
+            WideIndexCheck,
+            "CREATE INDEX idx ON users(a, b, c, d);",
+            &config
+        );
+        assert_detects_violation_with_config!(
+            WideIndexCheck,
+            "CREATE INDEX idx ON users(a, b, c, d, e);",
+            "CREATE INDEX with too many columns",
+            &config
+        );
+
+        let config = config_with_options("WideIndexCheck", "max_columns = 1");
+        assert_detects_violation_with_config!(
+            WideIndexCheck,
+            "CREATE INDEX idx ON users(a, b);",
+            "CREATE INDEX with too many columns",
+            &config
+        );
+    }
+
+    #[test]
+    fn test_allowed_tables_option() {
+        let config = config_with_options("WideIndexCheck", r#"allowed_tables = ["events"]"#);
+        assert_allows_with_config!(
+            WideIndexCheck,
+            "CREATE INDEX idx ON events(a, b, c, d);",
+            &config
+        );
+    }
 }
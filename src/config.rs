@@ -2,11 +2,33 @@
 //!
 //! This module handles loading and validating diesel-guard.toml configuration files.
 
-use camino::{Utf8Path, Utf8PathBuf};
+use crate::checks::options::CheckOptions;
+use crate::table_stats::{TableSize, TableStats};
+use crate::violation::Severity;
+use camino::{Utf8Component, Utf8Path, Utf8PathBuf};
 use miette::Diagnostic;
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::sync::Arc;
 use thiserror::Error;
 
+/// Generate help text for an invalid `[checks.<CheckName>]` option
+fn check_options_help(check: &str) -> String {
+    let options = crate::checks::Registry::builtin_check_options(check).unwrap_or_default();
+    if options.is_empty() {
+        return format!("{check} has no options.");
+    }
+    let list: Vec<String> = options
+        .iter()
+        .map(|o| format!("{} ({})", o.name, o.kind.type_name()))
+        .collect();
+    format!(
+        "{check} supports: {}. Run `diesel-guard explain {check}` for details.",
+        list.join(", ")
+    )
+}
+
 /// Generate help text for invalid check names from the registry
 fn valid_check_names_help() -> String {
     format!(
@@ -29,14 +51,47 @@ pub enum ConfigError {
     #[error("Invalid timestamp format: {0}")]
     InvalidTimestampFormat(String),
 
-    #[error("Missing required field 'framework' in diesel-guard.toml")]
+    #[error("No migration framework set in diesel-guard.toml")]
     MissingFramework,
 
-    #[error("Invalid framework \"{framework}\". Expected \"diesel\" or \"sqlx\".")]
+    #[error(
+        "Invalid framework \"{framework}\". Expected \"diesel\", \"sqlx\", \"refinery\", \"seaorm\", \"golang-migrate\", \"dbmate\" or \"flyway\"."
+    )]
     InvalidFramework { framework: String },
 
     #[error("enable_checks and disable_checks cannot both be set")]
     ConflictingCheckConfig,
+
+    #[error("Could not tell which migration framework this project uses: {candidates}")]
+    AmbiguousFramework {
+        candidates: String,
+        evidence: String,
+    },
+
+    #[error("[[migrations]] entry is missing 'path'")]
+    MissingMigrationsPath,
+
+    #[error("{path} has no diesel-guard table")]
+    MissingCargoMetadata { path: String },
+
+    #[error("[[overrides]] entry is missing 'files'")]
+    MissingOverrideFiles,
+
+    #[error("Invalid [[overrides]] pattern \"{pattern}\": {reason}")]
+    InvalidOverridePattern { pattern: String, reason: String },
+
+    #[error("Invalid table pattern \"{pattern}\": {reason}")]
+    InvalidTablePattern { pattern: String, reason: String },
+
+    #[error("Invalid table_stats file {path}: {reason}")]
+    InvalidTableStats { path: String, reason: String },
+
+    #[error("Invalid option '{option}' in [checks.{check}]: {reason}")]
+    InvalidCheckOption {
+        check: String,
+        option: String,
+        reason: String,
+    },
 }
 
 impl Diagnostic for ConfigError {
@@ -55,6 +110,30 @@ impl Diagnostic for ConfigError {
             Self::ConflictingCheckConfig => {
                 Some(Box::new("diesel_guard::config::conflicting_check_config"))
             }
+            Self::AmbiguousFramework { .. } => {
+                Some(Box::new("diesel_guard::config::ambiguous_framework"))
+            }
+            Self::MissingMigrationsPath => {
+                Some(Box::new("diesel_guard::config::missing_migrations_path"))
+            }
+            Self::MissingCargoMetadata { .. } => {
+                Some(Box::new("diesel_guard::config::missing_cargo_metadata"))
+            }
+            Self::MissingOverrideFiles => {
+                Some(Box::new("diesel_guard::config::missing_override_files"))
+            }
+            Self::InvalidOverridePattern { .. } => {
+                Some(Box::new("diesel_guard::config::invalid_override_pattern"))
+            }
+            Self::InvalidTablePattern { .. } => {
+                Some(Box::new("diesel_guard::config::invalid_table_pattern"))
+            }
+            Self::InvalidTableStats { .. } => {
+                Some(Box::new("diesel_guard::config::invalid_table_stats"))
+            }
+            Self::InvalidCheckOption { .. } => {
+                Some(Box::new("diesel_guard::config::invalid_check_option"))
+            }
         }
     }
 
@@ -65,12 +144,36 @@ impl Diagnostic for ConfigError {
                 "Expected format: YYYYMMDDHHMMSS, YYYY_MM_DD_HHMMSS, or YYYY-MM-DD-HHMMSS (e.g., 20240101000000, 2024_01_01_000000, or 2024-01-01-000000)",
             )),
             Self::MissingFramework => Some(Box::new(
-                "Add one of the following to your diesel-guard.toml file:\n  framework = \"diesel\"\n  framework = \"sqlx\"",
+                "Add one of the following to your diesel-guard.toml file:\n  framework = \"diesel\"\n  framework = \"sqlx\"\n  framework = \"refinery\"\n  framework = \"seaorm\"\n  framework = \"golang-migrate\"\n  framework = \"dbmate\"\n  framework = \"flyway\"",
+            )),
+            Self::InvalidFramework { .. } => Some(Box::new(
+                "Valid values: \"diesel\", \"sqlx\", \"refinery\", \"seaorm\", \"golang-migrate\", \"dbmate\", \"flyway\"",
             )),
-            Self::InvalidFramework { .. } => Some(Box::new("Valid values: \"diesel\", \"sqlx\"")),
             Self::ConflictingCheckConfig => Some(Box::new(
                 "Use either enable_checks (whitelist) or disable_checks (blacklist), not both.",
             )),
+            Self::AmbiguousFramework { evidence, .. } => Some(Box::new(format!(
+                "Found: {evidence}.\nSet the 'framework' field in diesel-guard.toml to pick one."
+            ))),
+            Self::MissingMigrationsPath => Some(Box::new(
+                "Each [[migrations]] entry needs the directory it covers, e.g. path = \"services/billing/migrations\"",
+            )),
+            Self::MissingCargoMetadata { .. } => Some(Box::new(
+                "Add a [package.metadata.diesel-guard] or [workspace.metadata.diesel-guard] table, or point --config at a diesel-guard.toml",
+            )),
+            Self::MissingOverrideFiles => Some(Box::new(
+                "List the migrations the entry applies to, e.g. files = [\"migrations/*_seed_*\"]",
+            )),
+            Self::InvalidOverridePattern { .. } => Some(Box::new(
+                "Patterns support *, ? and [...]; those containing '/' match paths, the others match migration names",
+            )),
+            Self::InvalidTablePattern { .. } => Some(Box::new(
+                "small_tables and large_tables entries support *, ? and [...], e.g. \"lookup_*\" or \"audit.*\"",
+            )),
+            Self::InvalidTableStats { .. } => Some(Box::new(
+                "Expected a JSON array of objects or a CSV file with a header row, with the columns table, rows and bytes (or relname, reltuples and pg_total_relation_size)",
+            )),
+            Self::InvalidCheckOption { check, .. } => Some(Box::new(check_options_help(check))),
             _ => None,
         }
     }
@@ -78,11 +181,13 @@ impl Diagnostic for ConfigError {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
-    /// Migration framework being used (required)
+    /// Migration framework being used
     ///
-    /// Valid values: "diesel" or "sqlx"
+    /// Valid values: "diesel", "sqlx", "refinery", "seaorm", "golang-migrate", "dbmate" or "flyway"
     ///
-    /// This field is required and must be explicitly set in diesel-guard.toml
+    /// Empty when diesel-guard.toml doesn't set it; the CLI then detects the
+    /// framework from the project (see [`crate::detect`]).
+    #[serde(default)]
     pub framework: String,
 
     /// Skip migrations before this timestamp
@@ -115,6 +220,12 @@ pub struct Config {
     #[serde(default)]
     pub warn_checks: Vec<String>,
 
+    /// Opt-in checks to run, e.g. `RequireLockTimeoutCheck`.
+    ///
+    /// Opt-in checks are off unless they are listed here or in `enable_checks`.
+    #[serde(default)]
+    pub opt_in_checks: Vec<String>,
+
     /// Directory containing custom Rhai check scripts (.rhai files)
     #[serde(default)]
     pub custom_checks_dir: Option<String>,
@@ -123,41 +234,245 @@ pub struct Config {
     /// When set, checks that are safe from that version onward are skipped.
     #[serde(default)]
     pub postgres_version: Option<u32>,
+
+    /// Tables small enough that locking them is harmless, as glob patterns.
+    ///
+    /// Checks that report long locks, rewrites or scans skip these tables (or
+    /// report them as warnings, see `small_tables_mode`). Patterns without a
+    /// schema match the table in any schema.
+    #[serde(default)]
+    pub small_tables: Vec<String>,
+
+    /// Tables never treated as small, even when `small_tables` matches them.
+    #[serde(default)]
+    pub large_tables: Vec<String>,
+
+    /// What lock checks do with violations on `small_tables`.
+    #[serde(default)]
+    pub small_tables_mode: SmallTablesMode,
+
+    /// Snapshot of production table sizes (JSON or CSV), relative to the config file.
+    ///
+    /// Lock, rewrite and scan violations then mention the table's size, and
+    /// `large_table_rows` / `large_table_bytes` decide their severity.
+    #[serde(default)]
+    pub table_stats: Option<String>,
+
+    /// Row count from which a table in `table_stats` is large.
+    #[serde(default = "default_large_table_rows")]
+    pub large_table_rows: u64,
+
+    /// Total size in bytes from which a table in `table_stats` is large.
+    #[serde(default = "default_large_table_bytes")]
+    pub large_table_bytes: u64,
+
+    /// Contents of `table_stats`, read when the config is loaded.
+    #[serde(skip)]
+    pub table_sizes: Option<Arc<TableStats>>,
+
+    /// Migration directories checked together, each with its own settings.
+    ///
+    /// `check` without a path lints all of them into one report.
+    #[serde(default)]
+    pub migrations: Vec<MigrationRoot>,
+
+    /// Check settings for the migrations matching some patterns.
+    #[serde(default)]
+    pub overrides: Vec<Override>,
+
+    /// Options for individual checks, from `[checks.<CheckName>]` tables.
+    #[serde(default)]
+    pub checks: BTreeMap<String, toml::Table>,
+
+    /// File the config was loaded from; `None` when no config file was found.
+    ///
+    /// Relative paths in the file are resolved against its directory.
+    #[serde(skip)]
+    pub source: Option<Utf8PathBuf>,
+}
+
+/// How lock checks treat tables listed in `small_tables`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SmallTablesMode {
+    /// Don't report the violation.
+    #[default]
+    Skip,
+    /// Report the violation as a warning.
+    Warn,
+}
+
+/// One `[[migrations]]` entry: a migration directory and the settings that
+/// override the top-level ones for it.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MigrationRoot {
+    /// Migration directory, relative to the config file.
+    #[serde(default)]
+    pub path: String,
+    pub framework: Option<String>,
+    pub start_after: Option<String>,
+    pub check_down: Option<bool>,
+    pub postgres_version: Option<u32>,
+    /// Replaces the top-level `enable_checks` and `disable_checks`.
+    pub enable_checks: Option<Vec<String>>,
+    /// Replaces the top-level `enable_checks` and `disable_checks`.
+    pub disable_checks: Option<Vec<String>>,
+    pub warn_checks: Option<Vec<String>>,
+}
+
+/// One `[[overrides]]` entry: check settings for the migrations whose path or
+/// name matches one of `files`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Override {
+    /// Glob patterns. Patterns containing `/` match migration paths (relative
+    /// to the config file); the others match a file or directory name.
+    #[serde(default)]
+    pub files: Vec<String>,
+    /// Replaces the `enable_checks` and `disable_checks` in effect.
+    pub enable_checks: Option<Vec<String>>,
+    /// Replaces the `enable_checks` and `disable_checks` in effect.
+    pub disable_checks: Option<Vec<String>>,
+    pub warn_checks: Option<Vec<String>>,
+    pub postgres_version: Option<u32>,
+}
+
+impl Override {
+    /// Whether `path` is one of the migrations this entry applies to.
+    ///
+    /// A path pattern matches the migration or a directory containing it, so
+    /// `migrations/*_seed_*` covers `migrations/2024_01_01_000000_seed_users/up.sql`.
+    pub fn matches(&self, path: &Utf8Path) -> bool {
+        let options = glob::MatchOptions {
+            require_literal_separator: true,
+            ..glob::MatchOptions::new()
+        };
+        let path = normalize(path);
+        self.files.iter().any(|pattern| {
+            if pattern.contains('/') {
+                let Ok(pattern) = glob::Pattern::new(normalize(Utf8Path::new(pattern)).as_str())
+                else {
+                    return false;
+                };
+                path.ancestors()
+                    .any(|p| pattern.matches_with(p.as_str(), options))
+            } else {
+                let Ok(pattern) = glob::Pattern::new(pattern) else {
+                    return false;
+                };
+                path.components()
+                    .any(|c| pattern.matches_with(c.as_str(), options))
+            }
+        })
+    }
+
+    /// Whether the entry runs `check_name` on the migrations it matches.
+    fn enables(&self, check_name: &str) -> bool {
+        match (&self.enable_checks, &self.disable_checks) {
+            (Some(enabled), _) => enabled.iter().any(|c| c == check_name),
+            (None, Some(disabled)) => !disabled.iter().any(|c| c == check_name),
+            (None, None) => false,
+        }
+    }
 }
 
 impl Config {
-    /// Load config from diesel-guard.toml in current directory
-    /// Returns default config if file doesn't exist
+    /// Load the config found by [`Config::find`] from the current directory.
+    /// Returns default settings with no framework if there is none.
     pub fn load() -> Result<Self, ConfigError> {
-        let config_path = Utf8PathBuf::from("diesel-guard.toml");
-
-        if !config_path.exists() {
-            return Ok(Self::default());
+        match Self::find(Utf8Path::new("."))? {
+            Some(path) => Self::load_from_path(&path),
+            None => Ok(Self {
+                framework: String::new(),
+                ..Self::default()
+            }),
         }
+    }
 
-        Self::load_from_path(&config_path)
+    /// Find the config file for `dir`.
+    ///
+    /// Looks in `dir` and then its parents, stopping at the repository root
+    /// (the first directory containing `.git`). In each directory a
+    /// diesel-guard.toml wins over a Cargo.toml with a diesel-guard table.
+    /// Returned paths are relative when `dir` is.
+    pub fn find(dir: &Utf8Path) -> Result<Option<Utf8PathBuf>, ConfigError> {
+        let absolute = camino::absolute_utf8(dir)?;
+        // Keep "diesel-guard.toml" rather than "./diesel-guard.toml" for the current directory
+        let mut current = if dir == "." {
+            Utf8PathBuf::new()
+        } else {
+            dir.to_owned()
+        };
+        for ancestor in absolute.ancestors() {
+            let config_file = current.join(CONFIG_FILE_NAME);
+            if config_file.is_file() {
+                return Ok(Some(config_file));
+            }
+            let manifest = current.join("Cargo.toml");
+            if manifest.is_file() && cargo_metadata_table(&manifest)?.is_some() {
+                return Ok(Some(manifest));
+            }
+            if ancestor.join(".git").exists() {
+                break;
+            }
+            current = current.join("..");
+        }
+        Ok(None)
     }
 
-    /// Load config from specific path (useful for testing)
+    /// Load config from a diesel-guard.toml, or from the
+    /// `[package.metadata.diesel-guard]` / `[workspace.metadata.diesel-guard]`
+    /// table of a Cargo.toml.
     pub fn load_from_path(path: &Utf8Path) -> Result<Self, ConfigError> {
-        let contents = std::fs::read_to_string(path)?;
-        let config: Config = toml::from_str(&contents).map_err(|e| {
-            // Check if the error is due to missing framework field
-            if e.to_string().contains("missing field `framework`") {
-                ConfigError::MissingFramework
-            } else {
-                ConfigError::ParseError(e)
-            }
-        })?;
+        let mut config: Config = if path.file_name() == Some("Cargo.toml") {
+            cargo_metadata_table(path)?
+                .ok_or_else(|| ConfigError::MissingCargoMetadata {
+                    path: path.to_string(),
+                })?
+                .try_into()?
+        } else {
+            let contents = std::fs::read_to_string(path)?;
+            toml::from_str(&contents)?
+        };
         config.validate()?;
+
+        let dir = path.parent().unwrap_or(Utf8Path::new(""));
+        if let Some(checks_dir) = &mut config.custom_checks_dir {
+            *checks_dir = dir.join(&*checks_dir).into_string();
+        }
+        for root in &mut config.migrations {
+            root.path = dir.join(&root.path).into_string();
+        }
+        if let Some(stats) = &mut config.table_stats {
+            *stats = dir.join(&*stats).into_string();
+            config.table_sizes = Some(Arc::new(TableStats::load(Utf8Path::new(stats))?));
+        }
+        for pattern in config.overrides.iter_mut().flat_map(|o| &mut o.files) {
+            if pattern.contains('/') {
+                *pattern = dir.join(&*pattern).into_string();
+            }
+        }
+        config.source = Some(path.to_owned());
         Ok(config)
     }
 
+    /// Directory of the config file, where default paths such as `migrations/`,
+    /// the baseline and the lock file are resolved.
+    ///
+    /// Empty (the current directory) when no config file was found or it is in
+    /// the current directory.
+    pub fn project_dir(&self) -> &Utf8Path {
+        self.source
+            .as_deref()
+            .and_then(Utf8Path::parent)
+            .unwrap_or(Utf8Path::new(""))
+    }
+
     /// Validate configuration values
     fn validate(&self) -> Result<(), ConfigError> {
-        // Validate framework field
+        // Validate framework field (empty: detected from the project)
         match self.framework.as_str() {
-            "diesel" | "sqlx" => {}
+            "" | "diesel" | "sqlx" | "refinery" | "seaorm" | "golang-migrate" | "dbmate"
+            | "flyway" => {}
             _ => {
                 return Err(ConfigError::InvalidFramework {
                     framework: self.framework.clone(),
@@ -169,9 +484,205 @@ impl Config {
             return Err(ConfigError::ConflictingCheckConfig);
         }
 
+        for root in &self.migrations {
+            if root.path.is_empty() {
+                return Err(ConfigError::MissingMigrationsPath);
+            }
+            self.for_root(root).validate()?;
+        }
+
+        for pattern in self.small_tables.iter().chain(&self.large_tables) {
+            if let Err(e) = glob::Pattern::new(pattern) {
+                return Err(ConfigError::InvalidTablePattern {
+                    pattern: pattern.clone(),
+                    reason: e.msg.to_string(),
+                });
+            }
+        }
+
+        // Custom check names are validated by SafetyChecker, which knows the scripts
+        for (check, options) in &self.checks {
+            let Some(supported) = crate::checks::Registry::builtin_check_options(check) else {
+                continue;
+            };
+            for (option, value) in options {
+                if let Some(reason) = crate::checks::options::option_error(supported, option, value)
+                {
+                    return Err(ConfigError::InvalidCheckOption {
+                        check: check.clone(),
+                        option: option.clone(),
+                        reason,
+                    });
+                }
+            }
+        }
+
+        for entry in &self.overrides {
+            if entry.files.is_empty() {
+                return Err(ConfigError::MissingOverrideFiles);
+            }
+            if entry.enable_checks.is_some() && entry.disable_checks.is_some() {
+                return Err(ConfigError::ConflictingCheckConfig);
+            }
+            for pattern in &entry.files {
+                if let Err(e) = glob::Pattern::new(pattern) {
+                    return Err(ConfigError::InvalidOverridePattern {
+                        pattern: pattern.clone(),
+                        reason: e.msg.to_string(),
+                    });
+                }
+            }
+        }
+
         Ok(())
     }
 
+    /// The settings for one `[[migrations]]` entry.
+    ///
+    /// Values the entry sets replace the top-level ones. A check list in the
+    /// entry replaces both top-level lists, so an entry's `enable_checks` is
+    /// never combined with a top-level `disable_checks`.
+    #[must_use]
+    pub fn for_root(&self, root: &MigrationRoot) -> Config {
+        let mut config = Config {
+            migrations: Vec::new(),
+            ..self.clone()
+        };
+        if let Some(framework) = &root.framework {
+            config.framework.clone_from(framework);
+        }
+        if root.start_after.is_some() {
+            config.start_after.clone_from(&root.start_after);
+        }
+        if let Some(check_down) = root.check_down {
+            config.check_down = check_down;
+        }
+        if root.postgres_version.is_some() {
+            config.postgres_version = root.postgres_version;
+        }
+        if root.enable_checks.is_some() || root.disable_checks.is_some() {
+            config.enable_checks = root.enable_checks.clone().unwrap_or_default();
+            config.disable_checks = root.disable_checks.clone().unwrap_or_default();
+        }
+        if let Some(warn_checks) = &root.warn_checks {
+            config.warn_checks.clone_from(warn_checks);
+        }
+        config
+    }
+
+    /// The `[[migrations]]` entry whose directory contains `path`.
+    ///
+    /// The deepest entry wins when entries are nested.
+    pub fn root_for(&self, path: &Utf8Path) -> Option<&MigrationRoot> {
+        let path = normalize(path);
+        self.migrations
+            .iter()
+            .filter(|root| path.starts_with(normalize(Utf8Path::new(&root.path))))
+            .max_by_key(|root| normalize(Utf8Path::new(&root.path)).components().count())
+    }
+
+    /// The settings for checking `path`: those of the `[[migrations]]` entry
+    /// containing it, or the top-level ones.
+    #[must_use]
+    pub fn for_path(&self, path: &Utf8Path) -> Config {
+        match self.root_for(path) {
+            Some(root) => self.for_root(root),
+            None => Config {
+                migrations: Vec::new(),
+                ..self.clone()
+            },
+        }
+    }
+
+    /// The settings for the migration at `path`, with the `[[overrides]]`
+    /// matching it applied in order.
+    pub fn for_file(&self, path: &Utf8Path) -> Cow<'_, Config> {
+        let mut matching = self.overrides.iter().filter(|o| o.matches(path)).peekable();
+        if matching.peek().is_none() {
+            return Cow::Borrowed(self);
+        }
+
+        let mut config = self.clone();
+        for entry in matching {
+            if entry.enable_checks.is_some() || entry.disable_checks.is_some() {
+                config.enable_checks = entry.enable_checks.clone().unwrap_or_default();
+                config.disable_checks = entry.disable_checks.clone().unwrap_or_default();
+            }
+            if let Some(warn_checks) = &entry.warn_checks {
+                config.warn_checks.clone_from(warn_checks);
+            }
+            if entry.postgres_version.is_some() {
+                config.postgres_version = entry.postgres_version;
+            }
+        }
+        Cow::Owned(config)
+    }
+
+    /// Whether `check_name` runs on any migration: it is enabled at the top
+    /// level or by an `[[overrides]]` entry.
+    ///
+    /// Opt-in checks count as enabled here, so they stay registered (and
+    /// listed) while `opt_in_checks` leaves them off.
+    pub fn may_enable_check(&self, check_name: &str) -> bool {
+        self.is_check_selected(check_name) || self.overrides.iter().any(|o| o.enables(check_name))
+    }
+
+    /// The `[checks.<CheckName>]` options for `check_name`.
+    pub fn check_options(&self, check_name: &str) -> CheckOptions<'_> {
+        CheckOptions::new(self.checks.get(check_name))
+    }
+
+    /// Severity for a lock, rewrite or scan violation on `table` (as returned by
+    /// `range_var_name`); `None` when the violation should not be reported.
+    ///
+    /// Tables matching `small_tables` but not `large_tables` are skipped or
+    /// downgraded to warnings, depending on `small_tables_mode`. Otherwise
+    /// tables that `table_stats` shows below both `large_table_rows` and
+    /// `large_table_bytes` are warnings, and everything else is an error.
+    ///
+    /// Checks that report long locks call this before building the violation
+    /// and return nothing on `None`: locking a small table is over quickly.
+    pub fn lock_severity(&self, table: &str) -> Option<Severity> {
+        let matches = |patterns: &[String]| patterns.iter().any(|p| table_matches(p, table));
+        if matches(&self.large_tables) {
+            return Some(Severity::Error);
+        }
+        if matches(&self.small_tables) {
+            return match self.small_tables_mode {
+                SmallTablesMode::Skip => None,
+                SmallTablesMode::Warn => Some(Severity::Warning),
+            };
+        }
+        match self.table_size(table) {
+            Some(size) if !self.is_large(size) => Some(Severity::Warning),
+            _ => Some(Severity::Error),
+        }
+    }
+
+    /// Size of `table` from the `table_stats` snapshot, if it lists the table.
+    pub fn table_size(&self, table: &str) -> Option<&TableSize> {
+        self.table_sizes.as_deref()?.get(table)
+    }
+
+    /// Whether `table_stats` shows `table` at or above `large_table_rows` or
+    /// `large_table_bytes`; `false` for tables it doesn't list.
+    pub fn is_large_table(&self, table: &str) -> bool {
+        self.table_size(table)
+            .is_some_and(|size| self.is_large(size))
+    }
+
+    /// Whether a table of `size` reaches `large_table_rows` or `large_table_bytes`.
+    /// Unknown sizes count as large.
+    fn is_large(&self, size: &TableSize) -> bool {
+        if size.rows.is_none() && size.bytes.is_none() {
+            return true;
+        }
+        size.rows.is_some_and(|rows| rows >= self.large_table_rows)
+            || size
+                .bytes
+                .is_some_and(|bytes| bytes >= self.large_table_bytes)
+    }
+
     /// Return true if the given check should produce warnings instead of errors
     pub fn is_check_warning(&self, check_name: &str) -> bool {
         self.warn_checks.iter().any(|c| c == check_name)
@@ -179,6 +690,15 @@ impl Config {
 
     /// Check if a specific check is enabled
     pub fn is_check_enabled(&self, check_name: &str) -> bool {
+        // Naming an opt-in check in enable_checks also turns it on
+        let opted_in = !self.enable_checks.is_empty()
+            || !crate::checks::OPT_IN_CHECKS.contains(&check_name)
+            || self.opt_in_checks.iter().any(|c| c == check_name);
+        opted_in && self.is_check_selected(check_name)
+    }
+
+    /// Whether `enable_checks` / `disable_checks` let `check_name` run.
+    fn is_check_selected(&self, check_name: &str) -> bool {
         if !self.enable_checks.is_empty() {
             return self.enable_checks.iter().any(|c| c == check_name);
         }
@@ -195,10 +715,91 @@ impl Default for Config {
             disable_checks: Vec::new(),
             enable_checks: Vec::new(),
             warn_checks: Vec::new(),
+            opt_in_checks: Vec::new(),
             custom_checks_dir: None,
             postgres_version: None,
+            small_tables: Vec::new(),
+            large_tables: Vec::new(),
+            small_tables_mode: SmallTablesMode::default(),
+            table_stats: None,
+            large_table_rows: default_large_table_rows(),
+            large_table_bytes: default_large_table_bytes(),
+            table_sizes: None,
+            migrations: Vec::new(),
+            overrides: Vec::new(),
+            checks: BTreeMap::new(),
+            source: None,
+        }
+    }
+}
+
+fn default_large_table_rows() -> u64 {
+    1_000_000
+}
+
+fn default_large_table_bytes() -> u64 {
+    1024 * 1024 * 1024
+}
+
+/// Name of the config file looked for by [`Config::find`].
+pub const CONFIG_FILE_NAME: &str = "diesel-guard.toml";
+
+/// The diesel-guard table of a Cargo.toml, if it has one.
+fn cargo_metadata_table(manifest: &Utf8Path) -> Result<Option<toml::Table>, ConfigError> {
+    let mut cargo: toml::Table = toml::from_str(&std::fs::read_to_string(manifest)?)?;
+    for section in ["package", "workspace"] {
+        let table = cargo
+            .get_mut(section)
+            .and_then(|s| s.get_mut("metadata"))
+            .and_then(|m| m.as_table_mut())
+            .and_then(|m| m.remove("diesel-guard"));
+        if let Some(toml::Value::Table(table)) = table {
+            return Ok(Some(table));
         }
     }
+    Ok(None)
+}
+
+/// Whether the glob `pattern` matches `table`, ignoring case.
+///
+/// Patterns without a schema match the table in any schema; unqualified tables
+/// are in `public`.
+fn table_matches(pattern: &str, table: &str) -> bool {
+    let Ok(glob) = glob::Pattern::new(pattern) else {
+        return false;
+    };
+    let options = glob::MatchOptions {
+        case_sensitive: false,
+        ..glob::MatchOptions::new()
+    };
+    let qualified = if table.contains('.') {
+        Cow::Borrowed(table)
+    } else {
+        Cow::Owned(format!("public.{table}"))
+    };
+    if pattern.contains('.') {
+        glob.matches_with(&qualified, options)
+    } else {
+        let name = table.rsplit_once('.').map_or(table, |(_, name)| name);
+        glob.matches_with(name, options)
+    }
+}
+
+/// `path` made absolute with `.` and `..` resolved, so `./migrations` and
+/// `../app/migrations` (run from `app/`) both match `migrations`.
+fn normalize(path: &Utf8Path) -> Utf8PathBuf {
+    let path = camino::absolute_utf8(path).unwrap_or_else(|_| path.to_owned());
+    let mut normalized = Utf8PathBuf::new();
+    for component in path.components() {
+        match component {
+            Utf8Component::CurDir => {}
+            Utf8Component::ParentDir => {
+                normalized.pop();
+            }
+            _ => normalized.push(component),
+        }
+    }
+    normalized
 }
 
 #[cfg(test)]
@@ -303,6 +904,35 @@ disable_checks = ["AddColumnCheck"]
         assert!(config.validate().is_ok());
     }
 
+    #[test]
+    fn test_valid_refinery_framework() {
+        let config = Config {
+            framework: "refinery".to_string(),
+            ..Default::default()
+        };
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_valid_seaorm_framework() {
+        let config = Config {
+            framework: "seaorm".to_string(),
+            ..Default::default()
+        };
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_valid_golang_migrate_dbmate_and_flyway_frameworks() {
+        for framework in ["golang-migrate", "dbmate", "flyway"] {
+            let config = Config {
+                framework: framework.to_string(),
+                ..Default::default()
+            };
+            assert!(config.validate().is_ok(), "{framework}");
+        }
+    }
+
     #[test]
     fn test_invalid_framework_value() {
         let config = Config {
@@ -324,7 +954,7 @@ disable_checks = ["AddColumnCheck"]
     }
 
     #[test]
-    fn test_missing_framework_field_errors() {
+    fn test_missing_framework_field_is_left_for_detection() {
         let temp_dir = tempdir().expect("Failed to create temp dir");
         let config_path = temp_dir.path().join("diesel-guard.toml");
 
@@ -339,8 +969,9 @@ check_down = true
         .unwrap();
 
         let config_path_utf8 = Utf8Path::from_path(&config_path).unwrap();
-        let err = Config::load_from_path(config_path_utf8).unwrap_err();
-        assert!(matches!(err, ConfigError::MissingFramework));
+        let config = Config::load_from_path(config_path_utf8).unwrap();
+        assert_eq!(config.framework, "");
+        assert!(config.check_down);
     }
 
     #[test]
@@ -443,7 +1074,25 @@ postgres_version = 14
         let help = err.help().unwrap().to_string();
         assert_eq!(
             help,
-            "Add one of the following to your diesel-guard.toml file:\n  framework = \"diesel\"\n  framework = \"sqlx\""
+            "Add one of the following to your diesel-guard.toml file:\n  framework = \"diesel\"\n  framework = \"sqlx\"\n  framework = \"refinery\"\n  framework = \"seaorm\"\n  framework = \"golang-migrate\"\n  framework = \"dbmate\"\n  framework = \"flyway\""
+        );
+    }
+
+    #[test]
+    fn test_diagnostic_help_ambiguous_framework() {
+        use miette::Diagnostic;
+        let err = ConfigError::AmbiguousFramework {
+            candidates: "\"sqlx\", \"golang-migrate\"".to_string(),
+            evidence: "migrations/1_a.up.sql is named like a SQLx or golang-migrate migration"
+                .to_string(),
+        };
+        assert_eq!(
+            err.to_string(),
+            "Could not tell which migration framework this project uses: \"sqlx\", \"golang-migrate\""
+        );
+        assert_eq!(
+            err.help().unwrap().to_string(),
+            "Found: migrations/1_a.up.sql is named like a SQLx or golang-migrate migration.\nSet the 'framework' field in diesel-guard.toml to pick one."
         );
     }
 
@@ -454,7 +1103,10 @@ postgres_version = 14
             framework: "mongo".to_string(),
         };
         let help = err.help().unwrap().to_string();
-        assert_eq!(help, "Valid values: \"diesel\", \"sqlx\"");
+        assert_eq!(
+            help,
+            "Valid values: \"diesel\", \"sqlx\", \"refinery\", \"seaorm\", \"golang-migrate\", \"dbmate\", \"flyway\""
+        );
     }
 
     #[test]
@@ -565,4 +1217,371 @@ warn_checks = ["TruncateTableCheck", "DropTableCheck"]
         let config = Config::default();
         assert!(config.warn_checks.is_empty());
     }
+
+    #[test]
+    fn test_opt_in_checks_run_only_when_named() {
+        let config = Config::default();
+        assert!(!config.is_check_enabled("RequireLockTimeoutCheck"));
+        assert!(config.may_enable_check("RequireLockTimeoutCheck"));
+
+        let opted_in: Config = toml::from_str(
+            r#"
+opt_in_checks = ["RequireLockTimeoutCheck"]
+disable_checks = ["AddColumnCheck"]
+"#,
+        )
+        .unwrap();
+        assert!(opted_in.is_check_enabled("RequireLockTimeoutCheck"));
+        assert!(opted_in.is_check_enabled("AddIndexCheck"));
+
+        let whitelisted = Config {
+            enable_checks: vec!["RequireLockTimeoutCheck".to_string()],
+            ..Default::default()
+        };
+        assert!(whitelisted.is_check_enabled("RequireLockTimeoutCheck"));
+        assert!(!whitelisted.is_check_enabled("AddIndexCheck"));
+
+        let disabled = Config {
+            opt_in_checks: vec!["RequireLockTimeoutCheck".to_string()],
+            disable_checks: vec!["RequireLockTimeoutCheck".to_string()],
+            ..Default::default()
+        };
+        assert!(!disabled.is_check_enabled("RequireLockTimeoutCheck"));
+    }
+
+    const MIGRATION_ROOTS: &str = r#"
+framework = "diesel"
+disable_checks = ["AddColumnCheck"]
+postgres_version = 16
+
+[[migrations]]
+path = "migrations"
+
+[[migrations]]
+path = "services/billing/migrations"
+framework = "sqlx"
+start_after = "20240101000000"
+enable_checks = ["AddIndexCheck"]
+"#;
+
+    #[test]
+    fn test_migration_root_overrides_top_level_settings() {
+        let config: Config = toml::from_str(MIGRATION_ROOTS).unwrap();
+        config.validate().unwrap();
+
+        let app = config.for_root(&config.migrations[0]);
+        assert_eq!(app.framework, "diesel");
+        assert_eq!(app.disable_checks, vec!["AddColumnCheck".to_string()]);
+        assert!(app.migrations.is_empty());
+
+        let billing = config.for_root(&config.migrations[1]);
+        assert_eq!(billing.framework, "sqlx");
+        assert_eq!(billing.start_after.as_deref(), Some("20240101000000"));
+        assert_eq!(billing.postgres_version, Some(16));
+        // The entry's check list replaces both top-level lists
+        assert_eq!(billing.enable_checks, vec!["AddIndexCheck".to_string()]);
+        assert!(billing.disable_checks.is_empty());
+    }
+
+    #[test]
+    fn test_for_path_uses_deepest_containing_root() {
+        let config: Config = toml::from_str(MIGRATION_ROOTS).unwrap();
+
+        let billing = config.for_path(Utf8Path::new(
+            "./services/billing/migrations/20240201000000_add_index.sql",
+        ));
+        assert_eq!(billing.framework, "sqlx");
+        assert_eq!(
+            config
+                .for_path(Utf8Path::new("migrations/2024_01_01_000000_x/up.sql"))
+                .framework,
+            "diesel"
+        );
+        assert!(config.root_for(Utf8Path::new("other/up.sql")).is_none());
+        assert_eq!(
+            config.for_path(Utf8Path::new("other/up.sql")).framework,
+            "diesel"
+        );
+    }
+
+    #[test]
+    fn test_migration_root_without_path_is_rejected() {
+        let config: Config = toml::from_str(
+            r#"
+framework = "diesel"
+
+[[migrations]]
+framework = "sqlx"
+"#,
+        )
+        .unwrap();
+        assert!(matches!(
+            config.validate(),
+            Err(ConfigError::MissingMigrationsPath)
+        ));
+    }
+
+    #[test]
+    fn test_migration_root_settings_are_validated() {
+        let config: Config = toml::from_str(
+            r#"
+framework = "diesel"
+
+[[migrations]]
+path = "db"
+framework = "rails"
+"#,
+        )
+        .unwrap();
+        assert!(matches!(
+            config.validate(),
+            Err(ConfigError::InvalidFramework { .. })
+        ));
+    }
+
+    const OVERRIDES: &str = r#"
+framework = "diesel"
+warn_checks = ["AddIndexCheck"]
+
+[[overrides]]
+files = ["migrations/*_seed_*"]
+disable_checks = ["MutationWithoutWhereCheck"]
+
+[[overrides]]
+files = ["*_backfill_*.sql"]
+enable_checks = ["AddIndexCheck"]
+warn_checks = []
+postgres_version = 11
+"#;
+
+    #[test]
+    fn test_override_path_pattern_matches_migration_directory() {
+        let config: Config = toml::from_str(OVERRIDES).unwrap();
+        let seed = &config.overrides[0];
+
+        assert!(seed.matches(Utf8Path::new(
+            "migrations/2024_01_01_000000_seed_users/up.sql"
+        )));
+        assert!(seed.matches(Utf8Path::new("./migrations/20240101_seed_users.sql")));
+        assert!(!seed.matches(Utf8Path::new("migrations/2024_01_01_000000_users/up.sql")));
+        // `*` doesn't cross directories
+        assert!(!seed.matches(Utf8Path::new("migrations/nested/x_seed_y/up.sql")));
+    }
+
+    #[test]
+    fn test_override_name_pattern_matches_any_directory() {
+        let config: Config = toml::from_str(OVERRIDES).unwrap();
+        let backfill = &config.overrides[1];
+
+        assert!(backfill.matches(Utf8Path::new("db/20240101_backfill_emails.sql")));
+        assert!(!backfill.matches(Utf8Path::new("db/20240101_backfill_emails/up.sql")));
+    }
+
+    #[test]
+    fn test_for_file_applies_matching_overrides() {
+        let config: Config = toml::from_str(OVERRIDES).unwrap();
+        config.validate().unwrap();
+
+        let plain = config.for_file(Utf8Path::new("migrations/2024_01_01_000000_users/up.sql"));
+        assert!(matches!(plain, Cow::Borrowed(_)));
+        assert!(plain.is_check_enabled("MutationWithoutWhereCheck"));
+
+        let seed = config.for_file(Utf8Path::new(
+            "migrations/2024_01_01_000000_seed_users/up.sql",
+        ));
+        assert!(!seed.is_check_enabled("MutationWithoutWhereCheck"));
+        assert!(seed.is_check_warning("AddIndexCheck"));
+
+        let backfill = config.for_file(Utf8Path::new("db/20240101_backfill_emails.sql"));
+        assert_eq!(backfill.enable_checks, vec!["AddIndexCheck".to_string()]);
+        assert!(!backfill.is_check_warning("AddIndexCheck"));
+        assert_eq!(backfill.postgres_version, Some(11));
+    }
+
+    #[test]
+    fn test_may_enable_check_includes_override_lists() {
+        let config: Config = toml::from_str(
+            r#"
+framework = "diesel"
+enable_checks = ["AddColumnCheck"]
+
+[[overrides]]
+files = ["*_index_*"]
+enable_checks = ["AddIndexCheck"]
+"#,
+        )
+        .unwrap();
+        assert!(config.may_enable_check("AddColumnCheck"));
+        assert!(config.may_enable_check("AddIndexCheck"));
+        assert!(!config.may_enable_check("DropTableCheck"));
+    }
+
+    #[test]
+    fn test_invalid_override_entries_are_rejected() {
+        let missing_files: Config =
+            toml::from_str("[[overrides]]\ndisable_checks = [\"AddColumnCheck\"]\n").unwrap();
+        assert!(matches!(
+            missing_files.validate(),
+            Err(ConfigError::MissingOverrideFiles)
+        ));
+
+        let bad_pattern: Config = toml::from_str("[[overrides]]\nfiles = [\"seed_[\"]\n").unwrap();
+        assert!(matches!(
+            bad_pattern.validate(),
+            Err(ConfigError::InvalidOverridePattern { .. })
+        ));
+
+        let conflicting: Config = toml::from_str(
+            "[[overrides]]\nfiles = [\"*\"]\nenable_checks = [\"AddColumnCheck\"]\ndisable_checks = [\"AddIndexCheck\"]\n",
+        )
+        .unwrap();
+        assert!(matches!(
+            conflicting.validate(),
+            Err(ConfigError::ConflictingCheckConfig)
+        ));
+    }
+
+    #[test]
+    fn test_check_options_are_validated_for_builtin_checks() {
+        let config: Config =
+            toml::from_str("[checks.WideIndexCheck]\nmax_columns = \"four\"\n").unwrap();
+        let Err(ConfigError::InvalidCheckOption {
+            check,
+            option,
+            reason,
+        }) = config.validate()
+        else {
+            panic!("expected InvalidCheckOption");
+        };
+        assert_eq!(
+            (check.as_str(), option.as_str(), reason.as_str()),
+            (
+                "WideIndexCheck",
+                "max_columns",
+                "expected a non-negative integer"
+            )
+        );
+
+        let unknown: Config = toml::from_str("[checks.CharTypeCheck]\nmax_columns = 4\n").unwrap();
+        assert!(matches!(
+            unknown.validate(),
+            Err(ConfigError::InvalidCheckOption { .. })
+        ));
+
+        // Sections for custom checks can hold anything
+        let custom: Config = toml::from_str("[checks.my_check]\nanything = true\n").unwrap();
+        custom.validate().unwrap();
+        assert!(
+            custom
+                .check_options("my_check")
+                .strings("anything")
+                .next()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_diagnostic_help_invalid_check_option() {
+        let err = ConfigError::InvalidCheckOption {
+            check: "WideIndexCheck".to_string(),
+            option: "max_column".to_string(),
+            reason: "unknown option".to_string(),
+        };
+        assert_eq!(
+            err.help().unwrap().to_string(),
+            "WideIndexCheck supports: max_columns (integer), allowed_tables (list of strings). \
+             Run `diesel-guard explain WideIndexCheck` for details."
+        );
+    }
+
+    #[test]
+    fn test_lock_severity_for_small_and_large_tables() {
+        let config: Config = toml::from_str(
+            r#"
+small_tables = ["countries", "lookup_*", "audit.*"]
+large_tables = ["lookup_events"]
+"#,
+        )
+        .unwrap();
+        config.validate().unwrap();
+
+        assert_eq!(config.lock_severity("countries"), None);
+        assert_eq!(config.lock_severity("public.Countries"), None);
+        assert_eq!(config.lock_severity("lookup_colors"), None);
+        assert_eq!(config.lock_severity("audit.logins"), None);
+        assert_eq!(config.lock_severity("lookup_events"), Some(Severity::Error));
+        assert_eq!(config.lock_severity("audits"), Some(Severity::Error));
+        assert_eq!(config.lock_severity("orders"), Some(Severity::Error));
+
+        let warn = Config {
+            small_tables_mode: SmallTablesMode::Warn,
+            ..config
+        };
+        assert_eq!(warn.lock_severity("countries"), Some(Severity::Warning));
+        assert_eq!(warn.lock_severity("orders"), Some(Severity::Error));
+    }
+
+    #[test]
+    fn test_invalid_table_pattern() {
+        let config: Config = toml::from_str(r#"small_tables = ["lookup_["]"#).unwrap();
+        assert!(matches!(
+            config.validate(),
+            Err(ConfigError::InvalidTablePattern { .. })
+        ));
+        let mode = toml::from_str::<Config>(r#"small_tables_mode = "ignore""#);
+        assert!(mode.is_err());
+    }
+
+    #[test]
+    fn test_table_stats_thresholds_decide_severity() {
+        let temp_dir = tempdir().unwrap();
+        let dir = Utf8Path::from_path(temp_dir.path()).unwrap();
+        fs::write(
+            dir.join("stats.csv"),
+            "table,rows,bytes\norders,480000000,225485783040\ncountries,250,65536\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("diesel-guard.toml"),
+            "table_stats = \"stats.csv\"\nsmall_tables = [\"countries\"]\n",
+        )
+        .unwrap();
+        let config = Config::load_from_path(&dir.join("diesel-guard.toml")).unwrap();
+
+        assert_eq!(
+            config.table_size("orders").unwrap().describe(),
+            "~480M rows / 210 GB"
+        );
+        assert_eq!(config.lock_severity("orders"), Some(Severity::Error));
+        // small_tables wins over the snapshot
+        assert_eq!(config.lock_severity("countries"), None);
+        // Tables missing from the snapshot stay errors
+        assert_eq!(config.lock_severity("users"), Some(Severity::Error));
+
+        let raised = Config {
+            large_table_rows: 1_000_000_000,
+            large_table_bytes: u64::MAX,
+            small_tables: Vec::new(),
+            ..config
+        };
+        assert_eq!(raised.lock_severity("orders"), Some(Severity::Warning));
+        assert_eq!(raised.lock_severity("countries"), Some(Severity::Warning));
+    }
+
+    #[test]
+    fn test_invalid_table_stats_file() {
+        let temp_dir = tempdir().unwrap();
+        let dir = Utf8Path::from_path(temp_dir.path()).unwrap();
+        fs::write(dir.join("stats.json"), r#"{"orders": 1}"#).unwrap();
+        fs::write(
+            dir.join("diesel-guard.toml"),
+            "table_stats = \"stats.json\"\n",
+        )
+        .unwrap();
+        let err = Config::load_from_path(&dir.join("diesel-guard.toml")).unwrap_err();
+        assert!(
+            matches!(err, ConfigError::InvalidTableStats { .. }),
+            "{err}"
+        );
+    }
 }
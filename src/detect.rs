@@ -0,0 +1,357 @@
+//! Migration framework detection.
+//!
+//! When `framework` is not set, diesel-guard looks at the project to pick an
+//! adapter: the layout of the migrations directory first (Diesel migration
+//! directories, SQLx-style numbered files, dbmate section markers, ...), then
+//! `diesel.toml` and the dependencies in `Cargo.toml` to tell apart frameworks
+//! whose files look the same.
+
+use crate::config::ConfigError;
+use camino::Utf8Path;
+use regex::Regex;
+use std::sync::LazyLock;
+use walkdir::WalkDir;
+
+/// Numbered SQL files with a direction suffix (SQLx reversible, golang-migrate).
+static UP_DOWN_FILE_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\d+_.*\.(up|down)\.sql$").expect("valid regex pattern"));
+
+/// Numbered SQL files without a direction (SQLx up-only, dbmate).
+static NUMBERED_FILE_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\d+_.*\.sql$").expect("valid regex pattern"));
+
+/// Versioned files shared by refinery and Flyway.
+static VERSIONED_FILE_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^[VU]\d+__\w+\.sql$").expect("valid regex pattern"));
+
+/// Flyway-only names: dotted versions and repeatable migrations.
+static FLYWAY_FILE_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^([VU]\d+([._]\d+)+__.+|R__.+)\.sql$").expect("valid regex pattern")
+});
+
+/// SeaORM migration modules.
+static SEAORM_FILE_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^m\d{8}_\d{6}_\w+\.rs$").expect("valid regex pattern"));
+
+/// dbmate section marker.
+static DBMATE_MARKER_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?m)^--\s*migrate:up").expect("valid regex pattern"));
+
+/// Crates that identify a Rust migration framework.
+const FRAMEWORK_CRATES: &[(&str, &str)] = &[
+    ("diesel", "diesel"),
+    ("diesel_migrations", "diesel"),
+    ("sqlx", "sqlx"),
+    ("refinery", "refinery"),
+    ("sea-orm-migration", "seaorm"),
+];
+
+/// Framework picked by [`detect_framework`] and why.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Detection {
+    pub framework: &'static str,
+    /// What pointed at the framework, e.g. "Cargo.toml depends on sqlx".
+    pub reasons: Vec<String>,
+}
+
+/// One observation about the project and the frameworks it fits.
+struct Evidence {
+    frameworks: &'static [&'static str],
+    reason: String,
+}
+
+/// Detect the migration framework for the migrations at `migrations`.
+///
+/// `project_dir` is where `diesel.toml` and `Cargo.toml` are looked up.
+/// Returns `Ok(None)` when nothing in the project points at a framework, and
+/// [`ConfigError::AmbiguousFramework`] when the evidence fits several.
+pub fn detect_framework(
+    project_dir: &Utf8Path,
+    migrations: &Utf8Path,
+) -> Result<Option<Detection>, ConfigError> {
+    let layout = layout_evidence(migrations);
+    let project = project_evidence(project_dir, migrations);
+
+    let frameworks_of = |evidence: &[Evidence]| {
+        let mut frameworks: Vec<&'static str> = Vec::new();
+        for framework in evidence.iter().flat_map(|e| e.frameworks) {
+            if !frameworks.contains(framework) {
+                frameworks.push(framework);
+            }
+        }
+        frameworks
+    };
+
+    // The files decide; the project only narrows down files several frameworks share
+    let mut candidates = frameworks_of(&layout);
+    let from_project = frameworks_of(&project);
+    if candidates.is_empty() {
+        candidates = from_project;
+    } else if candidates.len() > 1 {
+        let narrowed: Vec<_> = candidates
+            .iter()
+            .copied()
+            .filter(|f| from_project.contains(f))
+            .collect();
+        if !narrowed.is_empty() {
+            candidates = narrowed;
+        }
+    }
+
+    let evidence = || layout.iter().chain(&project);
+    match candidates.as_slice() {
+        [] => Ok(None),
+        [framework] => Ok(Some(Detection {
+            framework,
+            reasons: evidence()
+                .filter(|e| e.frameworks.contains(framework))
+                .map(|e| e.reason.clone())
+                .collect(),
+        })),
+        _ => Err(ConfigError::AmbiguousFramework {
+            candidates: candidates
+                .iter()
+                .map(|f| format!("\"{f}\""))
+                .collect::<Vec<_>>()
+                .join(", "),
+            evidence: evidence()
+                .map(|e| e.reason.clone())
+                .collect::<Vec<_>>()
+                .join("; "),
+        }),
+    }
+}
+
+/// Evidence from the migration files themselves.
+fn layout_evidence(migrations: &Utf8Path) -> Vec<Evidence> {
+    // A single file is judged by the directory it sits in
+    let dir = if migrations.is_file() {
+        migrations.parent().unwrap_or(Utf8Path::new("."))
+    } else {
+        migrations
+    };
+
+    let mut evidence = Vec::new();
+    let mut note = |frameworks: &'static [&'static str], reason: String| {
+        if !evidence
+            .iter()
+            .any(|e: &Evidence| e.frameworks == frameworks)
+        {
+            evidence.push(Evidence { frameworks, reason });
+        }
+    };
+
+    if dir.join("up.sql").is_file() {
+        note(&["diesel"], format!("{dir} contains up.sql"));
+    }
+
+    // SeaORM modules live in the migration crate's src/ directory
+    let dirs = [dir.to_owned(), dir.join("src")];
+    for entry in dirs.iter().flat_map(|d| {
+        WalkDir::new(d)
+            .min_depth(1)
+            .max_depth(1)
+            .sort_by_file_name()
+            .into_iter()
+            .filter_map(Result::ok)
+    }) {
+        let Some(path) = Utf8Path::from_path(entry.path()) else {
+            continue;
+        };
+        let name = path.file_name().unwrap_or("");
+
+        if entry.file_type().is_dir() {
+            if path.join("up.sql").is_file() {
+                note(
+                    &["diesel"],
+                    format!("{path} is a Diesel migration directory (up.sql)"),
+                );
+            }
+        } else if SEAORM_FILE_REGEX.is_match(name) {
+            note(&["seaorm"], format!("{path} is a SeaORM migration module"));
+        } else if FLYWAY_FILE_REGEX.is_match(name) {
+            note(
+                &["flyway"],
+                format!("{path} is named like a Flyway migration"),
+            );
+        } else if VERSIONED_FILE_REGEX.is_match(name) {
+            note(
+                &["refinery", "flyway"],
+                format!("{path} is named like a refinery or Flyway migration"),
+            );
+        } else if UP_DOWN_FILE_REGEX.is_match(name) {
+            note(
+                &["sqlx", "golang-migrate"],
+                format!("{path} is named like a SQLx or golang-migrate migration"),
+            );
+        } else if NUMBERED_FILE_REGEX.is_match(name) {
+            let has_sections =
+                std::fs::read_to_string(path).is_ok_and(|sql| DBMATE_MARKER_REGEX.is_match(&sql));
+            if has_sections {
+                note(&["dbmate"], format!("{path} has a `-- migrate:up` section"));
+            } else {
+                note(&["sqlx"], format!("{path} is named like a SQLx migration"));
+            }
+        }
+    }
+
+    evidence
+}
+
+/// Evidence from the project around the migrations: `diesel.toml` and `Cargo.toml`.
+fn project_evidence(project_dir: &Utf8Path, migrations: &Utf8Path) -> Vec<Evidence> {
+    let mut evidence = Vec::new();
+
+    if project_dir.join("diesel.toml").is_file() {
+        evidence.push(Evidence {
+            frameworks: &["diesel"],
+            reason: "diesel.toml exists".to_string(),
+        });
+    }
+
+    // The workspace manifest, and the migration crate's own (SeaORM)
+    let mut manifests = vec![project_dir.join("Cargo.toml")];
+    for dir in [Some(migrations), migrations.parent()]
+        .into_iter()
+        .flatten()
+    {
+        let manifest = dir.join("Cargo.toml");
+        if dir.as_str() != "" && !manifests.contains(&manifest) {
+            manifests.push(manifest);
+        }
+    }
+
+    for manifest in manifests {
+        let Ok(contents) = std::fs::read_to_string(&manifest) else {
+            continue;
+        };
+        let Ok(table) = contents.parse::<toml::Table>() else {
+            continue;
+        };
+        for (krate, framework) in FRAMEWORK_CRATES {
+            let already = evidence.iter().any(|e| e.frameworks.contains(framework));
+            if !already && depends_on(&table, krate) {
+                evidence.push(Evidence {
+                    frameworks: std::slice::from_ref(framework),
+                    reason: format!("{manifest} depends on {krate}"),
+                });
+            }
+        }
+    }
+
+    evidence
+}
+
+/// Whether a manifest lists `krate` in any dependency table.
+fn depends_on(manifest: &toml::Table, krate: &str) -> bool {
+    let has = |table: Option<&toml::Value>| {
+        table
+            .and_then(toml::Value::as_table)
+            .is_some_and(|deps| deps.contains_key(krate))
+    };
+    ["dependencies", "dev-dependencies", "build-dependencies"]
+        .iter()
+        .any(|section| has(manifest.get(*section)))
+        || has(manifest
+            .get("workspace")
+            .and_then(|workspace| workspace.get("dependencies")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    fn detect(project: &Utf8Path) -> Result<Option<Detection>, ConfigError> {
+        detect_framework(project, &project.join("migrations"))
+    }
+
+    fn project() -> (tempfile::TempDir, camino::Utf8PathBuf) {
+        let temp_dir = tempdir().expect("Failed to create temp dir");
+        let root = Utf8Path::from_path(temp_dir.path()).unwrap().to_owned();
+        fs::create_dir(root.join("migrations")).unwrap();
+        (temp_dir, root)
+    }
+
+    #[test]
+    fn test_detects_diesel_directories() {
+        let (_temp, root) = project();
+        fs::create_dir(root.join("migrations/2024_01_01_000000_users")).unwrap();
+        fs::write(root.join("migrations/2024_01_01_000000_users/up.sql"), "").unwrap();
+
+        let detection = detect(&root).unwrap().unwrap();
+        assert_eq!(detection.framework, "diesel");
+        assert!(detection.reasons[0].contains("Diesel migration directory"));
+    }
+
+    #[test]
+    fn test_detects_sqlx_files() {
+        let (_temp, root) = project();
+        fs::write(root.join("migrations/20240101000000_users.sql"), "").unwrap();
+
+        assert_eq!(detect(&root).unwrap().unwrap().framework, "sqlx");
+    }
+
+    #[test]
+    fn test_cargo_dependency_settles_shared_layout() {
+        let (_temp, root) = project();
+        fs::write(root.join("migrations/1_users.up.sql"), "").unwrap();
+
+        let err = detect(&root).unwrap_err();
+        assert!(matches!(err, ConfigError::AmbiguousFramework { .. }));
+
+        fs::write(
+            root.join("Cargo.toml"),
+            "[package]\nname = \"app\"\n\n[dependencies]\nsqlx = { version = \"0.8\" }\n",
+        )
+        .unwrap();
+        let detection = detect(&root).unwrap().unwrap();
+        assert_eq!(detection.framework, "sqlx");
+        assert_eq!(detection.reasons.len(), 2);
+    }
+
+    #[test]
+    fn test_mixed_layouts_are_ambiguous() {
+        let (_temp, root) = project();
+        fs::create_dir(root.join("migrations/2024_01_01_000000_users")).unwrap();
+        fs::write(root.join("migrations/2024_01_01_000000_users/up.sql"), "").unwrap();
+        fs::write(root.join("migrations/20240102000000_posts.sql"), "").unwrap();
+
+        let ConfigError::AmbiguousFramework {
+            candidates,
+            evidence,
+        } = detect(&root).unwrap_err()
+        else {
+            panic!("expected AmbiguousFramework");
+        };
+        assert!(candidates.contains("\"diesel\"") && candidates.contains("\"sqlx\""));
+        assert!(evidence.contains("SQLx"));
+    }
+
+    #[test]
+    fn test_dbmate_sections_and_flyway_names() {
+        let (_temp, root) = project();
+        fs::write(
+            root.join("migrations/20240101000000_users.sql"),
+            "-- migrate:up\nSELECT 1;\n",
+        )
+        .unwrap();
+        assert_eq!(detect(&root).unwrap().unwrap().framework, "dbmate");
+
+        let (_temp, root) = project();
+        fs::write(root.join("migrations/V1_1__users.sql"), "").unwrap();
+        assert_eq!(detect(&root).unwrap().unwrap().framework, "flyway");
+    }
+
+    #[test]
+    fn test_project_files_without_migrations() {
+        let (_temp, root) = project();
+        assert_eq!(detect(&root).unwrap(), None);
+
+        fs::write(root.join("diesel.toml"), "").unwrap();
+        let detection = detect(&root).unwrap().unwrap();
+        assert_eq!(detection.framework, "diesel");
+        assert_eq!(detection.reasons, ["diesel.toml exists"]);
+    }
+}
@@ -27,6 +27,27 @@ pub enum DieselGuardError {
     )]
     WalkDirError(#[from] walkdir::Error),
 
+    #[error("Invalid baseline file '{path}': {msg}")]
+    #[diagnostic(
+        code(diesel_guard::baseline_error),
+        help("Regenerate it with `diesel-guard baseline`")
+    )]
+    BaselineError { path: String, msg: String },
+
+    #[error("Invalid lock file '{path}': {msg}")]
+    #[diagnostic(
+        code(diesel_guard::lock_error),
+        help("Regenerate it with `diesel-guard lock`")
+    )]
+    LockError { path: String, msg: String },
+
+    #[error("git {command} failed: {msg}")]
+    #[diagnostic(
+        code(diesel_guard::git_error),
+        help("Run diesel-guard inside a git repository and check that the ref exists")
+    )]
+    GitError { command: String, msg: String },
+
     #[error(transparent)]
     #[diagnostic(transparent)]
     ConfigError(#[from] crate::config::ConfigError),
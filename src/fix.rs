@@ -0,0 +1,1078 @@
+//! Mechanical rewrites for violations with a known fix.
+//!
+//! `diesel-guard fix` runs the enabled checks over a migration and rewrites the
+//! statements that produced fixable violations, using the statement byte ranges
+//! from [`crate::parser::parse`] and pg_query's scanner to find insertion points:
+//!
+//! - `AddIndexCheck`, `DropIndexCheck`, `ReindexCheck`, `RefreshMatViewCheck`: add CONCURRENTLY
+//! - `AddForeignKeyCheck`, `AddCheckConstraintCheck`: add NOT VALID and a separate VALIDATE CONSTRAINT
+//! - Idempotency checks: add IF [NOT] EXISTS
+//!
+//! When a fix needs the migration to leave its transaction, the framework adapter
+//! describes how (`-- no-transaction` for SQLx, `metadata.toml` for Diesel).
+//! Statements inside safety-assured blocks and disabled checks are left alone.
+//!
+//! The same rewrites are attached to each violation as a [`Fix`] by the
+//! registry, so `--format json` consumers can apply them without running `fix`.
+//! Fixes returned by custom checks are applied when they are machine-applicable.
+
+use crate::adapters::{MigrationAdapter, MigrationContext, TransactionOptOut};
+use crate::checks::pg_helpers::{
+    AlterTableType, ConstrType, DropBehavior, NodeEnum, ObjectType, cmd_def_as_column_def,
+    cmd_def_as_constraint, range_var_name,
+};
+use crate::checks::{CheckedStmt, Registry};
+use crate::violation::{Applicability, Fix, Violation};
+use camino::{Utf8Path, Utf8PathBuf};
+use pg_query::protobuf::{AlterTableStmt, ScanToken, Token};
+use std::fs;
+use std::io;
+
+pub use crate::violation::TextEdit;
+
+/// Apply edits to `source`. Edits must not overlap; edits at the same offset
+/// are applied in the order given.
+pub fn apply_edits(source: &str, edits: &[TextEdit]) -> String {
+    let mut sorted: Vec<&TextEdit> = edits.iter().collect();
+    sorted.sort_by_key(|e| (e.start, e.end));
+
+    let mut out = String::with_capacity(source.len());
+    let mut cursor = 0;
+    for edit in sorted {
+        let start = edit.start.clamp(cursor, source.len());
+        out.push_str(&source[cursor..start]);
+        out.push_str(&edit.replacement);
+        cursor = edit.end.clamp(start, source.len());
+    }
+    out.push_str(&source[cursor..]);
+    out
+}
+
+/// Framework metadata file rewritten alongside the SQL.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MetadataChange {
+    pub path: Utf8PathBuf,
+    /// `None` when the file is created.
+    pub original: Option<String>,
+    pub contents: String,
+}
+
+/// Fixes computed for one migration file.
+#[derive(Debug, Clone)]
+pub struct FileFix {
+    pub path: Utf8PathBuf,
+    pub original: String,
+    pub fixed: String,
+    pub metadata: Option<MetadataChange>,
+    /// Statements that must run in a later migration, outside this one's transaction.
+    pub follow_ups: Vec<String>,
+    /// Number of statements rewritten.
+    pub fixed_statements: usize,
+}
+
+impl FileFix {
+    /// Return true when nothing needs to change.
+    pub fn is_empty(&self) -> bool {
+        self.original == self.fixed && self.metadata.is_none() && self.follow_ups.is_empty()
+    }
+
+    /// Write the fixed SQL (and metadata file, if any) to disk.
+    pub fn write(&self) -> io::Result<()> {
+        if self.original != self.fixed {
+            fs::write(&self.path, &self.fixed)?;
+        }
+        if let Some(metadata) = &self.metadata {
+            fs::write(&metadata.path, &metadata.contents)?;
+        }
+        Ok(())
+    }
+
+    /// Unified diff of every file this fix touches.
+    pub fn diff(&self) -> String {
+        let mut out = String::new();
+        if self.original != self.fixed {
+            out.push_str(&unified_diff(
+                self.path.as_str(),
+                self.path.as_str(),
+                &self.original,
+                &self.fixed,
+            ));
+        }
+        if let Some(metadata) = &self.metadata {
+            let from = if metadata.original.is_some() {
+                metadata.path.as_str()
+            } else {
+                "/dev/null"
+            };
+            out.push_str(&unified_diff(
+                from,
+                metadata.path.as_str(),
+                metadata.original.as_deref().unwrap_or_default(),
+                &metadata.contents,
+            ));
+        }
+        out
+    }
+}
+
+fn unified_diff(from: &str, to: &str, old: &str, new: &str) -> String {
+    similar::TextDiff::from_lines(old, new)
+        .unified_diff()
+        .context_radius(3)
+        .header(from, to)
+        .to_string()
+}
+
+/// Compute the fixes for one migration file from its checked statements.
+///
+/// `sql` is the text the statements were parsed from. It matches `source`
+/// byte for byte, except that sections of a shared file that weren't checked
+/// are blanked, so rewrites never land in them.
+pub fn fix_file(
+    path: &Utf8Path,
+    source: &str,
+    sql: &str,
+    checked: &[CheckedStmt],
+    ctx: &MigrationContext,
+    adapter: &dyn MigrationAdapter,
+) -> FileFix {
+    let tokens = significant_tokens(sql);
+    let mut edits = Vec::new();
+    let mut validations = Vec::new();
+    let mut needs_no_transaction = false;
+    let mut fixed_statements = 0;
+    // CONCURRENTLY is no fix when the migration can't leave its transaction
+    let opt_out = adapter.transaction_opt_out(path);
+    let can_leave_transaction =
+        !ctx.run_in_transaction || !matches!(opt_out, TransactionOptOut::Unsupported);
+
+    for stmt in checked.iter().filter(|s| !s.violations.is_empty()) {
+        let edits_before = edits.len();
+        for suggestion in suggest(stmt, tokens_in(&tokens, stmt.span.clone()))
+            .into_iter()
+            .filter(|s| can_leave_transaction || !s.needs_no_transaction)
+        {
+            needs_no_transaction |= suggestion.needs_no_transaction;
+            edits.extend(suggestion.edits);
+            validations.extend(suggestion.validation);
+        }
+        // Custom checks carry their own fixes; only apply the ones that are safe unattended
+        let custom = stmt
+            .violations
+            .iter()
+            .filter(|v| !Registry::builtin_check_names().contains(&v.check_name.as_str()));
+        for fix in custom.filter_map(|v| v.fix.as_ref()) {
+            let clashes = fix
+                .edits
+                .iter()
+                .any(|edit| edits.iter().any(|other| overlaps(edit, other)));
+            if fix.applicability == Applicability::MachineApplicable && !clashes {
+                for edit in &fix.edits {
+                    if !edits.contains(edit) {
+                        edits.push(edit.clone());
+                    }
+                }
+            }
+        }
+        if edits.len() > edits_before {
+            fixed_statements += 1;
+        }
+    }
+
+    let mut metadata = None;
+    let leaves_transaction = needs_no_transaction && ctx.run_in_transaction;
+    if leaves_transaction {
+        match opt_out {
+            TransactionOptOut::Directive(directive) => {
+                edits.insert(0, TextEdit::insert(0, format!("{directive}\n")));
+            }
+            TransactionOptOut::MetadataFile {
+                path,
+                original,
+                contents,
+            } => {
+                metadata = Some(MetadataChange {
+                    path,
+                    original,
+                    contents,
+                });
+            }
+            TransactionOptOut::Edit(edit) => edits.insert(0, edit),
+            TransactionOptOut::Unsupported => {}
+        }
+    }
+
+    // VALIDATE must not share a transaction with the NOT VALID add, or the
+    // validation scan runs while the stronger lock is still held.
+    let mut follow_ups = Vec::new();
+    if !ctx.run_in_transaction || leaves_transaction {
+        edits.extend(validations.iter().map(Validation::edit));
+    } else {
+        follow_ups.extend(validations.into_iter().map(|v| v.sql));
+    }
+
+    FileFix {
+        path: path.to_owned(),
+        original: source.to_string(),
+        fixed: apply_edits(source, &edits),
+        metadata,
+        follow_ups,
+        fixed_statements,
+    }
+}
+
+/// Attach the built-in rewrites to the violations they fix.
+///
+/// A rewrite that covers the whole statement (e.g. one IF EXISTS for a
+/// multi-object DROP) goes on the first violation it fixes. Violations that
+/// already carry a fix from their check keep it.
+pub(crate) fn attach_fixes(sql: &str, checked: &mut [CheckedStmt], run_in_transaction: bool) {
+    if checked.iter().all(|s| s.violations.is_empty()) {
+        return;
+    }
+    let tokens = significant_tokens(sql);
+
+    for stmt in checked.iter_mut() {
+        for suggestion in suggest(stmt, tokens_in(&tokens, stmt.span.clone())) {
+            if suggestion.edits.is_empty() {
+                continue;
+            }
+            let Some(violation) = stmt
+                .violations
+                .iter_mut()
+                .filter(|v| v.check_name == suggestion.check_name)
+                .nth(suggestion.nth)
+            else {
+                continue;
+            };
+            if violation.fix.is_some() {
+                continue;
+            }
+
+            // Inside a transaction CONCURRENTLY still fails and the VALIDATE
+            // has to move to a later migration, so the edits alone are not enough.
+            let applicability = if run_in_transaction
+                && (suggestion.needs_no_transaction || suggestion.validation.is_some())
+            {
+                Applicability::MaybeIncorrect
+            } else {
+                Applicability::MachineApplicable
+            };
+            let mut edits = suggestion.edits;
+            if !run_in_transaction {
+                edits.extend(suggestion.validation.as_ref().map(Validation::edit));
+            }
+            violation.fix = Some(Fix {
+                applicability,
+                edits,
+            });
+        }
+    }
+}
+
+/// Replace custom violations whose fix can't be applied with script errors.
+///
+/// Script edits must lie inside their statement, start and end on character
+/// boundaries, and leave the edits of the statement's other fixes alone.
+pub(crate) fn reject_invalid_custom_fixes(sql: &str, stmt: &mut CheckedStmt) {
+    for i in 0..stmt.violations.len() {
+        let violation = &stmt.violations[i];
+        if Registry::builtin_check_names().contains(&violation.check_name.as_str()) {
+            continue;
+        }
+        let Some(fix) = &violation.fix else {
+            continue;
+        };
+        let others: Vec<&TextEdit> = stmt
+            .violations
+            .iter()
+            .enumerate()
+            .filter(|&(j, _)| j != i)
+            .filter_map(|(_, v)| v.fix.as_ref())
+            .flat_map(|fix| &fix.edits)
+            .collect();
+        let Err(issue) = validate_edits(sql, &stmt.span, &fix.edits, &others) else {
+            continue;
+        };
+
+        let check_name = violation.check_name.clone();
+        stmt.violations[i] = Violation::new(
+            format!("SCRIPT ERROR: {check_name}"),
+            format!("Custom check returned an invalid fix: {issue}"),
+            "Fix the custom check script to return byte offsets inside the statement it flagged.",
+        )
+        .with_check_name(&check_name);
+    }
+}
+
+fn validate_edits(
+    sql: &str,
+    span: &std::ops::Range<usize>,
+    edits: &[TextEdit],
+    others: &[&TextEdit],
+) -> Result<(), String> {
+    for (i, edit) in edits.iter().enumerate() {
+        let TextEdit { start, end, .. } = *edit;
+        if start < span.start || end > span.end {
+            return Err(format!(
+                "edit {start}..{end} is outside its statement ({}..{})",
+                span.start, span.end
+            ));
+        }
+        if !sql.is_char_boundary(start) || !sql.is_char_boundary(end) {
+            return Err(format!("edit {start}..{end} splits a multi-byte character"));
+        }
+        if let Some(other) = edits[..i]
+            .iter()
+            .chain(others.iter().copied())
+            .find(|other| overlaps(edit, other))
+        {
+            return Err(format!(
+                "edit {start}..{end} overlaps edit {}..{}",
+                other.start, other.end
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Whether two edits touch the same bytes. Identical edits and insertions at
+/// the boundary of another edit don't count.
+fn overlaps(a: &TextEdit, b: &TextEdit) -> bool {
+    a != b && a.start < b.end && b.start < a.end
+}
+
+struct Validation {
+    /// Offset just past the statement being validated.
+    offset: usize,
+    /// Whether that statement ends with a semicolon.
+    terminated: bool,
+    sql: String,
+}
+
+impl Validation {
+    /// Edit that places the VALIDATE right after its statement.
+    fn edit(&self) -> TextEdit {
+        let separator = if self.terminated { "" } else { ";" };
+        TextEdit::insert(self.offset, format!("{separator}\n{}", self.sql))
+    }
+}
+
+/// Rewrite for one violation, independent of the migration's transaction mode.
+struct Suggestion {
+    check_name: &'static str,
+    /// Index among the violations `check_name` reported for the statement.
+    nth: usize,
+    edits: Vec<TextEdit>,
+    /// VALIDATE CONSTRAINT to run after the statement.
+    validation: Option<Validation>,
+    /// Whether the rewritten statement must run outside a transaction.
+    needs_no_transaction: bool,
+}
+
+impl Suggestion {
+    fn new(check_name: &'static str, nth: usize) -> Self {
+        Self {
+            check_name,
+            nth,
+            edits: Vec::new(),
+            validation: None,
+            needs_no_transaction: false,
+        }
+    }
+
+    fn with_edit(mut self, edit: Option<TextEdit>) -> Self {
+        self.edits.extend(edit);
+        self
+    }
+
+    fn leaving_transaction(mut self) -> Self {
+        self.needs_no_transaction = true;
+        self
+    }
+}
+
+fn fired(stmt: &CheckedStmt, check_name: &str) -> bool {
+    stmt.violations.iter().any(|v| v.check_name == check_name)
+}
+
+fn fired_op(stmt: &CheckedStmt, check_name: &str, operation: &str) -> bool {
+    stmt.violations
+        .iter()
+        .any(|v| v.check_name == check_name && v.operation == operation)
+}
+
+/// Suggestion for a CONCURRENTLY check: the edit when it is missing, or just
+/// the transaction opt-out when the statement already uses it.
+fn concurrently_suggestion(
+    stmt: &CheckedStmt,
+    check_name: &'static str,
+    missing_op: &str,
+    in_transaction_op: &str,
+    fixable: bool,
+    edit: impl FnOnce() -> Option<TextEdit>,
+) -> Option<Suggestion> {
+    if fixable && fired_op(stmt, check_name, missing_op) {
+        let suggestion = Suggestion::new(check_name, 0).with_edit(edit());
+        return (!suggestion.edits.is_empty()).then(|| suggestion.leaving_transaction());
+    }
+    fired_op(stmt, check_name, in_transaction_op)
+        .then(|| Suggestion::new(check_name, 0).leaving_transaction())
+}
+
+fn suggest(stmt: &CheckedStmt, tokens: &[ScanToken]) -> Vec<Suggestion> {
+    if stmt.violations.is_empty() {
+        return vec![];
+    }
+    let mut out = Vec::new();
+    match stmt.node {
+        NodeEnum::IndexStmt(index) => {
+            let anchor = modifier_anchor(tokens, Token::Index);
+            out.extend(concurrently_suggestion(
+                stmt,
+                "AddIndexCheck",
+                "ADD INDEX without CONCURRENTLY",
+                "CREATE INDEX CONCURRENTLY inside a transaction",
+                true,
+                || anchor.map(|a| TextEdit::insert(a, " CONCURRENTLY")),
+            ));
+            // IF NOT EXISTS requires an index name
+            if fired(stmt, "IdempotencyIndexCheck") && !index.idxname.is_empty() {
+                out.push(
+                    Suggestion::new("IdempotencyIndexCheck", 0)
+                        .with_edit(anchor.map(|a| TextEdit::insert(a, " IF NOT EXISTS"))),
+                );
+            }
+        }
+        NodeEnum::DropStmt(drop) => {
+            let keyword = match ObjectType::try_from(drop.remove_type) {
+                Ok(ObjectType::ObjectIndex) => Token::Index,
+                Ok(ObjectType::ObjectTable) => Token::Table,
+                _ => return out,
+            };
+            let anchor = modifier_anchor(tokens, keyword);
+            // DROP INDEX CONCURRENTLY supports a single index and no CASCADE
+            out.extend(concurrently_suggestion(
+                stmt,
+                "DropIndexCheck",
+                "DROP INDEX without CONCURRENTLY",
+                "DROP INDEX CONCURRENTLY inside a transaction",
+                drop.objects.len() == 1 && drop.behavior != DropBehavior::DropCascade as i32,
+                || anchor.map(|a| TextEdit::insert(a, " CONCURRENTLY")),
+            ));
+            if fired(stmt, "IdempotencyDropCheck") {
+                out.push(
+                    Suggestion::new("IdempotencyDropCheck", 0)
+                        .with_edit(anchor.map(|a| TextEdit::insert(a, " IF EXISTS"))),
+                );
+            }
+        }
+        NodeEnum::ReindexStmt(_) => {
+            out.extend(concurrently_suggestion(
+                stmt,
+                "ReindexCheck",
+                "REINDEX without CONCURRENTLY",
+                "REINDEX CONCURRENTLY inside a transaction",
+                true,
+                || reindex_object_token(tokens).map(|a| TextEdit::insert(a, " CONCURRENTLY")),
+            ));
+        }
+        NodeEnum::RefreshMatViewStmt(refresh) => {
+            // CONCURRENTLY cannot be combined with WITH NO DATA
+            out.extend(concurrently_suggestion(
+                stmt,
+                "RefreshMatViewCheck",
+                "REFRESH MATERIALIZED VIEW without CONCURRENTLY",
+                "REFRESH MATERIALIZED VIEW CONCURRENTLY inside a transaction",
+                !refresh.skip_data,
+                || {
+                    find_token(tokens, Token::View)
+                        .map(|view| TextEdit::insert(token_end(view), " CONCURRENTLY"))
+                },
+            ));
+        }
+        NodeEnum::CreateStmt(_) => {
+            if fired(stmt, "IdempotencyCreateCheck") {
+                out.push(
+                    Suggestion::new("IdempotencyCreateCheck", 0).with_edit(
+                        find_token(tokens, Token::Table)
+                            .map(|table| TextEdit::insert(token_end(table), " IF NOT EXISTS")),
+                    ),
+                );
+            }
+        }
+        NodeEnum::AlterTableStmt(alter) => suggest_alter_table(stmt, alter, tokens, &mut out),
+        _ => {}
+    }
+    out
+}
+
+/// Where CONCURRENTLY or an IF [NOT] EXISTS clause goes: after `keyword`,
+/// or after an existing CONCURRENTLY that follows it.
+fn modifier_anchor(tokens: &[ScanToken], keyword: Token) -> Option<usize> {
+    let pos = tokens.iter().position(|t| t.token == keyword as i32)?;
+    let anchor = match tokens.get(pos + 1) {
+        Some(next) if next.token == Token::Concurrently as i32 => next,
+        _ => &tokens[pos],
+    };
+    Some(token_end(anchor))
+}
+
+/// End of the INDEX/TABLE/SCHEMA/DATABASE keyword in `REINDEX [ (options) ] <kind>`.
+fn reindex_object_token(tokens: &[ScanToken]) -> Option<usize> {
+    let mut depth = 0usize;
+    for token in tokens.iter().skip(1) {
+        match Token::try_from(token.token) {
+            Ok(Token::Ascii40) => depth += 1,
+            Ok(Token::Ascii41) => depth = depth.saturating_sub(1),
+            Ok(Token::Index | Token::Table | Token::Schema | Token::Database) if depth == 0 => {
+                return Some(token_end(token));
+            }
+            _ if depth == 0 => return None,
+            _ => {}
+        }
+    }
+    None
+}
+
+fn suggest_alter_table(
+    stmt: &CheckedStmt,
+    alter: &AlterTableStmt,
+    tokens: &[ScanToken],
+    out: &mut Vec<Suggestion>,
+) {
+    let cmds: Vec<_> = alter
+        .cmds
+        .iter()
+        .filter_map(|n| match &n.node {
+            Some(NodeEnum::AlterTableCmd(cmd)) => Some(cmd.as_ref()),
+            _ => None,
+        })
+        .collect();
+    let segments = split_top_level_commas(tokens);
+    if segments.len() != cmds.len() {
+        return;
+    }
+
+    let idempotency = fired(stmt, "IdempotencyAlterCheck");
+    let foreign_key = fired(stmt, "AddForeignKeyCheck");
+    let check_constraint = fired(stmt, "AddCheckConstraintCheck");
+    let table = alter
+        .relation
+        .as_ref()
+        .map(range_var_name)
+        .unwrap_or_default();
+
+    // Each check reports one violation per matching command, in command order
+    let mut nth_idempotency = 0;
+    let mut nth_foreign_key = 0;
+    let mut nth_check = 0;
+
+    for (cmd, segment) in cmds.iter().zip(&segments) {
+        match AlterTableType::try_from(cmd.subtype) {
+            Ok(AlterTableType::AtAddColumn) if !cmd.missing_ok => {
+                if cmd_def_as_column_def(cmd).is_none() {
+                    continue;
+                }
+                if idempotency {
+                    out.push(
+                        Suggestion::new("IdempotencyAlterCheck", nth_idempotency).with_edit(
+                            keyword_then_column(segment, Token::AddP)
+                                .map(|a| TextEdit::insert(a, " IF NOT EXISTS")),
+                        ),
+                    );
+                }
+                nth_idempotency += 1;
+            }
+            Ok(AlterTableType::AtDropColumn) if !cmd.missing_ok => {
+                if idempotency {
+                    out.push(
+                        Suggestion::new("IdempotencyAlterCheck", nth_idempotency).with_edit(
+                            keyword_then_column(segment, Token::Drop)
+                                .map(|a| TextEdit::insert(a, " IF EXISTS")),
+                        ),
+                    );
+                }
+                nth_idempotency += 1;
+            }
+            Ok(AlterTableType::AtAddConstraint) => {
+                let Some(constraint) = cmd_def_as_constraint(cmd) else {
+                    continue;
+                };
+                if !constraint.initially_valid {
+                    continue;
+                }
+                let (check_name, nth, wanted) =
+                    if constraint.contype == ConstrType::ConstrForeign as i32 {
+                        nth_foreign_key += 1;
+                        ("AddForeignKeyCheck", nth_foreign_key - 1, foreign_key)
+                    } else if constraint.contype == ConstrType::ConstrCheck as i32 {
+                        nth_check += 1;
+                        ("AddCheckConstraintCheck", nth_check - 1, check_constraint)
+                    } else {
+                        continue;
+                    };
+                // VALIDATE CONSTRAINT needs a name; unnamed constraints are left as is
+                if !wanted || constraint.conname.is_empty() {
+                    continue;
+                }
+                let Some(last) = segment.last() else {
+                    continue;
+                };
+                let mut suggestion = Suggestion::new(check_name, nth)
+                    .with_edit(Some(TextEdit::insert(token_end(last), " NOT VALID")));
+                suggestion.validation = Some(Validation {
+                    offset: stmt_end(tokens),
+                    terminated: tokens
+                        .last()
+                        .is_some_and(|t| t.token == Token::Ascii59 as i32),
+                    sql: format!(
+                        "ALTER TABLE {} VALIDATE CONSTRAINT {};",
+                        quote_qualified(&table),
+                        quote_ident(&constraint.conname)
+                    ),
+                });
+                out.push(suggestion);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// End of `keyword` (ADD/DROP) or of the COLUMN keyword right after it.
+fn keyword_then_column(segment: &[ScanToken], keyword: Token) -> Option<usize> {
+    let pos = segment.iter().position(|t| t.token == keyword as i32)?;
+    let anchor = match segment.get(pos + 1) {
+        Some(next) if next.token == Token::Column as i32 => next,
+        _ => &segment[pos],
+    };
+    Some(token_end(anchor))
+}
+
+/// Split a statement's tokens on commas outside parentheses/brackets.
+/// The trailing semicolon is dropped.
+fn split_top_level_commas(tokens: &[ScanToken]) -> Vec<&[ScanToken]> {
+    let tokens = match tokens.last() {
+        Some(last) if last.token == Token::Ascii59 as i32 => &tokens[..tokens.len() - 1],
+        _ => tokens,
+    };
+    let mut segments = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+    for (i, token) in tokens.iter().enumerate() {
+        match Token::try_from(token.token) {
+            Ok(Token::Ascii40 | Token::Ascii91) => depth += 1,
+            Ok(Token::Ascii41 | Token::Ascii93) => depth = depth.saturating_sub(1),
+            Ok(Token::Ascii44) if depth == 0 => {
+                segments.push(&tokens[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    segments.push(&tokens[start..]);
+    segments
+}
+
+/// Offset just past the statement's last token (usually its semicolon).
+fn stmt_end(tokens: &[ScanToken]) -> usize {
+    tokens.last().map_or(0, token_end)
+}
+
+fn find_token(tokens: &[ScanToken], token: Token) -> Option<&ScanToken> {
+    tokens.iter().find(|t| t.token == token as i32)
+}
+
+fn token_end(token: &ScanToken) -> usize {
+    usize::try_from(token.end).unwrap_or(0)
+}
+
+/// Non-comment tokens of the whole file, in order.
+fn significant_tokens(sql: &str) -> Vec<ScanToken> {
+    let Ok(scan) = pg_query::scan(sql) else {
+        return vec![];
+    };
+    scan.tokens
+        .into_iter()
+        .filter(|t| t.token != Token::SqlComment as i32 && t.token != Token::CComment as i32)
+        .collect()
+}
+
+/// Tokens starting inside `span`.
+fn tokens_in(tokens: &[ScanToken], span: std::ops::Range<usize>) -> &[ScanToken] {
+    let start = |t: &ScanToken| usize::try_from(t.start).unwrap_or(0);
+    let from = tokens.partition_point(|t| start(t) < span.start);
+    let to = tokens.partition_point(|t| start(t) < span.end);
+    &tokens[from..to]
+}
+
+/// Quote an identifier unless it is a plain lowercase name.
+fn quote_ident(name: &str) -> String {
+    let plain = name.starts_with(|c: char| c.is_ascii_lowercase() || c == '_')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+    if plain {
+        name.to_string()
+    } else {
+        format!("\"{}\"", name.replace('"', "\"\""))
+    }
+}
+
+fn quote_qualified(name: &str) -> String {
+    name.split('.')
+        .map(quote_ident)
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapters::{DieselAdapter, SqlxAdapter};
+    use crate::checks::Registry;
+    use crate::config::Config;
+
+    fn fix_with(
+        sql: &str,
+        checks: &[&str],
+        ctx: &MigrationContext,
+        adapter: &dyn MigrationAdapter,
+    ) -> FileFix {
+        let config = Config {
+            enable_checks: checks.iter().map(|c| (*c).to_string()).collect(),
+            ..Default::default()
+        };
+        let registry = Registry::with_config(&config);
+        let parsed = crate::parser::parse_with_metadata(sql).unwrap();
+        let checked =
+            registry.check_each_stmt(&parsed.stmts, sql, &parsed.ignore_ranges, &config, ctx);
+        fix_file(
+            Utf8Path::new("migrations/001_test.sql"),
+            sql,
+            sql,
+            &checked,
+            ctx,
+            adapter,
+        )
+    }
+
+    fn fix(sql: &str, checks: &[&str]) -> FileFix {
+        fix_with(sql, checks, &MigrationContext::default(), &SqlxAdapter)
+    }
+
+    fn outside_transaction() -> MigrationContext {
+        MigrationContext {
+            run_in_transaction: false,
+            ..MigrationContext::default()
+        }
+    }
+
+    #[test]
+    fn test_apply_edits_keeps_insertion_order_at_same_offset() {
+        let edits = vec![
+            TextEdit::insert(3, "b"),
+            TextEdit::insert(3, "c"),
+            TextEdit::insert(0, "a"),
+        ];
+        assert_eq!(apply_edits("xyz!", &edits), "axyzbc!");
+    }
+
+    #[test]
+    fn test_adds_concurrently_to_create_index_and_no_transaction_directive() {
+        let fixed = fix(
+            "CREATE INDEX idx_users_email ON users(email);",
+            &["AddIndexCheck"],
+        );
+        assert_eq!(
+            fixed.fixed,
+            "-- no-transaction\nCREATE INDEX CONCURRENTLY idx_users_email ON users(email);"
+        );
+        assert_eq!(fixed.fixed_statements, 1);
+    }
+
+    #[test]
+    fn test_adds_directive_for_concurrently_inside_transaction() {
+        let fixed = fix(
+            "CREATE INDEX CONCURRENTLY idx_users_email ON users(email);",
+            &["AddIndexCheck"],
+        );
+        assert_eq!(
+            fixed.fixed,
+            "-- no-transaction\nCREATE INDEX CONCURRENTLY idx_users_email ON users(email);"
+        );
+    }
+
+    #[test]
+    fn test_no_directive_when_already_outside_transaction() {
+        let fixed = fix_with(
+            "CREATE UNIQUE INDEX idx ON users(email);",
+            &["AddIndexCheck", "IdempotencyIndexCheck"],
+            &outside_transaction(),
+            &SqlxAdapter,
+        );
+        assert_eq!(
+            fixed.fixed,
+            "CREATE UNIQUE INDEX CONCURRENTLY IF NOT EXISTS idx ON users(email);"
+        );
+    }
+
+    #[test]
+    fn test_diesel_writes_metadata_toml() {
+        let fixed = fix_with(
+            "DROP INDEX idx_users_email;",
+            &["DropIndexCheck"],
+            &MigrationContext::default(),
+            &DieselAdapter,
+        );
+        assert_eq!(fixed.fixed, "DROP INDEX CONCURRENTLY idx_users_email;");
+        let metadata = fixed.metadata.expect("metadata.toml should be written");
+        assert_eq!(metadata.path, "migrations/metadata.toml");
+        assert_eq!(metadata.contents, "run_in_transaction = false\n");
+    }
+
+    #[test]
+    fn test_drop_index_cascade_only_gets_if_exists() {
+        let fixed = fix(
+            "DROP INDEX idx_a CASCADE;",
+            &["DropIndexCheck", "IdempotencyDropCheck"],
+        );
+        assert_eq!(fixed.fixed, "DROP INDEX IF EXISTS idx_a CASCADE;");
+    }
+
+    #[test]
+    fn test_reindex_and_refresh_get_concurrently() {
+        let fixed = fix_with(
+            "REINDEX (VERBOSE) TABLE users;\nREFRESH MATERIALIZED VIEW stats;",
+            &["ReindexCheck", "RefreshMatViewCheck"],
+            &outside_transaction(),
+            &SqlxAdapter,
+        );
+        assert_eq!(
+            fixed.fixed,
+            "REINDEX (VERBOSE) TABLE CONCURRENTLY users;\nREFRESH MATERIALIZED VIEW CONCURRENTLY stats;"
+        );
+        assert_eq!(fixed.fixed_statements, 2);
+    }
+
+    #[test]
+    fn test_foreign_key_gets_not_valid_and_follow_up_validate() {
+        let fixed = fix(
+            "ALTER TABLE posts ADD CONSTRAINT fk_user FOREIGN KEY (user_id) REFERENCES users(id);",
+            &["AddForeignKeyCheck"],
+        );
+        assert_eq!(
+            fixed.fixed,
+            "ALTER TABLE posts ADD CONSTRAINT fk_user FOREIGN KEY (user_id) REFERENCES users(id) NOT VALID;"
+        );
+        assert_eq!(
+            fixed.follow_ups,
+            vec!["ALTER TABLE posts VALIDATE CONSTRAINT fk_user;"]
+        );
+    }
+
+    #[test]
+    fn test_check_constraint_validated_in_place_outside_transaction() {
+        let fixed = fix_with(
+            "ALTER TABLE users ADD CONSTRAINT \"Positive\" CHECK (age > 0), ADD COLUMN x INT;",
+            &["AddCheckConstraintCheck"],
+            &outside_transaction(),
+            &SqlxAdapter,
+        );
+        assert_eq!(
+            fixed.fixed,
+            "ALTER TABLE users ADD CONSTRAINT \"Positive\" CHECK (age > 0) NOT VALID, ADD COLUMN x INT;\nALTER TABLE users VALIDATE CONSTRAINT \"Positive\";"
+        );
+        assert!(fixed.follow_ups.is_empty());
+    }
+
+    #[test]
+    fn test_unnamed_foreign_key_is_not_fixed() {
+        let fixed = fix(
+            "ALTER TABLE posts ADD FOREIGN KEY (user_id) REFERENCES users(id);",
+            &["AddForeignKeyCheck"],
+        );
+        assert!(fixed.is_empty());
+    }
+
+    #[test]
+    fn test_idempotency_guards() {
+        let fixed = fix(
+            "CREATE TABLE users (id BIGINT);
+ALTER TABLE users ADD COLUMN email TEXT, DROP legacy, ADD COLUMN IF NOT EXISTS name TEXT;
+DROP TABLE old_users;",
+            &[
+                "IdempotencyCreateCheck",
+                "IdempotencyAlterCheck",
+                "IdempotencyDropCheck",
+            ],
+        );
+        assert_eq!(
+            fixed.fixed,
+            "CREATE TABLE IF NOT EXISTS users (id BIGINT);
+ALTER TABLE users ADD COLUMN IF NOT EXISTS email TEXT, DROP IF EXISTS legacy, ADD COLUMN IF NOT EXISTS name TEXT;
+DROP TABLE IF EXISTS old_users;"
+        );
+        assert_eq!(fixed.fixed_statements, 3);
+    }
+
+    #[test]
+    fn test_safety_assured_statements_are_left_alone() {
+        let sql = "-- safety-assured:start
+CREATE INDEX idx_a ON users(a);
+-- safety-assured:end
+CREATE INDEX idx_b ON users(b);";
+        let fixed = fix_with(
+            sql,
+            &["AddIndexCheck"],
+            &outside_transaction(),
+            &SqlxAdapter,
+        );
+        assert_eq!(
+            fixed.fixed,
+            "-- safety-assured:start
+CREATE INDEX idx_a ON users(a);
+-- safety-assured:end
+CREATE INDEX CONCURRENTLY idx_b ON users(b);"
+        );
+    }
+
+    #[test]
+    fn test_diff_shows_sql_and_new_metadata_file() {
+        let fixed = fix_with(
+            "CREATE INDEX idx ON users(email);\n",
+            &["AddIndexCheck"],
+            &MigrationContext::default(),
+            &DieselAdapter,
+        );
+        let diff = fixed.diff();
+        assert!(diff.contains("--- migrations/001_test.sql"));
+        assert!(diff.contains("-CREATE INDEX idx ON users(email);"));
+        assert!(diff.contains("+CREATE INDEX CONCURRENTLY idx ON users(email);"));
+        assert!(diff.contains("--- /dev/null"));
+        assert!(diff.contains("+run_in_transaction = false"));
+    }
+
+    fn violation_fixes(sql: &str, checks: &[&str], ctx: &MigrationContext) -> Vec<Option<Fix>> {
+        let config = Config {
+            enable_checks: checks.iter().map(|c| (*c).to_string()).collect(),
+            ..Default::default()
+        };
+        let parsed = crate::parser::parse_with_metadata(sql).unwrap();
+        Registry::with_config(&config)
+            .check_stmts_with_context(&parsed.stmts, sql, &parsed.ignore_ranges, &config, ctx)
+            .into_iter()
+            .map(|(_, v)| v.fix)
+            .collect()
+    }
+
+    #[test]
+    fn test_violations_carry_fixes() {
+        let sql = "SELECT 1;\nCREATE INDEX idx ON users(email);";
+        let fixes = violation_fixes(
+            sql,
+            &["AddIndexCheck", "IdempotencyIndexCheck"],
+            &outside_transaction(),
+        );
+        assert_eq!(
+            fixes,
+            vec![
+                Some(Fix {
+                    applicability: Applicability::MachineApplicable,
+                    edits: vec![TextEdit::insert(22, " CONCURRENTLY")],
+                }),
+                Some(Fix {
+                    applicability: Applicability::MachineApplicable,
+                    edits: vec![TextEdit::insert(22, " IF NOT EXISTS")],
+                }),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_violation_fix_maybe_incorrect_inside_transaction() {
+        let fixes = violation_fixes(
+            "ALTER TABLE posts ADD CONSTRAINT fk_user FOREIGN KEY (user_id) REFERENCES users(id);",
+            &["AddForeignKeyCheck"],
+            &MigrationContext::default(),
+        );
+        let fix = fixes[0].as_ref().unwrap();
+        assert_eq!(fix.applicability, Applicability::MaybeIncorrect);
+        assert_eq!(fix.edits, vec![TextEdit::insert(83, " NOT VALID")]);
+
+        let fixes = violation_fixes(
+            "CREATE INDEX CONCURRENTLY idx ON users(email);",
+            &["AddIndexCheck"],
+            &MigrationContext::default(),
+        );
+        assert_eq!(fixes, vec![None]);
+    }
+
+    #[test]
+    fn test_violation_fix_validates_in_place_outside_transaction() {
+        let sql =
+            "ALTER TABLE users ADD CONSTRAINT a CHECK (x > 0), ADD CONSTRAINT b CHECK (y > 0)";
+        let fixes = violation_fixes(sql, &["AddCheckConstraintCheck"], &outside_transaction());
+        let edits: Vec<_> = fixes
+            .iter()
+            .map(|f| f.as_ref().unwrap().edits.clone())
+            .collect();
+        assert_eq!(
+            apply_edits(sql, &edits[1]),
+            "ALTER TABLE users ADD CONSTRAINT a CHECK (x > 0), ADD CONSTRAINT b CHECK (y > 0) NOT VALID;\nALTER TABLE users VALIDATE CONSTRAINT b;"
+        );
+        assert_eq!(
+            apply_edits(sql, &edits[0]),
+            "ALTER TABLE users ADD CONSTRAINT a CHECK (x > 0) NOT VALID, ADD CONSTRAINT b CHECK (y > 0);\nALTER TABLE users VALIDATE CONSTRAINT a;"
+        );
+    }
+
+    #[test]
+    fn test_rejects_custom_edits_out_of_range_or_overlapping() {
+        let sql = "ALTER TABLE users ADD COLUMN a INT;";
+        let node = crate::checks::test_utils::parse_sql(sql);
+        let custom = |start, end| {
+            Violation::new("custom", "p", "s")
+                .with_check_name("my_check")
+                .with_fix(Fix {
+                    applicability: Applicability::MachineApplicable,
+                    edits: vec![TextEdit {
+                        start,
+                        end,
+                        replacement: "x".to_string(),
+                    }],
+                })
+        };
+        let operations = |violations: Vec<Violation>| {
+            let mut stmt = CheckedStmt {
+                node: &node,
+                span: 0..sql.len(),
+                line: 1,
+                violations,
+            };
+            reject_invalid_custom_fixes(sql, &mut stmt);
+            stmt.violations
+                .into_iter()
+                .map(|v| v.operation)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(operations(vec![custom(12, 17)]), ["custom"]);
+        assert_eq!(operations(vec![custom(30, 40)]), ["SCRIPT ERROR: my_check"]);
+        assert_eq!(
+            operations(vec![custom(12, 17), custom(15, 15)]),
+            ["custom", "SCRIPT ERROR: my_check"]
+        );
+        assert_eq!(
+            operations(vec![custom(12, 17), custom(12, 17)]),
+            ["custom", "custom"]
+        );
+    }
+
+    #[test]
+    fn test_quote_ident() {
+        assert_eq!(quote_ident("users"), "users");
+        assert_eq!(quote_ident("Users"), "\"Users\"");
+        assert_eq!(quote_qualified("app.user_events"), "app.user_events");
+    }
+}
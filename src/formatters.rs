@@ -1,7 +1,9 @@
 use crate::ViolationList;
-use crate::checks::Check;
+use crate::baseline::file_key;
+use crate::checks::{Check, OPT_IN_CHECKS};
 use crate::config::Config;
-use crate::violation::Severity;
+use crate::violation::{Severity, Violation};
+use camino::{Utf8Path, Utf8PathBuf};
 use colored::Colorize;
 use serde_json;
 use std::fmt::Write;
@@ -16,6 +18,29 @@ pub struct JsonFormatter;
 pub struct TextFormatter;
 pub struct GithubFormatter;
 
+/// JUnit XML report: one testsuite per migration file, one testcase per check.
+///
+/// Errors are reported as failures and warnings as skipped testcases. Files passed
+/// to [`JunitFormatter::with_files`] get a suite of passing testcases when clean.
+#[derive(Default)]
+pub struct JunitFormatter {
+    check_names: Vec<String>,
+    files: Vec<String>,
+}
+
+/// GitLab Code Quality report (the Code Climate JSON subset GitLab reads).
+pub struct GitlabFormatter;
+
+/// SARIF 2.1.0 log, as accepted by GitHub code scanning.
+///
+/// Results reference rules by index, so the formatter is built from the checks
+/// that should appear in the rule metadata (usually every built-in and custom check).
+#[derive(Default)]
+pub struct SarifFormatter {
+    rules: Vec<serde_json::Value>,
+    rule_ids: Vec<String>,
+}
+
 impl Formatter for JsonFormatter {
     fn format_results(&self, results: &[(String, ViolationList)]) -> String {
         let output: Vec<_> = results
@@ -23,14 +48,23 @@ impl Formatter for JsonFormatter {
             .map(|(file, violations)| {
                 serde_json::json!({
                     "file": file,
-                    "violations": violations.iter().map(|(line, v)| serde_json::json!({
-                        "line": line,
-                        "check_name": v.check_name,
-                        "operation": v.operation,
-                        "problem": v.problem,
-                        "safe_alternative": v.safe_alternative,
-                        "severity": v.severity,
-                    })).collect::<Vec<_>>(),
+                    "violations": violations.iter().map(|(line, v)| {
+                        let mut obj = serde_json::json!({
+                            "line": line,
+                            "check_name": v.check_name,
+                            "operation": v.operation,
+                            "problem": v.problem,
+                            "safe_alternative": v.safe_alternative,
+                            "severity": v.severity,
+                        });
+                        if let Some(table_size) = &v.table_size {
+                            obj["table_size"] = serde_json::json!(table_size);
+                        }
+                        if let Some(fix) = &v.fix {
+                            obj["fix"] = serde_json::json!(fix);
+                        }
+                        obj
+                    }).collect::<Vec<_>>(),
                 })
             })
             .collect();
@@ -71,6 +105,18 @@ impl Formatter for JsonFormatter {
             Some(text) => serde_json::Value::String(text),
             None => serde_json::Value::Null,
         };
+        obj["options"] = check
+            .options()
+            .iter()
+            .map(|o| {
+                serde_json::json!({
+                    "name": o.name,
+                    "type": o.kind.type_name(),
+                    "description": o.description,
+                    "value": config.checks.get(check.name()).and_then(|t| t.get(o.name)),
+                })
+            })
+            .collect();
         serde_json::to_string_pretty(&obj).unwrap_or_else(|_| "{}".into())
     }
 }
@@ -120,6 +166,8 @@ impl Formatter for TextFormatter {
             };
             let enabled = if config.is_check_enabled(c.name()) {
                 "yes"
+            } else if OPT_IN_CHECKS.contains(&c.name()) {
+                "opt-in"
             } else {
                 "no"
             };
@@ -149,6 +197,8 @@ impl Formatter for TextFormatter {
         };
         let enabled_str = if config.is_check_enabled(check.name()) {
             "enabled"
+        } else if OPT_IN_CHECKS.contains(&check.name()) {
+            "opt-in"
         } else {
             "disabled"
         };
@@ -164,6 +214,25 @@ impl Formatter for TextFormatter {
             }
             None => out.push_str("No documentation available.\n"),
         }
+        if !check.options().is_empty() {
+            writeln!(
+                out,
+                "\nOptions ([checks.{}] in diesel-guard.toml):",
+                check.name()
+            )
+            .unwrap();
+            for option in check.options() {
+                write!(out, "  {} ({})", option.name, option.kind.type_name()).unwrap();
+                if let Some(value) = config
+                    .checks
+                    .get(check.name())
+                    .and_then(|t| t.get(option.name))
+                {
+                    write!(out, " = {value}").unwrap();
+                }
+                writeln!(out, "\n      {}", option.description).unwrap();
+            }
+        }
         out
     }
 }
@@ -177,7 +246,7 @@ impl Formatter for GithubFormatter {
                     Severity::Error => "error",
                     Severity::Warning => "warning",
                 };
-                let raw = format!("{}: {}", violation.operation, violation.problem);
+                let raw = format!("{}: {}", violation.operation, violation.full_problem());
                 let file = encode_property(file_path);
                 let message = encode_data(&raw);
                 writeln!(out, "::{level} file={file},line={line}::{message}").unwrap();
@@ -195,6 +264,435 @@ impl Formatter for GithubFormatter {
     }
 }
 
+impl JunitFormatter {
+    pub fn new(checks: &[&dyn Check]) -> Self {
+        Self {
+            check_names: checks.iter().map(|c| c.name().to_string()).collect(),
+            files: Vec::new(),
+        }
+    }
+
+    /// List every checked file, so files without violations get a passing testsuite.
+    #[must_use]
+    pub fn with_files(mut self, files: Vec<String>) -> Self {
+        self.files = files;
+        self
+    }
+
+    /// Append the testsuite for one file; returns its (tests, failures, skipped) counts.
+    fn write_suite(
+        &self,
+        out: &mut String,
+        file: &str,
+        violations: &ViolationList,
+    ) -> (usize, usize, usize) {
+        // Every check that ran gets a testcase; a violation from a check that is not
+        // in the list (e.g. a script error) still gets its own
+        let mut names: Vec<&str> = self.check_names.iter().map(String::as_str).collect();
+        for (_, v) in violations {
+            if !names.contains(&v.check_name.as_str()) {
+                names.push(&v.check_name);
+            }
+        }
+
+        let cases: Vec<(&str, Vec<&(usize, Violation)>)> = names
+            .into_iter()
+            .map(|name| {
+                let found = violations.iter().filter(|(_, v)| v.check_name == name);
+                (name, found.collect())
+            })
+            .collect();
+        let failures = cases
+            .iter()
+            .filter(|(_, found)| found.iter().any(|(_, v)| v.severity == Severity::Error))
+            .count();
+        let skipped = cases
+            .iter()
+            .filter(|(_, found)| {
+                !found.is_empty() && found.iter().all(|(_, v)| v.severity == Severity::Warning)
+            })
+            .count();
+
+        let file = escape_xml(file);
+        writeln!(
+            out,
+            r#"  <testsuite name="{file}" tests="{}" failures="{failures}" errors="0" skipped="{skipped}">"#,
+            cases.len()
+        )
+        .unwrap();
+        for (name, found) in &cases {
+            let name = escape_xml(name);
+            if found.is_empty() {
+                writeln!(out, r#"    <testcase name="{name}" classname="{file}"/>"#).unwrap();
+                continue;
+            }
+            writeln!(out, r#"    <testcase name="{name}" classname="{file}">"#).unwrap();
+            let message = escape_xml(&junit_message(found));
+            let details: Vec<String> = found
+                .iter()
+                .map(|(line, v)| {
+                    format!(
+                        "line {line}: {}: {}\n\nSafe alternative:\n{}",
+                        v.operation,
+                        v.full_problem(),
+                        v.safe_alternative
+                    )
+                })
+                .collect();
+            let details = escape_xml(&details.join("\n\n"));
+            if found.iter().any(|(_, v)| v.severity == Severity::Error) {
+                writeln!(
+                    out,
+                    r#"      <failure message="{message}" type="{name}">{details}</failure>"#
+                )
+                .unwrap();
+            } else {
+                writeln!(out, r#"      <skipped message="{message}"/>"#).unwrap();
+                writeln!(out, "      <system-out>{details}</system-out>").unwrap();
+            }
+            writeln!(out, "    </testcase>").unwrap();
+        }
+        writeln!(out, "  </testsuite>").unwrap();
+        (cases.len(), failures, skipped)
+    }
+}
+
+impl Formatter for JunitFormatter {
+    fn format_results(&self, results: &[(String, ViolationList)]) -> String {
+        let mut suites = String::new();
+        let (mut tests, mut failures, mut skipped) = (0, 0, 0);
+        // Checked files in order, then files that only appear in the results
+        // (e.g. an edited shipped migration outside the checked range)
+        let mut files: Vec<&str> = self.files.iter().map(String::as_str).collect();
+        for (file, _) in results {
+            if !files.contains(&file.as_str()) {
+                files.push(file);
+            }
+        }
+        let no_violations = ViolationList::new();
+        for file in files {
+            let violations = results
+                .iter()
+                .find(|(f, _)| f == file)
+                .map_or(&no_violations, |(_, v)| v);
+            let counts = self.write_suite(&mut suites, file, violations);
+            tests += counts.0;
+            failures += counts.1;
+            skipped += counts.2;
+        }
+
+        let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        writeln!(
+            out,
+            r#"<testsuites name="diesel-guard" tests="{tests}" failures="{failures}" errors="0" skipped="{skipped}">"#
+        )
+        .unwrap();
+        out.push_str(&suites);
+        out.push_str("</testsuites>\n");
+        out
+    }
+
+    fn format_checks(&self, checks: &[&dyn Check], config: &Config) -> String {
+        TextFormatter.format_checks(checks, config)
+    }
+
+    fn format_explain(&self, check: &dyn Check, config: &Config) -> String {
+        TextFormatter.format_explain(check, config)
+    }
+}
+
+/// One-line summary of a check's violations in a file.
+fn junit_message(found: &[&(usize, Violation)]) -> String {
+    if let [(line, v)] = found {
+        return format!("line {line}: {}", v.operation);
+    }
+    let lines: Vec<String> = found.iter().map(|(line, _)| line.to_string()).collect();
+    format!("{} violations (lines {})", found.len(), lines.join(", "))
+}
+
+/// Escape text for XML attributes and content, dropping characters XML 1.0 cannot represent.
+fn escape_xml(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            '\t' | '\n' | '\r' => out.push(c),
+            c if c < ' ' => {}
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+impl Formatter for GitlabFormatter {
+    fn format_results(&self, results: &[(String, ViolationList)]) -> String {
+        let root = report_root();
+        let mut issues = Vec::new();
+        for (file, violations) in results {
+            // GitLab maps issues to the diff by their path from the repository root
+            let path = &file_key(Utf8Path::new(file), &root);
+            // Identical violations in one file are told apart by their order,
+            // not their line, so moving unrelated lines keeps every fingerprint
+            let mut seen: Vec<(&str, &str, &str)> = Vec::new();
+            for (line, v) in violations {
+                let key = (
+                    v.check_name.as_str(),
+                    v.operation.as_str(),
+                    v.problem.as_str(),
+                );
+                let occurrence = seen.iter().filter(|k| **k == key).count();
+                seen.push(key);
+
+                issues.push(serde_json::json!({
+                    "description": format!("{}: {}", v.operation, v.full_problem()),
+                    "check_name": v.check_name,
+                    "fingerprint": gitlab_fingerprint(path, v, occurrence),
+                    "severity": match v.severity {
+                        Severity::Error => "major",
+                        Severity::Warning => "minor",
+                    },
+                    "location": {
+                        "path": path,
+                        "lines": { "begin": line },
+                    },
+                }));
+            }
+        }
+        serde_json::to_string_pretty(&issues).unwrap_or_else(|_| "[]".into())
+    }
+
+    fn format_checks(&self, checks: &[&dyn Check], config: &Config) -> String {
+        TextFormatter.format_checks(checks, config)
+    }
+
+    fn format_explain(&self, check: &dyn Check, config: &Config) -> String {
+        TextFormatter.format_explain(check, config)
+    }
+}
+
+/// Directory report paths are relative to: the root of the git repository, or
+/// the current directory outside one. Paths then don't depend on where
+/// diesel-guard runs or how the migrations were passed to it.
+fn report_root() -> Utf8PathBuf {
+    crate::git::toplevel()
+        .or_else(|| camino::absolute_utf8(".").ok())
+        .unwrap_or_default()
+}
+
+/// SHA-256 of everything that identifies a violation except its line number
+/// and the table size from `table_stats`.
+fn gitlab_fingerprint(path: &str, violation: &Violation, occurrence: usize) -> String {
+    use sha2::{Digest, Sha256};
+
+    let mut hasher = Sha256::new();
+    for part in [
+        path,
+        &violation.check_name,
+        &violation.operation,
+        &violation.problem,
+        &occurrence.to_string(),
+    ] {
+        hasher.update(part.as_bytes());
+        hasher.update([0]);
+    }
+    hasher.finalize().iter().fold(String::new(), |mut hex, b| {
+        write!(hex, "{b:02x}").unwrap();
+        hex
+    })
+}
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+const DOCS_URL: &str = "https://ayarotsky.github.io/diesel-guard/";
+
+impl SarifFormatter {
+    pub fn new(checks: &[&dyn Check], config: &Config) -> Self {
+        Self {
+            rules: checks.iter().map(|c| sarif_rule(*c, config)).collect(),
+            rule_ids: checks.iter().map(|c| c.name().to_string()).collect(),
+        }
+    }
+
+    fn result(
+        &self,
+        location: &serde_json::Value,
+        line: usize,
+        violation: &Violation,
+    ) -> serde_json::Value {
+        let mut region = serde_json::json!({ "startLine": line });
+        if let Some(column) = violation.column {
+            region["startColumn"] = column.into();
+        }
+        let mut result = serde_json::json!({
+            "ruleId": violation.check_name,
+            "level": sarif_level(violation.severity),
+            "message": { "text": format!("{}: {}", violation.operation, violation.full_problem()) },
+            "locations": [{
+                "physicalLocation": {
+                    "artifactLocation": location,
+                    "region": region,
+                }
+            }],
+        });
+        if let Some(index) = self
+            .rule_ids
+            .iter()
+            .position(|id| *id == violation.check_name)
+        {
+            result["ruleIndex"] = index.into();
+        }
+        if let Some(fix) = &violation.fix {
+            result["fixes"] = serde_json::json!([{
+                "properties": { "applicability": fix.applicability },
+                "artifactChanges": [{
+                    "artifactLocation": location,
+                    "replacements": fix.edits.iter().map(|edit| serde_json::json!({
+                        "deletedRegion": {
+                            "byteOffset": edit.start,
+                            "byteLength": edit.end - edit.start,
+                        },
+                        "insertedContent": { "text": edit.replacement },
+                    })).collect::<Vec<_>>(),
+                }],
+            }]);
+        }
+        result
+    }
+}
+
+impl Formatter for SarifFormatter {
+    fn format_results(&self, results: &[(String, ViolationList)]) -> String {
+        let root = report_root();
+        let results: Vec<_> = results
+            .iter()
+            .flat_map(|(file, violations)| {
+                let location = sarif_artifact_location(file, &root);
+                violations
+                    .iter()
+                    .map(move |(line, v)| self.result(&location, *line, v))
+            })
+            .collect();
+        sarif_log(&self.rules, &results, Some(&root))
+    }
+
+    fn format_checks(&self, checks: &[&dyn Check], config: &Config) -> String {
+        let rules: Vec<_> = checks.iter().map(|c| sarif_rule(*c, config)).collect();
+        sarif_log(&rules, &[], None)
+    }
+
+    fn format_explain(&self, check: &dyn Check, config: &Config) -> String {
+        sarif_log(&[sarif_rule(check, config)], &[], None)
+    }
+}
+
+/// Base id of URIs relative to the repository root.
+const SARIF_SRCROOT: &str = "%SRCROOT%";
+
+/// Where a result's file is: a URI relative to [`SARIF_SRCROOT`], or a
+/// `file://` URI for files outside the repository.
+fn sarif_artifact_location(file: &str, root: &Utf8Path) -> serde_json::Value {
+    let path = file_key(Utf8Path::new(file), root);
+    if Utf8Path::new(&path).has_root() {
+        serde_json::json!({ "uri": file_uri(&path) })
+    } else {
+        serde_json::json!({ "uri": percent_encode(&path), "uriBaseId": SARIF_SRCROOT })
+    }
+}
+
+/// `file://` URI of an absolute path.
+fn file_uri(path: &str) -> String {
+    let path = path.replace('\\', "/");
+    let slash = if path.starts_with('/') { "" } else { "/" };
+    format!("file://{slash}{}", percent_encode(&path))
+}
+
+/// Percent-encode everything in a path except unreserved characters and `/`.
+fn percent_encode(path: &str) -> String {
+    let mut out = String::with_capacity(path.len());
+    for byte in path.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~/".contains(&byte) {
+            out.push(char::from(byte));
+        } else {
+            write!(out, "%{byte:02X}").unwrap();
+        }
+    }
+    out
+}
+
+/// SARIF log with one run; `root` is what [`SARIF_SRCROOT`] stands for.
+fn sarif_log(
+    rules: &[serde_json::Value],
+    results: &[serde_json::Value],
+    root: Option<&Utf8Path>,
+) -> String {
+    let mut log = serde_json::json!({
+        "$schema": SARIF_SCHEMA,
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "diesel-guard",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "informationUri": DOCS_URL,
+                    "rules": rules,
+                }
+            },
+            "columnKind": "unicodeCodePoints",
+            "results": results,
+        }]
+    });
+    if let Some(root) = root {
+        log["runs"][0]["originalUriBaseIds"] = serde_json::json!({
+            SARIF_SRCROOT: { "uri": file_uri(&format!("{root}/")) },
+        });
+    }
+    serde_json::to_string_pretty(&log).unwrap_or_else(|_| "{}".into())
+}
+
+fn sarif_rule(check: &dyn Check, config: &Config) -> serde_json::Value {
+    let doc = check.doc().map(str::to_owned).or_else(|| check.describe());
+    // Check docs start with a markdown heading such as "# Adding an Index"
+    let title = doc
+        .as_deref()
+        .and_then(|d| d.lines().find(|l| !l.trim().is_empty()))
+        .map_or_else(
+            || check.name().to_string(),
+            |l| l.trim_start_matches('#').trim().to_string(),
+        );
+    let severity = if config.is_check_warning(check.name()) {
+        Severity::Warning
+    } else {
+        Severity::Error
+    };
+
+    let mut rule = serde_json::json!({
+        "id": check.name(),
+        "name": check.name(),
+        "shortDescription": { "text": title },
+        "defaultConfiguration": {
+            "level": sarif_level(severity),
+            "enabled": config.is_check_enabled(check.name()),
+        },
+        "properties": {
+            "type": if check.script_path().is_some() { "custom" } else { "builtin" },
+        },
+    });
+    if let Some(doc) = doc {
+        rule["help"] = serde_json::json!({ "text": doc, "markdown": doc });
+    }
+    rule
+}
+
+fn sarif_level(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Error => "error",
+        Severity::Warning => "warning",
+    }
+}
+
 fn format_file_violations(file_path: &str, violations: &ViolationList) -> String {
     let mut output = String::new();
 
@@ -231,7 +729,7 @@ fn format_file_violations(file_path: &str, violations: &ViolationList) -> String
         .unwrap();
 
         writeln!(output, "{}", "Problem:".white().bold()).unwrap();
-        write!(output, "  {}\n\n", violation.problem).unwrap();
+        write!(output, "  {}\n\n", violation.full_problem()).unwrap();
 
         writeln!(output, "{}", "Safe alternative:".green().bold()).unwrap();
         for safe_line in violation.safe_alternative.lines() {
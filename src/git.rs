@@ -0,0 +1,197 @@
+//! Git helpers for `check --since` / `--changed-only` and `--shipped-at`,
+//! using the local `git` binary.
+
+use crate::error::{DieselGuardError, Result};
+use camino::{Utf8Path, Utf8PathBuf};
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::io::{Read, Write};
+use std::process::{Command, Stdio};
+
+/// Files added, modified or renamed since `since`, plus untracked files.
+///
+/// The diff runs from the merge base of `since` and `HEAD` to the working tree,
+/// so on a branch it covers the branch's commits and any uncommitted changes.
+/// Renamed files (e.g. a migration directory renamed by `diesel migration generate`)
+/// are reported under their new path. Paths are absolute.
+pub fn changed_files(since: &str) -> Result<Vec<Utf8PathBuf>> {
+    let root = Utf8PathBuf::from(git(&["rev-parse", "--show-toplevel"])?.trim());
+    let base = git(&["merge-base", since, "HEAD"])?;
+
+    let diff = git(&[
+        "diff",
+        "--name-only",
+        "--find-renames",
+        "--diff-filter=d",
+        "-z",
+        base.trim(),
+        "--",
+    ])?;
+    // ls-files only lists the current directory, so run it at the top level
+    let untracked = git(&[
+        "-C",
+        root.as_str(),
+        "ls-files",
+        "--others",
+        "--exclude-standard",
+        "-z",
+        "--full-name",
+    ])?;
+
+    Ok(diff
+        .split('\0')
+        .chain(untracked.split('\0'))
+        .filter(|path| !path.is_empty())
+        .map(|path| canonical(&root.join(path)))
+        .collect())
+}
+
+/// Root of the git repository around the current directory, if there is one.
+pub fn toplevel() -> Option<Utf8PathBuf> {
+    let root = git(&["rev-parse", "--show-toplevel"]).ok()?;
+    Some(canonical(Utf8Path::new(root.trim())))
+}
+
+/// Contents of `paths` as of `rev`, in the same order.
+///
+/// A file renamed since `rev` is looked up under its old name. `None` means the
+/// file did not exist at `rev`.
+pub fn contents_at(rev: &str, paths: &[Utf8PathBuf]) -> Result<Vec<Option<Vec<u8>>>> {
+    let root = canonical(Utf8Path::new(
+        git(&["rev-parse", "--show-toplevel"])?.trim(),
+    ));
+    // cat-file reports a bad ref as missing files, so check it up front
+    git(&[
+        "rev-parse",
+        "--verify",
+        "--quiet",
+        &format!("{rev}^{{commit}}"),
+    ])?;
+    let renames = renamed_since(rev)?;
+
+    let mut input = String::new();
+    for path in paths {
+        let path = canonical(path);
+        let relative = path
+            .strip_prefix(&root)
+            .map_or_else(|_| path.to_string(), |p| p.as_str().replace('\\', "/"));
+        let old = renames.get(&relative).unwrap_or(&relative);
+        let _ = writeln!(input, "{rev}:{old}");
+    }
+
+    let output = git_with_input(&["cat-file", "--batch"], input)?;
+    parse_batch(&output, paths.len())
+}
+
+/// Files renamed between `rev` and the working tree: new path -> old path,
+/// both relative to the repository root.
+fn renamed_since(rev: &str) -> Result<HashMap<String, String>> {
+    let diff = git(&[
+        "diff",
+        "--name-status",
+        "--find-renames",
+        "--diff-filter=R",
+        "-z",
+        rev,
+        "--",
+    ])?;
+    let mut fields = diff.split('\0').filter(|f| !f.is_empty());
+    let mut renames = HashMap::new();
+    // Each rename is "R<score>", old path, new path
+    while let (Some(_status), Some(old), Some(new)) = (fields.next(), fields.next(), fields.next())
+    {
+        renames.insert(new.to_string(), old.to_string());
+    }
+    Ok(renames)
+}
+
+/// Parse `git cat-file --batch` output: a "<oid> <type> <size>" header and the
+/// object's bytes per found object, "<name> missing" for the rest.
+fn parse_batch(mut output: &[u8], count: usize) -> Result<Vec<Option<Vec<u8>>>> {
+    let malformed = || DieselGuardError::GitError {
+        command: "cat-file".to_string(),
+        msg: "unexpected output".to_string(),
+    };
+    let mut contents = Vec::with_capacity(count);
+    for _ in 0..count {
+        let newline = output
+            .iter()
+            .position(|b| *b == b'\n')
+            .ok_or_else(malformed)?;
+        let header = String::from_utf8_lossy(&output[..newline]).into_owned();
+        output = &output[newline + 1..];
+        if header.ends_with(" missing") || header.ends_with(" ambiguous") {
+            contents.push(None);
+            continue;
+        }
+        let size: usize = header
+            .rsplit(' ')
+            .next()
+            .and_then(|size| size.parse().ok())
+            .ok_or_else(malformed)?;
+        let body = output.get(..size).ok_or_else(malformed)?;
+        contents.push(Some(body.to_vec()));
+        // Skip the object and the newline that follows it
+        output = output.get(size + 1..).unwrap_or_default();
+    }
+    Ok(contents)
+}
+
+/// Resolve symlinks so paths from git and from the migration walk compare equal.
+pub(crate) fn canonical(path: &Utf8Path) -> Utf8PathBuf {
+    path.canonicalize_utf8().unwrap_or_else(|_| path.to_owned())
+}
+
+fn git_with_input(args: &[&str], input: String) -> Result<Vec<u8>> {
+    let command = args.first().copied().unwrap_or_default().to_string();
+    let error = |msg: String| DieselGuardError::GitError {
+        command: command.clone(),
+        msg,
+    };
+    let mut child = Command::new("git")
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| error(e.to_string()))?;
+
+    // Write from a separate thread so a full stdout pipe can't block us
+    let mut stdin = child.stdin.take().ok_or_else(|| error("no stdin".into()))?;
+    let writer = std::thread::spawn(move || stdin.write_all(input.as_bytes()));
+    let mut stdout = Vec::new();
+    if let Some(mut out) = child.stdout.take() {
+        out.read_to_end(&mut stdout)
+            .map_err(|e| error(e.to_string()))?;
+    }
+    let output = child.wait_with_output().map_err(|e| error(e.to_string()))?;
+    let _ = writer.join();
+    if !output.status.success() {
+        return Err(error(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+    Ok(stdout)
+}
+
+fn git(args: &[&str]) -> Result<String> {
+    let command = args.first().copied().unwrap_or_default().to_string();
+    let output =
+        Command::new("git")
+            .args(args)
+            .output()
+            .map_err(|e| DieselGuardError::GitError {
+                command: command.clone(),
+                msg: e.to_string(),
+            })?;
+    if !output.status.success() {
+        return Err(DieselGuardError::GitError {
+            command,
+            msg: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        });
+    }
+    String::from_utf8(output.stdout).map_err(|e| DieselGuardError::GitError {
+        command,
+        msg: e.to_string(),
+    })
+}
@@ -16,19 +16,26 @@
 
 pub mod adapters;
 pub mod ast_dump;
+pub mod baseline;
 pub mod checks;
 pub mod config;
+pub mod detect;
 pub mod error;
+pub mod fix;
 pub mod formatters;
+pub mod git;
+pub mod lock;
 pub mod parser;
 pub mod safety_checker;
+pub mod schema;
 pub mod scripting;
+pub mod table_stats;
 pub mod violation;
 
 pub use adapters::{MigrationAdapter, MigrationContext, MigrationFile};
-pub use config::{Config, ConfigError};
+pub use config::{Config, ConfigError, MigrationRoot, Override, SmallTablesMode};
 pub use safety_checker::SafetyChecker;
-pub use violation::Violation;
+pub use violation::{Applicability, Fix, TextEdit, Violation};
 
 /// A list of `(line_number, violation)` pairs produced by a single SQL file.
 ///
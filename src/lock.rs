@@ -0,0 +1,304 @@
+//! Detection of edits to migrations that already shipped.
+//!
+//! Once a migration has run in production, editing it does nothing there: the
+//! database has recorded it as applied and won't run it again. Fresh databases
+//! built from the edited file then drift from production, and SQLx refuses to
+//! run at all when a recorded checksum changes.
+//!
+//! `diesel-guard lock` records a content hash of every up migration in
+//! `diesel-guard.lock`, keyed by the migration timestamp. `diesel-guard check`
+//! compares the migrations against that file (or against their content at a
+//! git ref with `--shipped-at`) and reports every edited one, regardless of
+//! `start_after`. Repeatable migrations (Flyway `R__`) are meant to be edited
+//! and are left out.
+
+use crate::adapters::MigrationFile;
+use crate::baseline::file_key;
+use crate::error::{DieselGuardError, Result};
+use crate::git;
+use crate::violation::Violation;
+use camino::{Utf8Path, Utf8PathBuf};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+
+/// Lock file read by `check` and written by `lock` by default.
+pub const DEFAULT_LOCK_PATH: &str = "diesel-guard.lock";
+
+/// Check name reported on violations for edited shipped migrations.
+pub const MODIFIED_MIGRATION_CHECK: &str = "ModifiedMigration";
+
+const LOCK_VERSION: u32 = 1;
+
+/// Recorded content of one up migration.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct LockEntry {
+    pub timestamp: String,
+    pub file: String,
+    pub sha256: String,
+}
+
+/// Contents of a lock file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MigrationLock {
+    version: u32,
+    pub migrations: Vec<LockEntry>,
+}
+
+impl MigrationLock {
+    pub fn new(mut migrations: Vec<LockEntry>) -> Self {
+        migrations.sort();
+        Self {
+            version: LOCK_VERSION,
+            migrations,
+        }
+    }
+
+    /// Hash the current content of `files`, except repeatable migrations.
+    ///
+    /// File names are recorded relative to `base` (see [`file_key`]).
+    pub fn from_files(files: &[MigrationFile], base: &Utf8Path) -> Result<Self> {
+        let mut migrations = Vec::with_capacity(files.len());
+        for file in files.iter().filter(|f| !f.repeatable) {
+            migrations.push(LockEntry {
+                timestamp: file.timestamp.clone(),
+                file: file_key(&file.path, base),
+                sha256: content_hash(&fs::read(&file.path)?),
+            });
+        }
+        Ok(Self::new(migrations))
+    }
+
+    /// Read a lock file.
+    pub fn load(path: &Utf8Path) -> Result<Self> {
+        let contents = fs::read_to_string(path)?;
+        let invalid = |msg: String| DieselGuardError::LockError {
+            path: path.to_string(),
+            msg,
+        };
+        let lock: Self = serde_json::from_str(&contents).map_err(|e| invalid(e.to_string()))?;
+        if lock.version != LOCK_VERSION {
+            return Err(invalid(format!(
+                "unsupported version {} (expected {LOCK_VERSION})",
+                lock.version
+            )));
+        }
+        Ok(lock)
+    }
+
+    /// Write the lock as pretty-printed JSON.
+    pub fn write(&self, path: &Utf8Path) -> Result<()> {
+        let mut json = serde_json::to_string_pretty(self).unwrap_or_default();
+        json.push('\n');
+        fs::write(path, json)?;
+        Ok(())
+    }
+
+    /// The recorded entry for `file`.
+    ///
+    /// Entries are matched by timestamp, so a migration directory that was
+    /// renamed without changing its timestamp is still the same migration.
+    /// When several entries share a timestamp the file name decides.
+    fn entry_for(&self, file: &MigrationFile, base: &Utf8Path) -> Option<&LockEntry> {
+        let key = file_key(&file.path, base);
+        let mut same_timestamp = self
+            .migrations
+            .iter()
+            .filter(|entry| entry.timestamp == file.timestamp);
+        match (same_timestamp.next(), same_timestamp.next()) {
+            (Some(entry), None) => Some(entry),
+            _ => self
+                .migrations
+                .iter()
+                .find(|entry| entry.timestamp == file.timestamp && entry.file == key),
+        }
+    }
+}
+
+/// Where the shipped version of each migration comes from.
+#[derive(Debug, Clone)]
+pub enum ShippedMigrations {
+    /// Hashes recorded by `diesel-guard lock`.
+    Lock {
+        path: Utf8PathBuf,
+        lock: MigrationLock,
+    },
+    /// Migration content at a git ref (e.g. the main branch).
+    GitRef(String),
+}
+
+impl ShippedMigrations {
+    /// Violations for the up migrations in `history` that changed since they shipped.
+    ///
+    /// Migrations that didn't exist yet are new, not edited, and are skipped.
+    /// So are repeatable migrations, which run again after every edit.
+    pub(crate) fn modified(
+        &self,
+        history: &[MigrationFile],
+        base: &Utf8Path,
+    ) -> Result<Vec<(Utf8PathBuf, Violation)>> {
+        let history: Vec<&MigrationFile> = history.iter().filter(|f| !f.repeatable).collect();
+        let shipped: Vec<Option<String>> = match self {
+            Self::Lock { lock, .. } => history
+                .iter()
+                .map(|file| lock.entry_for(file, base).map(|entry| entry.sha256.clone()))
+                .collect(),
+            Self::GitRef(rev) => {
+                let paths: Vec<Utf8PathBuf> = history.iter().map(|f| f.path.clone()).collect();
+                git::contents_at(rev, &paths)?
+                    .into_iter()
+                    .map(|contents| contents.map(|c| content_hash(&c)))
+                    .collect()
+            }
+        };
+
+        let mut modified = Vec::new();
+        for (file, shipped) in history.into_iter().zip(shipped) {
+            let Some(shipped) = shipped else {
+                continue;
+            };
+            if content_hash(&fs::read(&file.path)?) != shipped {
+                modified.push((file.path.clone(), self.violation(file)));
+            }
+        }
+        Ok(modified)
+    }
+
+    fn violation(&self, file: &MigrationFile) -> Violation {
+        let (source, accept) = match self {
+            Self::Lock { path, .. } => (
+                format!("{path}"),
+                format!(
+                    " If the edit cannot change what the migration does (e.g. a comment), \
+                     run `diesel-guard lock` to update {path}."
+                ),
+            ),
+            Self::GitRef(rev) => (format!("git ref '{rev}'"), String::new()),
+        };
+        Violation::new(
+            "Edited migration that already shipped",
+            format!(
+                "Migration {} differs from the version in {source}. Databases that already ran it \
+                 won't run it again, so they drift from databases built from the edited file. \
+                 SQLx also refuses to run migrations whose checksum changed.",
+                file.timestamp
+            ),
+            format!("Revert the edit and make the change in a new migration.{accept}"),
+        )
+        .with_check_name(MODIFIED_MIGRATION_CHECK)
+    }
+}
+
+/// SHA-256 of a migration file's exact bytes.
+pub fn content_hash(contents: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(contents))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn migration(dir: &Utf8Path, name: &str, timestamp: &str, sql: &str) -> MigrationFile {
+        let path = dir.join(name);
+        fs::write(&path, sql).unwrap();
+        MigrationFile::new(path, timestamp.to_string())
+    }
+
+    #[test]
+    fn test_unchanged_migrations_are_not_reported() {
+        let temp = tempdir().unwrap();
+        let dir = Utf8Path::from_path(temp.path()).unwrap();
+        let files = vec![migration(dir, "1_a.sql", "1", "SELECT 1;\n")];
+        let shipped = ShippedMigrations::Lock {
+            path: DEFAULT_LOCK_PATH.into(),
+            lock: MigrationLock::from_files(&files, dir).unwrap(),
+        };
+
+        assert!(shipped.modified(&files, dir).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_edited_migration_is_reported() {
+        let temp = tempdir().unwrap();
+        let dir = Utf8Path::from_path(temp.path()).unwrap();
+        let files = vec![
+            migration(dir, "1_a.sql", "1", "SELECT 1;\n"),
+            migration(dir, "2_b.sql", "2", "SELECT 2;\n"),
+        ];
+        let shipped = ShippedMigrations::Lock {
+            path: DEFAULT_LOCK_PATH.into(),
+            lock: MigrationLock::from_files(&files, dir).unwrap(),
+        };
+        fs::write(&files[1].path, "SELECT 3;\n").unwrap();
+
+        let modified = shipped.modified(&files, dir).unwrap();
+        assert_eq!(modified.len(), 1);
+        assert_eq!(modified[0].0, files[1].path);
+        assert_eq!(modified[0].1.check_name, MODIFIED_MIGRATION_CHECK);
+        assert!(modified[0].1.problem.contains("Migration 2 differs"));
+    }
+
+    #[test]
+    fn test_migrations_are_matched_by_timestamp() {
+        let temp = tempdir().unwrap();
+        let dir = Utf8Path::from_path(temp.path()).unwrap();
+        let old = vec![migration(dir, "1_a.sql", "1", "SELECT 1;\n")];
+        let shipped = ShippedMigrations::Lock {
+            path: DEFAULT_LOCK_PATH.into(),
+            lock: MigrationLock::from_files(&old, dir).unwrap(),
+        };
+
+        // Renamed, same content: fine
+        let renamed = vec![migration(dir, "1_renamed.sql", "1", "SELECT 1;\n")];
+        assert!(shipped.modified(&renamed, dir).unwrap().is_empty());
+
+        // Renamed and edited: reported
+        fs::write(&renamed[0].path, "SELECT 2;\n").unwrap();
+        assert_eq!(shipped.modified(&renamed, dir).unwrap().len(), 1);
+
+        // New migration: not in the lock, not reported
+        let new = vec![migration(dir, "2_new.sql", "2", "SELECT 2;\n")];
+        assert!(shipped.modified(&new, dir).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_repeatable_migrations_are_not_locked() {
+        let temp = tempdir().unwrap();
+        let dir = Utf8Path::from_path(temp.path()).unwrap();
+        let views = migration(
+            dir,
+            "R__views.sql",
+            "R__views",
+            "CREATE VIEW v AS SELECT 1;\n",
+        );
+        let files = vec![
+            migration(dir, "V1__a.sql", "1", "SELECT 1;\n"),
+            MigrationFile::repeatable(views.path, views.timestamp),
+        ];
+        let lock = MigrationLock::from_files(&files, dir).unwrap();
+        assert_eq!(lock.migrations.len(), 1);
+
+        // Even a lock written before repeatables were skipped ignores their edits
+        let shipped = ShippedMigrations::Lock {
+            path: DEFAULT_LOCK_PATH.into(),
+            lock: MigrationLock::new(vec![LockEntry {
+                timestamp: "R__views".to_string(),
+                file: file_key(&files[1].path, dir),
+                sha256: content_hash(b"CREATE VIEW v AS SELECT 1;\n"),
+            }]),
+        };
+        fs::write(&files[1].path, "CREATE VIEW v AS SELECT 2;\n").unwrap();
+        assert!(shipped.modified(&files, dir).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_load_rejects_unknown_version() {
+        let temp = tempdir().unwrap();
+        let path = Utf8Path::from_path(temp.path()).unwrap().join("lock.json");
+        fs::write(&path, r#"{"version": 2, "migrations": []}"#).unwrap();
+
+        let err = MigrationLock::load(&path).unwrap_err();
+        assert!(err.to_string().contains("unsupported version 2"));
+    }
+}
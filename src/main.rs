@@ -1,7 +1,15 @@
-use camino::Utf8PathBuf;
+use camino::{Utf8Path, Utf8PathBuf};
 use clap::{Parser, Subcommand};
 use diesel_guard::ast_dump;
-use diesel_guard::formatters::{Formatter, GithubFormatter, JsonFormatter, TextFormatter};
+use diesel_guard::baseline::{Baseline, DEFAULT_BASELINE_PATH};
+use diesel_guard::checks::Check;
+use diesel_guard::detect::detect_framework;
+use diesel_guard::formatters::{
+    Formatter, GithubFormatter, GitlabFormatter, JsonFormatter, JunitFormatter, SarifFormatter,
+    TextFormatter,
+};
+use diesel_guard::git;
+use diesel_guard::lock::{DEFAULT_LOCK_PATH, MigrationLock, ShippedMigrations};
 use diesel_guard::violation::Severity;
 use diesel_guard::{Config, SafetyChecker};
 use miette::{IntoDiagnostic, Result};
@@ -17,6 +25,9 @@ enum Format {
     Text,
     Json,
     Github,
+    Sarif,
+    Junit,
+    Gitlab,
 }
 
 impl std::fmt::Display for Format {
@@ -25,6 +36,9 @@ impl std::fmt::Display for Format {
             Format::Text => write!(f, "text"),
             Format::Json => write!(f, "json"),
             Format::Github => write!(f, "github"),
+            Format::Sarif => write!(f, "sarif"),
+            Format::Junit => write!(f, "junit"),
+            Format::Gitlab => write!(f, "gitlab"),
         }
     }
 }
@@ -35,6 +49,9 @@ impl Format {
             Format::Text => Box::new(TextFormatter),
             Format::Json => Box::new(JsonFormatter),
             Format::Github => Box::new(GithubFormatter),
+            Format::Sarif => Box::new(SarifFormatter::default()),
+            Format::Junit => Box::new(JunitFormatter::default()),
+            Format::Gitlab => Box::new(GitlabFormatter),
         }
     }
 }
@@ -54,12 +71,20 @@ QUICK START:
   diesel-guard check             Check all migrations in ./migrations/
   diesel-guard check up.sql      Check a single file
   diesel-guard check -           Read SQL from stdin
+  diesel-guard fix --dry-run     Preview mechanical fixes as a diff
+  diesel-guard baseline          Grandfather current violations
+  diesel-guard lock              Catch edits to migrations that already shipped
 
 Exit codes:
   0  No violations found (warnings do not affect exit code)
   1  One or more errors found (or a fatal error occurred)"
 )]
 struct Cli {
+    /// Config file to use instead of searching for one: a diesel-guard.toml, or a
+    /// Cargo.toml with a [package.metadata.diesel-guard] table
+    #[arg(long, global = true, value_name = "PATH")]
+    config: Option<Utf8PathBuf>,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -74,10 +99,14 @@ PATH can be:
   - A single .sql file
   - \"-\" to read from stdin
 
-If PATH is omitted, defaults to \"migrations/\".
+If PATH is omitted, checks every [[migrations]] entry in diesel-guard.toml,
+or \"migrations/\" when there are none.
 
-diesel-guard looks for diesel-guard.toml in the current directory. If no config
-file is found, default settings are used.
+diesel-guard looks for diesel-guard.toml, or a [package.metadata.diesel-guard]
+table in Cargo.toml, in the current directory and its parents up to the
+repository root; --config points at a file instead. If no config file is found,
+default settings are used. When the config doesn't set a framework,
+it is detected from the migrations, diesel.toml and Cargo.toml.
 
 Exit codes:
   0  No errors found (warnings do not affect exit code)
@@ -88,21 +117,134 @@ EXAMPLES:
   diesel-guard check migrations/
   diesel-guard check db/migrate/20240101_add_users/up.sql
   cat migration.sql | diesel-guard check -
-  diesel-guard check migrations/ --format json")]
+  diesel-guard check migrations/ --format json
+  diesel-guard check migrations/ --format sarif > diesel-guard.sarif
+  diesel-guard check migrations/ --format junit > diesel-guard.xml
+  diesel-guard check migrations/ --format gitlab > gl-code-quality-report.json
+  diesel-guard check --since origin/main
+  diesel-guard check --changed-only")]
     Check {
-        /// Path to migration file or directory, or "-" for stdin (default: "migrations/")
+        /// Path to migration file or directory, or "-" for stdin (default: every [[migrations]] entry, or "migrations/")
         path: Option<Utf8PathBuf>,
 
         /// Output format (default: text)
         #[arg(long, default_value_t = Format::Text)]
         format: Format,
+
+        /// Baseline of violations to ignore (default: diesel-guard-baseline.json next to the config file, if present)
+        #[arg(long)]
+        baseline: Option<Utf8PathBuf>,
+
+        /// Warn about baseline entries that no longer match a violation
+        #[arg(long)]
+        warn_stale_baseline: bool,
+
+        /// Only check migrations added, modified or renamed since this git ref
+        #[arg(long, value_name = "GIT_REF", conflicts_with = "changed_only")]
+        since: Option<String>,
+
+        /// Only check migrations with uncommitted changes (same as --since HEAD)
+        #[arg(long)]
+        changed_only: bool,
+
+        /// Lock file of shipped migrations to compare against (default: diesel-guard.lock next to the config file, if present)
+        #[arg(long, conflicts_with = "shipped_at")]
+        lock: Option<Utf8PathBuf>,
+
+        /// Report migrations whose content differs from this git ref
+        #[arg(long, value_name = "GIT_REF")]
+        shipped_at: Option<String>,
+    },
+
+    /// Record current violations so `check` only reports new ones
+    #[command(
+        long_about = "Record current violations so `check` only reports new ones.
+
+Writes every violation found in the migrations to diesel-guard-baseline.json,
+keyed by file, check name and a hash of the statement. `diesel-guard check`
+reads that file when it exists and hides the violations it lists. The hash
+ignores whitespace, comments and keyword case, so reformatting a statement
+keeps it baselined; changing what it does makes it a new violation.
+
+Running baseline again replaces the file with the current violations.
+
+EXAMPLES:
+  diesel-guard baseline
+  diesel-guard baseline migrations/ --output ci/diesel-guard-baseline.json
+  diesel-guard check --warn-stale-baseline"
+    )]
+    Baseline {
+        /// Path to migration file or directory (default: "migrations/")
+        path: Option<Utf8PathBuf>,
+
+        /// File to write (default: diesel-guard-baseline.json next to the config file)
+        #[arg(long)]
+        output: Option<Utf8PathBuf>,
+    },
+
+    /// Record a hash of every migration so `check` can catch edits to them
+    #[command(
+        long_about = "Record a hash of every migration so `check` can catch edits to them.
+
+Writes the content hash of every up migration to diesel-guard.lock, keyed by
+the migration timestamp. `diesel-guard check` reads that file when it exists
+and reports a ModifiedMigration violation for every migration whose content
+changed, whatever `start_after` is set to. Commit the file and run lock again
+after adding migrations.
+
+Alternatively, `diesel-guard check --shipped-at <git-ref>` compares the
+migrations with their content at a git ref, without a lock file.
+
+EXAMPLES:
+  diesel-guard lock
+  diesel-guard lock migrations/ --output ci/diesel-guard.lock
+  diesel-guard check --shipped-at origin/main"
+    )]
+    Lock {
+        /// Path to migrations directory (default: "migrations/")
+        path: Option<Utf8PathBuf>,
+
+        /// File to write (default: diesel-guard.lock next to the config file)
+        #[arg(long)]
+        output: Option<Utf8PathBuf>,
+    },
+
+    /// Rewrite unsafe statements that have a mechanical fix
+    #[command(long_about = "Rewrite unsafe statements that have a mechanical fix.
+
+Runs the enabled checks and rewrites the statements they flag when the safe
+version is mechanical:
+  - AddIndexCheck, DropIndexCheck, ReindexCheck, RefreshMatViewCheck: add CONCURRENTLY
+  - AddForeignKeyCheck, AddCheckConstraintCheck: add NOT VALID and a separate
+    VALIDATE CONSTRAINT
+  - Idempotency checks: add IF EXISTS / IF NOT EXISTS
+
+When CONCURRENTLY is added to a migration that runs in a transaction, the
+migration is opted out: `-- no-transaction` for SQLx, `run_in_transaction = false`
+in metadata.toml for Diesel. VALIDATE CONSTRAINT for a transactional migration
+is printed so you can add it to a later migration.
+
+Statements in safety-assured blocks and disabled checks are left alone.
+Run `diesel-guard check` afterwards for anything that needs a manual fix.
+
+EXAMPLES:
+  diesel-guard fix --dry-run
+  diesel-guard fix migrations/
+  diesel-guard fix migrations/2024_01_01_000000_add_index/up.sql")]
+    Fix {
+        /// Path to migration file or directory (default: "migrations/")
+        path: Option<Utf8PathBuf>,
+
+        /// Print a unified diff instead of writing files
+        #[arg(long)]
+        dry_run: bool,
     },
 
     /// Initialize diesel-guard configuration file
     #[command(long_about = "Initialize diesel-guard configuration file.
 
 Creates diesel-guard.toml in the current directory with all available options
-documented. Edit the file to set your migration framework (\"diesel\" or \"sqlx\")
+documented. Edit the file to set your migration framework (\"diesel\", \"sqlx\", \"refinery\", \"seaorm\", \"golang-migrate\", \"dbmate\" or \"flyway\")
 and any other options.
 
 Use --force to regenerate the config file and reset it to defaults.
@@ -154,16 +296,176 @@ EXAMPLES:
     },
 }
 
-fn run_check(path: &camino::Utf8Path, format: Format) -> Result<()> {
-    let config = Config::load().map_err(|e| miette::miette!(e))?;
-    let checker = SafetyChecker::with_config(config).map_err(|e| miette::miette!(e))?;
-    let results = checker.check_path(path)?;
+/// `check` flags beyond the path and output format.
+struct CheckOptions<'a> {
+    baseline: Option<&'a Utf8Path>,
+    warn_stale_baseline: bool,
+    since: Option<&'a str>,
+    lock: Option<&'a Utf8Path>,
+    shipped_at: Option<&'a str>,
+}
+
+/// Load the config given with `--config`, or the one found from the current directory.
+fn load_config(config_path: Option<&Utf8Path>) -> Result<Config> {
+    match config_path {
+        Some(path) => Config::load_from_path(path),
+        None => Config::load(),
+    }
+    .map_err(|e| miette::miette!(e))
+}
+
+/// The migration paths a command works on, each with its settings.
+///
+/// An explicit path uses the settings of the `[[migrations]]` entry it falls
+/// under. Without a path, every `[[migrations]]` entry is used, or `migrations/`.
+fn resolve_targets(
+    config_path: Option<&Utf8Path>,
+    path: Option<Utf8PathBuf>,
+) -> Result<Vec<(Utf8PathBuf, Config)>> {
+    let config = load_config(config_path)?;
+    let targets = match path {
+        Some(path) => {
+            let config = config.for_path(&path);
+            vec![(path, config)]
+        }
+        None if config.migrations.is_empty() => {
+            vec![(config.project_dir().join("migrations"), config)]
+        }
+        None => config
+            .migrations
+            .iter()
+            .map(|root| (Utf8PathBuf::from(&root.path), config.for_root(root)))
+            .collect(),
+    };
+
+    targets
+        .into_iter()
+        .map(|(path, config)| {
+            let config = with_framework(config, &path)?;
+            Ok((path, config))
+        })
+        .collect()
+}
+
+/// Detect the framework for `path` when the config doesn't set one.
+fn with_framework(mut config: Config, path: &Utf8Path) -> Result<Config> {
+    // SQL from stdin is checked without an adapter
+    if !config.framework.is_empty() || path.as_str() == "-" {
+        return Ok(config);
+    }
+
+    let project_dir = match config.project_dir() {
+        dir if dir.as_str().is_empty() => Utf8Path::new("."),
+        dir => dir,
+    };
+    let detection = detect_framework(project_dir, path).map_err(miette::Report::new)?;
+    if let Some(detection) = detection {
+        eprintln!(
+            "Detected framework \"{}\": {}",
+            detection.framework,
+            detection.reasons.join(", ")
+        );
+        config.framework = detection.framework.to_string();
+    } else {
+        eprintln!(
+            "Warning: Could not detect the migration framework, assuming \"diesel\". \
+             Set the 'framework' field in diesel-guard.toml."
+        );
+        config.framework = "diesel".to_string();
+    }
+    Ok(config)
+}
+
+fn build_checker(config: Config, options: &CheckOptions) -> Result<SafetyChecker> {
+    // Default files live next to the config file, wherever the command runs from
+    let default_baseline = config.project_dir().join(DEFAULT_BASELINE_PATH);
+    let default_lock = config.project_dir().join(DEFAULT_LOCK_PATH);
+    let mut checker = SafetyChecker::with_config(config).map_err(|e| miette::miette!(e))?;
+    if let Some(since) = options.since {
+        checker = checker.with_changed_files(git::changed_files(since)?);
+    }
+    let baseline = options.baseline.or_else(|| {
+        default_baseline
+            .exists()
+            .then_some(default_baseline.as_path())
+    });
+    if let Some(baseline) = baseline {
+        checker = checker.with_baseline(Baseline::load(baseline)?);
+    }
+    if let Some(rev) = options.shipped_at {
+        checker = checker.with_shipped_migrations(ShippedMigrations::GitRef(rev.to_string()));
+    } else if let Some(lock) = options
+        .lock
+        .or_else(|| default_lock.exists().then_some(default_lock.as_path()))
+    {
+        checker = checker.with_shipped_migrations(ShippedMigrations::Lock {
+            path: lock.to_owned(),
+            lock: MigrationLock::load(lock)?,
+        });
+    }
+    Ok(checker)
+}
+
+fn run_check(
+    config_path: Option<&Utf8Path>,
+    path: Option<Utf8PathBuf>,
+    format: Format,
+    options: &CheckOptions,
+) -> Result<()> {
+    // One report for every migration root
+    let mut results = Vec::new();
+    let mut checkers = Vec::new();
+    for (path, config) in resolve_targets(config_path, path)? {
+        let checker = build_checker(config, options)?;
+        results.extend(checker.check_path(&path)?);
+        checkers.push(checker);
+    }
+
+    if options.warn_stale_baseline {
+        let stale: Vec<_> = checkers
+            .iter()
+            .flat_map(SafetyChecker::stale_baseline_entries)
+            .collect();
+        for entry in &stale {
+            eprintln!(
+                "Warning: stale baseline entry {} ({}) matches no violation",
+                entry.file, entry.check_name
+            );
+        }
+        if !stale.is_empty() {
+            eprintln!("Run `diesel-guard baseline` to refresh the baseline.");
+        }
+    }
+
     let total_errors: usize = results
         .iter()
         .flat_map(|(_, v)| v)
         .filter(|(_, v)| v.severity == Severity::Error)
         .count();
-    print!("{}", format.formatter().format_results(&results));
+    let formatter: Box<dyn Formatter> = match format {
+        // SARIF rules list every check, not just the enabled ones
+        Format::Sarif => {
+            let (config, all_checks) = load_all_checks(config_path)?;
+            let checks: Vec<_> = all_checks.registry().iter_checks().collect();
+            Box::new(SarifFormatter::new(&checks, &config))
+        }
+        // One testsuite per checked file and one testcase per check that ran in any root
+        Format::Junit => {
+            let mut checks: Vec<&dyn Check> = Vec::new();
+            for check in checkers.iter().flat_map(|c| c.registry().iter_checks()) {
+                if !checks.iter().any(|c| c.name() == check.name()) {
+                    checks.push(check);
+                }
+            }
+            let files = checkers
+                .iter()
+                .flat_map(SafetyChecker::checked_files)
+                .collect();
+            Box::new(JunitFormatter::new(&checks).with_files(files))
+        }
+        _ => format.formatter(),
+    };
+    print!("{}", formatter.format_results(&results));
     if total_errors > 0 {
         let _ = std::io::stdout().flush();
         exit(1);
@@ -171,8 +473,115 @@ fn run_check(path: &camino::Utf8Path, format: Format) -> Result<()> {
     Ok(())
 }
 
-fn load_all_checks() -> Result<(Config, SafetyChecker)> {
-    let config = Config::load().map_err(|e| miette::miette!(e))?;
+fn run_baseline(
+    config_path: Option<&Utf8Path>,
+    path: Option<Utf8PathBuf>,
+    output: Option<Utf8PathBuf>,
+) -> Result<()> {
+    if path.as_ref().is_some_and(|p| p.as_str() == "-") {
+        return Err(miette::miette!(
+            "baseline needs a migration file or directory, not stdin"
+        ));
+    }
+
+    let mut entries = Vec::new();
+    for (path, config) in resolve_targets(config_path, path)? {
+        let checker = SafetyChecker::with_config(config).map_err(|e| miette::miette!(e))?;
+        entries.extend(checker.baseline_path(&path)?.entries);
+    }
+    let output = match output {
+        Some(output) => output,
+        None => load_config(config_path)?
+            .project_dir()
+            .join(DEFAULT_BASELINE_PATH),
+    };
+    let baseline = Baseline::new(entries);
+    baseline.write(&output)?;
+    println!(
+        "✓ Wrote {} baseline entries to {output}",
+        baseline.entries.len()
+    );
+    Ok(())
+}
+
+fn run_lock(
+    config_path: Option<&Utf8Path>,
+    path: Option<Utf8PathBuf>,
+    output: Option<Utf8PathBuf>,
+) -> Result<()> {
+    let mut migrations = Vec::new();
+    for (path, config) in resolve_targets(config_path, path)? {
+        if !path.is_dir() {
+            return Err(miette::miette!(
+                "lock needs a migrations directory, got '{path}'"
+            ));
+        }
+        let checker = SafetyChecker::with_config(config).map_err(|e| miette::miette!(e))?;
+        migrations.extend(checker.lock_directory(&path)?.migrations);
+    }
+    let output = match output {
+        Some(output) => output,
+        None => load_config(config_path)?
+            .project_dir()
+            .join(DEFAULT_LOCK_PATH),
+    };
+    let lock = MigrationLock::new(migrations);
+    lock.write(&output)?;
+    println!("✓ Locked {} migrations in {output}", lock.migrations.len());
+    Ok(())
+}
+
+fn run_fix(config_path: Option<&Utf8Path>, path: Option<Utf8PathBuf>, dry_run: bool) -> Result<()> {
+    if path.as_ref().is_some_and(|p| p.as_str() == "-") {
+        return Err(miette::miette!(
+            "fix needs a migration file or directory, not stdin"
+        ));
+    }
+
+    let mut fixes = Vec::new();
+    for (path, config) in resolve_targets(config_path, path)? {
+        let checker = SafetyChecker::with_config(config).map_err(|e| miette::miette!(e))?;
+        fixes.extend(checker.fix_path(&path)?);
+    }
+    if fixes.is_empty() {
+        println!("No fixable violations found.");
+        return Ok(());
+    }
+
+    for fix in &fixes {
+        if dry_run {
+            print!("{}", fix.diff());
+        } else {
+            fix.write()
+                .into_diagnostic()
+                .map_err(|e| miette::miette!("Failed to write fix for '{}': {}", fix.path, e))?;
+            println!(
+                "✓ Fixed {} statement(s) in {}",
+                fix.fixed_statements, fix.path
+            );
+            if let Some(metadata) = &fix.metadata {
+                println!("✓ Set run_in_transaction = false in {}", metadata.path);
+            }
+        }
+
+        if !fix.follow_ups.is_empty() {
+            println!();
+            println!(
+                "{}: run these in a later migration, outside this migration's transaction:",
+                fix.path
+            );
+            for stmt in &fix.follow_ups {
+                println!("  {stmt}");
+            }
+            println!();
+        }
+    }
+
+    Ok(())
+}
+
+fn load_all_checks(config_path: Option<&Utf8Path>) -> Result<(Config, SafetyChecker)> {
+    let config = load_config(config_path)?;
     let checker = SafetyChecker::with_config(Config {
         disable_checks: vec![],
         enable_checks: vec![],
@@ -182,15 +591,19 @@ fn load_all_checks() -> Result<(Config, SafetyChecker)> {
     Ok((config, checker))
 }
 
-fn run_list_checks(format: Format) -> Result<()> {
-    let (config, checker) = load_all_checks()?;
+fn run_list_checks(config_path: Option<&Utf8Path>, format: Format) -> Result<()> {
+    let (config, checker) = load_all_checks(config_path)?;
+    match &config.source {
+        Some(path) => eprintln!("Config: {path}"),
+        None => eprintln!("Config: none found, using defaults"),
+    }
     let checks: Vec<_> = checker.registry().iter_checks().collect();
     print!("{}", format.formatter().format_checks(&checks, &config));
     Ok(())
 }
 
-fn run_explain(check_name: &str, format: Format) -> Result<()> {
-    let (config, checker) = load_all_checks()?;
+fn run_explain(config_path: Option<&Utf8Path>, check_name: &str, format: Format) -> Result<()> {
+    let (config, checker) = load_all_checks(config_path)?;
     let Some(check) = checker
         .registry()
         .iter_checks()
@@ -218,11 +631,44 @@ fn main() -> Result<()> {
     }))?;
 
     let cli = Cli::parse();
+    let config_path = cli.config.as_deref();
 
     match cli.command {
-        Commands::Check { path, format } => {
-            let path = path.unwrap_or_else(|| Utf8PathBuf::from("migrations"));
-            run_check(&path, format)?;
+        Commands::Check {
+            path,
+            format,
+            baseline,
+            warn_stale_baseline,
+            since,
+            changed_only,
+            lock,
+            shipped_at,
+        } => {
+            let since = since.or_else(|| changed_only.then(|| "HEAD".to_string()));
+            run_check(
+                config_path,
+                path,
+                format,
+                &CheckOptions {
+                    baseline: baseline.as_deref(),
+                    warn_stale_baseline,
+                    since: since.as_deref(),
+                    lock: lock.as_deref(),
+                    shipped_at: shipped_at.as_deref(),
+                },
+            )?;
+        }
+
+        Commands::Lock { path, output } => {
+            run_lock(config_path, path, output)?;
+        }
+
+        Commands::Baseline { path, output } => {
+            run_baseline(config_path, path, output)?;
+        }
+
+        Commands::Fix { path, dry_run } => {
+            run_fix(config_path, path, dry_run)?;
         }
 
         Commands::DumpAst { sql, file } => {
@@ -238,9 +684,9 @@ fn main() -> Result<()> {
             println!("{json}");
         }
 
-        Commands::ListChecks { format } => run_list_checks(format)?,
+        Commands::ListChecks { format } => run_list_checks(config_path, format)?,
 
-        Commands::Explain { check_name, format } => run_explain(&check_name, format)?,
+        Commands::Explain { check_name, format } => run_explain(config_path, &check_name, format)?,
 
         Commands::Init { force } => {
             let config_path = Utf8PathBuf::from("diesel-guard.toml");
@@ -264,7 +710,7 @@ fn main() -> Result<()> {
             println!();
             println!("Next steps:");
             println!(
-                "1. Edit diesel-guard.toml and set the 'framework' field to \"diesel\" or \"sqlx\""
+                "1. Edit diesel-guard.toml and set the 'framework' field to \"diesel\", \"sqlx\", \"refinery\", \"seaorm\", \"golang-migrate\", \"dbmate\" or \"flyway\""
             );
             println!("2. Customize other configuration options as needed");
             println!("3. Run 'diesel-guard check' to check your migrations");
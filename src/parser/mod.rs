@@ -26,16 +26,18 @@ pub fn parse(sql: &str) -> Result<Vec<RawStmt>> {
 
     let mut all_stmts = Vec::new();
     for stmt in stmts {
+        let start = stmt.as_ptr() as usize - sql.as_ptr() as usize;
         let leading = stmt.len() - stmt.trim_start().len();
-        let offset = stmt.as_ptr() as usize - sql.as_ptr() as usize + leading;
         let parsed = pg_query::parse(stmt).map_err(|e| DieselGuardError::ParseError {
             msg: e.to_string(),
             src: None,
-            span: Some(SourceSpan::new(SourceOffset::from(offset), 0)),
+            span: Some(SourceSpan::new(SourceOffset::from(start + leading), 0)),
         })?;
         // Adjust stmt_location to be relative to the full SQL, not the individual statement.
+        // Node `location` fields stay relative to the statement's slice, so
+        // `stmt_location + location` is a byte offset into the full SQL.
         let adjusted = parsed.protobuf.stmts.into_iter().map(|mut s| {
-            s.stmt_location += i32::try_from(offset).unwrap_or(0);
+            s.stmt_location += i32::try_from(start).unwrap_or(0);
             s
         });
         all_stmts.extend(adjusted);
@@ -1,18 +1,48 @@
 use crate::ViolationList;
-use crate::adapters::{DieselAdapter, MigrationAdapter, SqlxAdapter};
-use crate::checks::{MigrationContext, Registry};
+use crate::adapters::{
+    DbmateAdapter, DieselAdapter, FlywayAdapter, GolangMigrateAdapter, MigrationAdapter,
+    MigrationFile, RefineryAdapter, SeaOrmAdapter, SqlxAdapter,
+};
+use crate::baseline::{self, Baseline, BaselineEntry};
+use crate::checks::{CheckedStmt, MigrationContext, Registry};
 use crate::config::Config;
 use crate::error::Result;
-use crate::parser;
+use crate::fix::{self, FileFix};
+use crate::git;
+use crate::lock::{MigrationLock, ShippedMigrations};
+use crate::parser::{self, ParsedSql};
+use crate::schema::SchemaCatalog;
 use crate::scripting;
-use camino::Utf8Path;
+use camino::{Utf8Path, Utf8PathBuf};
+use std::collections::HashSet;
 use std::fs;
 use std::io::{self, BufRead, BufReader};
+use std::sync::{Arc, Mutex};
+
+/// A parsed migration file and the context it is checked with.
+struct LoadedMigration {
+    path: Utf8PathBuf,
+    parsed: ParsedSql,
+    ctx: MigrationContext,
+    /// SQL extracted from another language (see [`MigrationAdapter::embeds_sql`]).
+    embedded: bool,
+    /// Rollback section of a file that holds both directions.
+    down_section: bool,
+}
 
 pub struct SafetyChecker {
     registry: Registry,
     config: Config,
     known_check_names: Vec<String>,
+    baseline: Option<Baseline>,
+    /// Baseline entries for checked files that matched no violation.
+    stale_baseline: Mutex<Vec<BaselineEntry>>,
+    /// Files checked so far, with or without violations, in the order they were checked.
+    checked_files: Mutex<Vec<String>>,
+    /// When set, only these files are checked (canonical paths).
+    changed_files: Option<HashSet<Utf8PathBuf>>,
+    /// Shipped migration content that directory checks compare against.
+    shipped: Option<ShippedMigrations>,
 }
 
 impl SafetyChecker {
@@ -21,8 +51,8 @@ impl SafetyChecker {
     /// # Errors
     ///
     /// Returns [`crate::config::ConfigError::InvalidCheckName`] if any name in `enable_checks`,
-    /// `disable_checks`, or `warn_checks` is not a known check name (built-in or
-    /// custom script stem from `custom_checks_dir`).
+    /// `disable_checks`, `warn_checks` or `opt_in_checks` is not a known check name
+    /// (built-in or custom script stem from `custom_checks_dir`).
     pub fn with_config(config: Config) -> std::result::Result<Self, crate::config::ConfigError> {
         let mut registry = Registry::with_config(&config);
 
@@ -85,14 +115,93 @@ impl SafetyChecker {
         validate_names(&config.disable_checks)?;
         validate_names(&config.enable_checks)?;
         validate_names(&config.warn_checks)?;
+        validate_names(&config.opt_in_checks)?;
+        validate_names(&config.checks.keys().cloned().collect::<Vec<_>>())?;
+        for entry in &config.overrides {
+            for names in [
+                &entry.enable_checks,
+                &entry.disable_checks,
+                &entry.warn_checks,
+            ]
+            .into_iter()
+            .flatten()
+            {
+                validate_names(names)?;
+            }
+        }
 
         Ok(Self {
             registry,
             config,
             known_check_names,
+            baseline: None,
+            stale_baseline: Mutex::default(),
+            checked_files: Mutex::default(),
+            changed_files: None,
+            shipped: None,
         })
     }
 
+    /// Hide violations recorded in `baseline` from file and directory checks.
+    #[must_use]
+    pub fn with_baseline(mut self, baseline: Baseline) -> Self {
+        self.baseline = Some(baseline);
+        self
+    }
+
+    /// Only check migrations in `files`, e.g. those changed since a git ref.
+    ///
+    /// Migrations outside the list are still replayed to build the schema,
+    /// they just aren't reported on.
+    #[must_use]
+    pub fn with_changed_files(mut self, files: impl IntoIterator<Item = Utf8PathBuf>) -> Self {
+        self.changed_files = Some(files.into_iter().map(|f| git::canonical(&f)).collect());
+        self
+    }
+
+    /// Report up migrations that differ from their shipped version when
+    /// checking a directory.
+    #[must_use]
+    pub fn with_shipped_migrations(mut self, shipped: ShippedMigrations) -> Self {
+        self.shipped = Some(shipped);
+        self
+    }
+
+    fn is_changed(&self, path: &Utf8Path) -> bool {
+        self.changed_files
+            .as_ref()
+            .is_none_or(|files| files.contains(&git::canonical(path)))
+    }
+
+    /// Baseline entries that matched nothing in the files checked so far.
+    ///
+    /// Only files that were actually checked count, so checking a single
+    /// migration does not report the rest of the baseline as stale.
+    pub fn stale_baseline_entries(&self) -> Vec<BaselineEntry> {
+        self.stale_baseline
+            .lock()
+            .map(|stale| stale.clone())
+            .unwrap_or_default()
+    }
+
+    /// Every file checked so far, including those without violations.
+    ///
+    /// Stdin is listed as `-`. Reports that list passing files (JUnit) use this.
+    pub fn checked_files(&self) -> Vec<String> {
+        self.checked_files
+            .lock()
+            .map(|files| files.clone())
+            .unwrap_or_default()
+    }
+
+    fn record_checked(&self, path: &str) {
+        if let Ok(mut files) = self.checked_files.lock()
+            && !files.iter().any(|f| f == path)
+        {
+            files.push(path.to_string());
+        }
+    }
+
     /// Expose the registry for introspection (e.g. list-checks, explain).
     pub fn registry(&self) -> &Registry {
         &self.registry
@@ -103,6 +212,12 @@ impl SafetyChecker {
         match self.config.framework.as_str() {
             "diesel" => Ok(Box::new(DieselAdapter)),
             "sqlx" => Ok(Box::new(SqlxAdapter)),
+            "refinery" => Ok(Box::new(RefineryAdapter)),
+            "seaorm" => Ok(Box::new(SeaOrmAdapter)),
+            "golang-migrate" => Ok(Box::new(GolangMigrateAdapter)),
+            "dbmate" => Ok(Box::new(DbmateAdapter)),
+            "flyway" => Ok(Box::new(FlywayAdapter)),
+            "" => Err(crate::config::ConfigError::MissingFramework.into()),
             _ => Err(crate::config::ConfigError::InvalidFramework {
                 framework: self.config.framework.clone(),
             }
@@ -151,32 +266,251 @@ impl SafetyChecker {
 
     /// Check a single migration file
     pub fn check_file(&self, path: &Utf8Path) -> Result<ViolationList> {
-        let sql = fs::read_to_string(path)?;
+        let adapter = self.adapter()?;
+        let file = MigrationFile::new(path.to_owned(), String::new());
+        let migration = self.load_migration(adapter.as_ref(), &file, Arc::default())?;
+        Ok(self.check_migration(&migration))
+    }
+
+    /// Check all migration files in a directory
+    pub fn check_directory(&self, dir: &Utf8Path) -> Result<Vec<(String, ViolationList)>> {
+        let mut results = Vec::new();
+        let mut checked = Vec::new();
+        let history = self.visit_directory(dir, |i, migration| {
+            let violations = self.check_migration(&migration);
+            checked.push((i, migration.path.to_string()));
+            if !violations.is_empty() {
+                results.push((i, (migration.path.to_string(), violations)));
+            }
+        })?;
+
+        checked.sort_by_key(|(i, _)| *i);
+        for (_, path) in &checked {
+            self.record_checked(path);
+        }
+        results.sort_by_key(|(i, _)| *i);
+        let mut results: Vec<_> = results.into_iter().map(|(_, result)| result).collect();
+
+        // Edited shipped migrations are listed first, whether or not `start_after`
+        // or `--since` selected them for the other checks.
+        if let Some(shipped) = &self.shipped {
+            let mut edited = Vec::new();
+            for (path, violation) in shipped.modified(&history, self.config.project_dir())? {
+                let path = path.to_string();
+                if let Some((_, violations)) = results.iter_mut().find(|(p, _)| *p == path) {
+                    violations.insert(0, (1, violation));
+                } else {
+                    edited.push((path, vec![(1, violation)]));
+                }
+            }
+            results.splice(0..0, edited);
+        }
+
+        Ok(results)
+    }
+
+    /// Compute fixes for a migration file or directory without writing anything.
+    ///
+    /// Only files that need changes are returned.
+    pub fn fix_path(&self, path: &Utf8Path) -> Result<Vec<FileFix>> {
+        let adapter = self.adapter()?;
+        let mut fixes = Vec::new();
+
+        if path.is_dir() {
+            self.visit_directory(path, |i, migration| {
+                // The up section's fix rewrites the whole file
+                if !migration.down_section {
+                    fixes.push((i, self.fix_migration(adapter.as_ref(), &migration)));
+                }
+            })?;
+            fixes.sort_by_key(|(i, _)| *i);
+        } else {
+            let file = MigrationFile::new(path.to_owned(), String::new());
+            let migration = self.load_migration(adapter.as_ref(), &file, Arc::default())?;
+            fixes.push((0, self.fix_migration(adapter.as_ref(), &migration)));
+        }
+
+        Ok(fixes
+            .into_iter()
+            .map(|(_, fix)| fix)
+            .filter(|fix| !fix.is_empty())
+            .collect())
+    }
+
+    /// Record the current violations in a migration file or directory as a baseline.
+    pub fn baseline_path(&self, path: &Utf8Path) -> Result<Baseline> {
+        let adapter = self.adapter()?;
+        let mut entries = Vec::new();
+
+        if path.is_dir() {
+            self.visit_directory(path, |_, migration| {
+                entries.extend(self.baseline_entries(&migration));
+            })?;
+        } else {
+            let file = MigrationFile::new(path.to_owned(), String::new());
+            let migration = self.load_migration(adapter.as_ref(), &file, Arc::default())?;
+            entries.extend(self.baseline_entries(&migration));
+        }
+
+        Ok(Baseline::new(entries))
+    }
 
+    /// Hash every up migration in `dir` for `diesel-guard lock`.
+    ///
+    /// `start_after` is ignored: the lock covers every migration that has shipped.
+    pub fn lock_directory(&self, dir: &Utf8Path) -> Result<MigrationLock> {
         let adapter = self.adapter()?;
-        let ctx = adapter.extract_migration_metadata(path);
+        let history = adapter
+            .collect_migration_files(dir, None, false)
+            .map_err(|e| crate::error::DieselGuardError::parse_error(e.to_string()))?;
+        MigrationLock::from_files(&history, self.config.project_dir())
+    }
+
+    fn baseline_entries(&self, migration: &LoadedMigration) -> Vec<BaselineEntry> {
+        let file = baseline::file_key(&migration.path, self.config.project_dir());
+        let mut entries = Vec::new();
+        for stmt in self.check_each_stmt(migration) {
+            let hash = baseline::statement_hash(&migration.parsed.sql[stmt.span.clone()]);
+            entries.extend(stmt.violations.into_iter().map(|v| BaselineEntry {
+                file: file.clone(),
+                check_name: v.check_name,
+                statement_hash: hash.clone(),
+            }));
+        }
+        entries
+    }
+
+    fn check_each_stmt<'a>(&self, migration: &'a LoadedMigration) -> Vec<CheckedStmt<'a>> {
+        let mut checked = self.registry.check_each_stmt(
+            &migration.parsed.stmts,
+            &migration.parsed.sql,
+            &migration.parsed.ignore_ranges,
+            &self.config.for_file(&migration.path),
+            &migration.ctx,
+        );
+        // Fix offsets point into the extracted SQL, not the migration file
+        if migration.embedded {
+            for stmt in &mut checked {
+                for violation in &mut stmt.violations {
+                    violation.fix = None;
+                }
+            }
+        }
+        checked
+    }
+
+    fn check_migration(&self, migration: &LoadedMigration) -> ViolationList {
+        let checked = self.check_each_stmt(migration);
+        let Some(baseline) = &self.baseline else {
+            return checked
+                .into_iter()
+                .flat_map(|stmt| {
+                    let line = stmt.line;
+                    stmt.violations.into_iter().map(move |v| (line, v))
+                })
+                .collect();
+        };
+
+        // Each entry hides one violation, so a statement repeated in a file
+        // needs as many entries as it has violations
+        let mut remaining = baseline.entries_for(&baseline::file_key(
+            &migration.path,
+            self.config.project_dir(),
+        ));
+        let mut violations = Vec::new();
+        for stmt in checked {
+            let hash = baseline::statement_hash(&migration.parsed.sql[stmt.span.clone()]);
+            for v in stmt.violations {
+                let baselined = remaining
+                    .iter()
+                    .position(|e| e.check_name == v.check_name && e.statement_hash == hash);
+                match baselined {
+                    Some(pos) => {
+                        remaining.swap_remove(pos);
+                    }
+                    None => violations.push((stmt.line, v)),
+                }
+            }
+        }
+
+        if let Ok(mut stale) = self.stale_baseline.lock() {
+            stale.extend(remaining.into_iter().cloned());
+        }
+        violations
+    }
+
+    fn fix_migration(
+        &self,
+        adapter: &dyn MigrationAdapter,
+        migration: &LoadedMigration,
+    ) -> FileFix {
+        let parsed = &migration.parsed;
+        let checked = if migration.embedded {
+            Vec::new()
+        } else {
+            self.check_each_stmt(migration)
+        };
+        // Sections of a shared file are blanked in place, so edits apply to the file as-is
+        let source = fs::read_to_string(&migration.path).unwrap_or_else(|_| parsed.sql.clone());
+        fix::fix_file(
+            &migration.path,
+            &source,
+            &parsed.sql,
+            &checked,
+            &migration.ctx,
+            adapter,
+        )
+    }
+
+    /// Read and parse a migration file, and build its context.
+    fn load_migration(
+        &self,
+        adapter: &dyn MigrationAdapter,
+        file: &MigrationFile,
+        schema: Arc<SchemaCatalog>,
+    ) -> Result<LoadedMigration> {
+        let path = &file.path;
+        let sql = adapter.read_sql(file)?;
+        let ctx = adapter.migration_context(file).with_schema(schema);
 
         match parser::parse_with_metadata(&sql) {
             Ok(parsed) => {
                 let ctx = ctx.with_disabled_checks(&parsed.disabled_checks);
+                let embedded = adapter.embeds_sql(path);
+                if embedded && parsed.stmts.is_empty() {
+                    eprintln!(
+                        "Warning: No raw SQL found in {path}; statements built at runtime \
+                         (e.g. with a query builder) are not checked."
+                    );
+                }
                 self.warn_unknown_migration_disabled_checks(
                     &ctx.disabled_checks,
                     &format!("{path} migration-scoped disable_checks"),
                 );
-                Ok(self.registry.check_stmts_with_context(
-                    &parsed.stmts,
-                    &parsed.sql,
-                    &parsed.ignore_ranges,
-                    &self.config,
-                    &ctx,
-                ))
+                Ok(LoadedMigration {
+                    path: path.to_owned(),
+                    parsed,
+                    ctx,
+                    embedded,
+                    down_section: file.down_section,
+                })
             }
             Err(e) => Err(e.with_file_context(path.as_str(), sql)),
         }
     }
 
-    /// Check all migration files in a directory
-    pub fn check_directory(&self, dir: &Utf8Path) -> Result<Vec<(String, ViolationList)>> {
+    /// Load every migration selected by the config in `dir` and pass it to `visit`
+    /// together with its index in adapter order.
+    ///
+    /// Migrations are visited in replay order, so each one sees the schema
+    /// built by the migrations that run before it.
+    ///
+    /// Returns every up migration in `dir`, in the order they run.
+    fn visit_directory(
+        &self,
+        dir: &Utf8Path,
+        mut visit: impl FnMut(usize, LoadedMigration),
+    ) -> Result<Vec<MigrationFile>> {
         let adapter = self.adapter()?;
 
         if let Some(start_after) = self.config.start_after.as_deref() {
@@ -185,46 +519,77 @@ impl SafetyChecker {
                 .map_err(|e| crate::config::ConfigError::InvalidTimestampFormat(e.to_string()))?;
         }
 
-        let migration_files = adapter
+        let mut migration_files = adapter
             .collect_migration_files(
                 dir,
                 self.config.start_after.as_deref(),
                 self.config.check_down,
             )
             .map_err(|e| crate::error::DieselGuardError::parse_error(e.to_string()))?;
+        migration_files.retain(|file| self.is_changed(&file.path));
 
-        let mut results = Vec::new();
+        // Every up migration, regardless of `start_after`, in the order they run.
+        // Replaying them builds the schema each checked migration starts from.
+        let history = adapter
+            .collect_migration_files(dir, None, false)
+            .map_err(|e| crate::error::DieselGuardError::parse_error(e.to_string()))?;
 
-        for mig_file in migration_files {
-            let sql = fs::read_to_string(&mig_file.path)?;
+        // Down migrations run after their up migration, so they see its schema.
+        let mut order: Vec<(usize, usize)> = migration_files
+            .iter()
+            .enumerate()
+            .map(|(i, file)| (Self::replay_position(&history, file), i))
+            .collect();
+        order.sort_unstable();
 
-            let ctx = adapter.extract_migration_metadata(&mig_file.path);
+        let mut schema = Arc::new(SchemaCatalog::default());
+        let mut replayed = 0;
 
-            match parser::parse_with_metadata(&sql) {
-                Ok(parsed) => {
-                    let ctx = ctx.with_disabled_checks(&parsed.disabled_checks);
-                    self.warn_unknown_migration_disabled_checks(
-                        &ctx.disabled_checks,
-                        &format!("{} migration-scoped disable_checks", mig_file.path),
-                    );
-                    let violations = self.registry.check_stmts_with_context(
-                        &parsed.stmts,
-                        &parsed.sql,
-                        &parsed.ignore_ranges,
-                        &self.config,
-                        &ctx,
-                    );
-                    if !violations.is_empty() {
-                        results.push((mig_file.path.to_string(), violations));
-                    }
-                }
-                Err(e) => {
-                    return Err(e.with_file_context(mig_file.path.as_str(), sql));
-                }
+        for (position, i) in order {
+            for past in &history[replayed..position] {
+                Self::replay(adapter.as_ref(), &mut schema, past);
             }
+            replayed = replayed.max(position);
+
+            let migration =
+                self.load_migration(adapter.as_ref(), &migration_files[i], Arc::clone(&schema))?;
+            visit(i, migration);
         }
 
-        Ok(results)
+        Ok(history)
+    }
+
+    /// Number of history entries that run before `file`.
+    ///
+    /// Up migrations see everything before them; other files (down migrations)
+    /// see the schema left by the up migration with the same timestamp.
+    fn replay_position(history: &[MigrationFile], file: &MigrationFile) -> usize {
+        if let Some(pos) = history
+            .iter()
+            .position(|h| h.path == file.path && h.down_section == file.down_section)
+        {
+            return pos;
+        }
+        history
+            .iter()
+            .rposition(|h| h.timestamp == file.timestamp)
+            .map_or(0, |pos| pos + 1)
+    }
+
+    /// Apply a past migration to the schema catalog.
+    ///
+    /// Unreadable or unparsable files are skipped: they are reported when checked directly.
+    fn replay(
+        adapter: &dyn MigrationAdapter,
+        schema: &mut Arc<SchemaCatalog>,
+        file: &MigrationFile,
+    ) {
+        let Ok(sql) = adapter.read_sql(file) else {
+            return;
+        };
+        if let Ok(stmts) = parser::parse(&sql) {
+            Arc::make_mut(schema).apply_stmts(&stmts);
+        }
     }
 
     // check a migration string from a buffer
@@ -239,6 +604,7 @@ impl SafetyChecker {
         // "-" means we're using stdin as an input.
         if path.as_str() == "-" {
             let violations = self.check_buffer(&mut BufReader::new(io::stdin().lock()))?;
+            self.record_checked(path.as_str());
             if violations.is_empty() {
                 Ok(vec![])
             } else {
@@ -246,8 +612,11 @@ impl SafetyChecker {
             }
         } else if path.is_dir() {
             self.check_directory(path)
+        } else if !self.is_changed(path) {
+            Ok(vec![])
         } else {
             let violations = self.check_file(path)?;
+            self.record_checked(path.as_str());
             if violations.is_empty() {
                 Ok(vec![])
             } else {
@@ -378,7 +747,7 @@ mod tests {
         let result = checker.check_directory(camino::Utf8Path::new("."));
         assert_eq!(
             result.unwrap_err().to_string(),
-            "Invalid framework \"unknown\". Expected \"diesel\" or \"sqlx\"."
+            "Invalid framework \"unknown\". Expected \"diesel\", \"sqlx\", \"refinery\", \"seaorm\", \"golang-migrate\", \"dbmate\" or \"flyway\"."
         );
     }
 
@@ -402,14 +771,14 @@ mod tests {
         let err = checker.check_file(path).unwrap_err();
         assert_eq!(
             err.to_string(),
-            "Invalid framework \"unknown\". Expected \"diesel\" or \"sqlx\"."
+            "Invalid framework \"unknown\". Expected \"diesel\", \"sqlx\", \"refinery\", \"seaorm\", \"golang-migrate\", \"dbmate\" or \"flyway\"."
         );
 
         // check_path on a file routes through check_file, so it must error too.
         let err = checker.check_path(path).unwrap_err();
         assert_eq!(
             err.to_string(),
-            "Invalid framework \"unknown\". Expected \"diesel\" or \"sqlx\"."
+            "Invalid framework \"unknown\". Expected \"diesel\", \"sqlx\", \"refinery\", \"seaorm\", \"golang-migrate\", \"dbmate\" or \"flyway\"."
         );
     }
 
@@ -684,6 +1053,218 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_checked_files_include_files_without_violations() {
+        use std::fs;
+
+        let temp_dir = tempdir().expect("Failed to create temp dir");
+        fs::write(
+            temp_dir.path().join("20240102000000_add_idx.up.sql"),
+            "CREATE INDEX idx_users_email ON users(email);",
+        )
+        .unwrap();
+        fs::write(
+            temp_dir.path().join("20240101000000_create_users.up.sql"),
+            "CREATE TABLE users (id BIGINT PRIMARY KEY, email TEXT);",
+        )
+        .unwrap();
+
+        let checker = SafetyChecker::with_config(Config {
+            framework: "sqlx".to_string(),
+            enable_checks: vec!["AddIndexCheck".to_string()],
+            ..Default::default()
+        })
+        .unwrap();
+        let dir_path =
+            camino::Utf8Path::from_path(temp_dir.path()).expect("path should be valid UTF-8");
+
+        let results = checker.check_directory(dir_path).unwrap();
+        assert_eq!(results.len(), 1);
+        let names: Vec<_> = checker
+            .checked_files()
+            .iter()
+            .map(|f| f.rsplit('/').next().unwrap().to_string())
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "20240101000000_create_users.up.sql",
+                "20240102000000_add_idx.up.sql"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_refinery_concurrently_requires_no_transaction_header() {
+        use std::fs;
+
+        let temp_dir = tempdir().expect("Failed to create temp dir");
+        fs::write(
+            temp_dir.path().join("V1__add_idx.sql"),
+            "CREATE INDEX CONCURRENTLY idx_users_email ON users(email);",
+        )
+        .unwrap();
+        fs::write(
+            temp_dir.path().join("V2__add_other_idx.sql"),
+            "-- refinery:noTransaction\nCREATE INDEX CONCURRENTLY idx_users_name ON users(name);",
+        )
+        .unwrap();
+
+        let checker = SafetyChecker::with_config(Config {
+            framework: "refinery".to_string(),
+            enable_checks: vec!["AddIndexCheck".to_string()],
+            ..Default::default()
+        })
+        .unwrap();
+        let dir_path =
+            camino::Utf8Path::from_path(temp_dir.path()).expect("path should be valid UTF-8");
+
+        let results = checker.check_directory(dir_path).unwrap();
+        assert_eq!(results.len(), 1);
+        assert!(results[0].0.ends_with("V1__add_idx.sql"));
+        assert!(
+            results[0].1[0]
+                .1
+                .safe_alternative
+                .contains("-- refinery:noTransaction")
+        );
+    }
+
+    // --- Schema replay across migrations ---
+
+    /// Custom check that flags ALTER TABLE on tables missing from `ctx.schema`.
+    const UNKNOWN_TABLE_CHECK: &str = r#"
+let rel = node.AlterTableStmt?.relation ?? return;
+if ctx.schema.tables[rel.relname] == () {
+    #{ operation: "unknown table " + rel.relname, problem: "p", safe_alternative: "s" }
+}
+"#;
+
+    fn schema_checker(dir: &std::path::Path, framework: &str, config: Config) -> SafetyChecker {
+        let checks_dir = dir.join("checks");
+        fs::create_dir(&checks_dir).unwrap();
+        fs::write(checks_dir.join("unknown_table.rhai"), UNKNOWN_TABLE_CHECK).unwrap();
+        SafetyChecker::with_config(Config {
+            framework: framework.to_string(),
+            enable_checks: vec!["unknown_table".to_string()],
+            custom_checks_dir: Some(checks_dir.to_str().unwrap().to_string()),
+            ..config
+        })
+        .unwrap()
+    }
+
+    fn write_diesel_migration(dir: &std::path::Path, name: &str, up: &str, down: &str) {
+        let migration_dir = dir.join(name);
+        fs::create_dir_all(&migration_dir).unwrap();
+        fs::write(migration_dir.join("up.sql"), up).unwrap();
+        fs::write(migration_dir.join("down.sql"), down).unwrap();
+    }
+
+    fn operations(results: &[(String, ViolationList)]) -> Vec<String> {
+        results
+            .iter()
+            .flat_map(|(_, v)| v.iter().map(|(_, v)| v.operation.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn test_check_directory_sees_tables_from_earlier_migrations() {
+        let temp_dir = tempdir().expect("Failed to create temp dir");
+        let migrations = temp_dir.path().join("migrations");
+        write_diesel_migration(
+            &migrations,
+            "2024_01_01_000000_create_users",
+            "CREATE TABLE users (id BIGINT);",
+            "DROP TABLE users;",
+        );
+        write_diesel_migration(
+            &migrations,
+            "2024_01_02_000000_alter",
+            "ALTER TABLE users ADD COLUMN email TEXT;\nALTER TABLE ghosts ADD COLUMN email TEXT;",
+            "ALTER TABLE users DROP COLUMN email;",
+        );
+
+        let checker = schema_checker(temp_dir.path(), "diesel", Config::default());
+        let dir = Utf8Path::from_path(&migrations).unwrap();
+        let results = checker.check_directory(dir).unwrap();
+
+        assert_eq!(operations(&results), vec!["unknown table ghosts"]);
+    }
+
+    #[test]
+    fn test_check_directory_replays_migrations_before_start_after() {
+        let temp_dir = tempdir().expect("Failed to create temp dir");
+        let migrations = temp_dir.path().join("migrations");
+        write_diesel_migration(
+            &migrations,
+            "2024_01_01_000000_create_users",
+            "CREATE TABLE users (id BIGINT);",
+            "DROP TABLE users;",
+        );
+        write_diesel_migration(
+            &migrations,
+            "2024_01_02_000000_alter",
+            "ALTER TABLE users ADD COLUMN email TEXT;",
+            "ALTER TABLE users DROP COLUMN email;",
+        );
+
+        let checker = schema_checker(
+            temp_dir.path(),
+            "diesel",
+            Config {
+                start_after: Some("2024_01_01_000000".to_string()),
+                ..Default::default()
+            },
+        );
+        let dir = Utf8Path::from_path(&migrations).unwrap();
+        let results = checker.check_directory(dir).unwrap();
+
+        assert!(results.is_empty(), "users should be known: {results:?}");
+    }
+
+    #[test]
+    fn test_check_directory_down_migration_sees_its_up_schema() {
+        let temp_dir = tempdir().expect("Failed to create temp dir");
+        let migrations = temp_dir.path().join("migrations");
+        fs::create_dir(&migrations).unwrap();
+        // SQLx sorts `.down.sql` before `.up.sql`; the down file must still see the table.
+        fs::write(
+            migrations.join("20240101000000_users.up.sql"),
+            "CREATE TABLE users (id BIGINT);",
+        )
+        .unwrap();
+        fs::write(
+            migrations.join("20240101000000_users.down.sql"),
+            "ALTER TABLE users DROP COLUMN id;",
+        )
+        .unwrap();
+        fs::write(
+            migrations.join("20240102000000_drop.up.sql"),
+            "DROP TABLE users;",
+        )
+        .unwrap();
+        fs::write(
+            migrations.join("20240103000000_alter.up.sql"),
+            "ALTER TABLE users ADD COLUMN id BIGINT;",
+        )
+        .unwrap();
+
+        let checker = schema_checker(
+            temp_dir.path(),
+            "sqlx",
+            Config {
+                check_down: true,
+                ..Default::default()
+            },
+        );
+        let dir = Utf8Path::from_path(&migrations).unwrap();
+        let results = checker.check_directory(dir).unwrap();
+
+        assert_eq!(results.len(), 1);
+        assert!(results[0].0.ends_with("20240103000000_alter.up.sql"));
+        assert_eq!(operations(&results), vec!["unknown table users"]);
+    }
+
     // --- Line number integration tests (full pipeline through check_sql) ---
 
     fn line_number_checker() -> SafetyChecker {
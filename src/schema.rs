@@ -0,0 +1,709 @@
+//! In-memory schema catalog built by replaying migrations.
+//!
+//! `SafetyChecker::check_directory` replays every up migration in the order the
+//! adapter returns them, so each migration is checked against the tables,
+//! columns, constraints, and indexes left behind by the migrations before it.
+//! Checks read the catalog through `MigrationContext::schema`.
+//!
+//! The catalog is a best-effort model: statements it does not understand are
+//! ignored, and objects created outside the migration history are unknown.
+
+use crate::checks::pg_helpers::{
+    AlterTableType, ColumnDef, ConstrType, Constraint, NodeEnum, ObjectType, column_type_name,
+    drop_object_names, extract_node,
+};
+use pg_query::NodeRef;
+use pg_query::protobuf::{AlterTableStmt, CreateStmt, IndexStmt, RangeVar, RawStmt, RenameStmt};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Schema state produced by replaying migration statements.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SchemaCatalog {
+    tables: BTreeMap<String, Table>,
+}
+
+/// A table known to the catalog.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Table {
+    pub name: String,
+    pub columns: Vec<Column>,
+    pub constraints: Vec<TableConstraint>,
+    pub indexes: Vec<Index>,
+}
+
+/// A column of a catalog table.
+#[derive(Debug, Clone, Serialize)]
+pub struct Column {
+    pub name: String,
+    /// Last segment of the type name as pg_query reports it (e.g. "int4", "text").
+    pub type_name: String,
+    pub not_null: bool,
+}
+
+/// Kind of a table-level constraint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConstraintKind {
+    PrimaryKey,
+    Unique,
+    ForeignKey,
+    Check,
+    Exclusion,
+}
+
+/// A named constraint on a catalog table.
+#[derive(Debug, Clone, Serialize)]
+pub struct TableConstraint {
+    /// Explicit name, or the name Postgres would generate for an unnamed constraint.
+    pub name: String,
+    pub kind: ConstraintKind,
+    pub columns: Vec<String>,
+}
+
+/// An index on a catalog table.
+#[derive(Debug, Clone, Serialize)]
+pub struct Index {
+    pub name: String,
+    pub columns: Vec<String>,
+    pub unique: bool,
+}
+
+impl Table {
+    /// Look up a column by name.
+    pub fn column(&self, name: &str) -> Option<&Column> {
+        self.columns.iter().find(|c| c.name == name)
+    }
+
+    /// Look up a constraint by name.
+    pub fn constraint(&self, name: &str) -> Option<&TableConstraint> {
+        self.constraints.iter().find(|c| c.name == name)
+    }
+
+    /// Return true when the table has a primary key.
+    pub fn has_primary_key(&self) -> bool {
+        self.constraints
+            .iter()
+            .any(|c| c.kind == ConstraintKind::PrimaryKey)
+    }
+
+    fn column_mut(&mut self, name: &str) -> Option<&mut Column> {
+        self.columns.iter_mut().find(|c| c.name == name)
+    }
+
+    fn add_column(&mut self, col: &ColumnDef) {
+        if self.column(&col.colname).is_some() {
+            return;
+        }
+        let not_null = col.constraints.iter().any(|n| {
+            matches!(
+                &n.node,
+                Some(NodeEnum::Constraint(c))
+                    if c.contype == ConstrType::ConstrNotnull as i32
+                        || c.contype == ConstrType::ConstrPrimary as i32
+            )
+        });
+        self.columns.push(Column {
+            name: col.colname.clone(),
+            type_name: column_type_name(col),
+            not_null,
+        });
+        for node in &col.constraints {
+            if let Some(NodeEnum::Constraint(c)) = &node.node {
+                self.add_constraint(c, Some(&col.colname));
+            }
+        }
+    }
+
+    /// Record a constraint. `column` is set for column-level constraints,
+    /// whose key list is implicit.
+    fn add_constraint(&mut self, c: &Constraint, column: Option<&str>) {
+        let kind = match ConstrType::try_from(c.contype) {
+            Ok(ConstrType::ConstrPrimary) => ConstraintKind::PrimaryKey,
+            Ok(ConstrType::ConstrUnique) => ConstraintKind::Unique,
+            Ok(ConstrType::ConstrForeign) => ConstraintKind::ForeignKey,
+            Ok(ConstrType::ConstrCheck) => ConstraintKind::Check,
+            Ok(ConstrType::ConstrExclusion) => ConstraintKind::Exclusion,
+            _ => return,
+        };
+
+        let mut columns = match kind {
+            ConstraintKind::ForeignKey => string_list(&c.fk_attrs),
+            ConstraintKind::Check => referenced_columns(c),
+            ConstraintKind::Exclusion => exclusion_columns(c),
+            _ => string_list(&c.keys),
+        };
+        // A CHECK names exactly the columns its expression uses
+        if columns.is_empty()
+            && kind != ConstraintKind::Check
+            && let Some(column) = column
+        {
+            columns.push(column.to_string());
+        }
+
+        if kind == ConstraintKind::PrimaryKey {
+            for name in &columns {
+                if let Some(col) = self.column_mut(name) {
+                    col.not_null = true;
+                }
+            }
+        }
+
+        let name = if c.conname.is_empty() {
+            self.generated_constraint_name(kind, &columns)
+        } else {
+            c.conname.clone()
+        };
+        self.constraints.push(TableConstraint {
+            name,
+            kind,
+            columns,
+        });
+    }
+
+    /// Name Postgres generates for an unnamed constraint: table, columns and
+    /// a label, cut to fit an identifier, with a number after the label when
+    /// the name is taken. Only this table's constraints and indexes are
+    /// checked for collisions, not every relation in the schema.
+    fn generated_constraint_name(&self, kind: ConstraintKind, columns: &[String]) -> String {
+        let (addition, label) = match kind {
+            ConstraintKind::PrimaryKey => (String::new(), "pkey"),
+            ConstraintKind::Unique => (columns.join("_"), "key"),
+            ConstraintKind::ForeignKey => (columns.join("_"), "fkey"),
+            // Named after the column only when the expression uses exactly one
+            ConstraintKind::Check if columns.len() == 1 => (columns[0].clone(), "check"),
+            ConstraintKind::Check => (String::new(), "check"),
+            ConstraintKind::Exclusion => (columns.join("_"), "excl"),
+        };
+        let table = unqualified(&self.name);
+        (0..)
+            .map(|pass| match pass {
+                0 => make_object_name(table, &addition, label),
+                _ => make_object_name(table, &addition, &format!("{label}{pass}")),
+            })
+            .find(|name| {
+                self.constraint(name).is_none() && !self.indexes.iter().any(|i| i.name == *name)
+            })
+            .expect("some numbered name is free")
+    }
+}
+
+impl SchemaCatalog {
+    /// Look up a table by name. Unqualified names and `public.`-qualified names
+    /// refer to the same table.
+    pub fn table(&self, name: &str) -> Option<&Table> {
+        self.tables.get(&relation_key(name))
+    }
+
+    /// Return true when the catalog knows about the table.
+    pub fn has_table(&self, name: &str) -> bool {
+        self.table(name).is_some()
+    }
+
+    /// Iterate over all known tables in name order.
+    pub fn tables(&self) -> impl Iterator<Item = &Table> {
+        self.tables.values()
+    }
+
+    /// Find the table an index belongs to.
+    pub fn index_table(&self, index_name: &str) -> Option<&Table> {
+        let index_name = unqualified(index_name);
+        self.tables
+            .values()
+            .find(|t| t.indexes.iter().any(|i| i.name == index_name))
+    }
+
+    /// Replay every statement of a parsed migration.
+    pub fn apply_stmts(&mut self, stmts: &[RawStmt]) {
+        for node in stmts.iter().filter_map(extract_node) {
+            self.apply(node);
+        }
+    }
+
+    /// Replay a single statement. Statements that do not change the schema are ignored.
+    pub fn apply(&mut self, node: &NodeEnum) {
+        match node {
+            NodeEnum::CreateStmt(create) => self.apply_create(create),
+            NodeEnum::CreateTableAsStmt(create) => {
+                if let Some(rel) = create.into.as_ref().and_then(|i| i.rel.as_ref()) {
+                    self.create_table(rel);
+                }
+            }
+            NodeEnum::AlterTableStmt(alter) => self.apply_alter(alter),
+            NodeEnum::IndexStmt(index) => self.apply_index(index),
+            NodeEnum::RenameStmt(rename) => self.apply_rename(rename),
+            NodeEnum::DropStmt(drop) => {
+                let names = drop_object_names(&drop.objects);
+                match ObjectType::try_from(drop.remove_type) {
+                    Ok(ObjectType::ObjectTable) => {
+                        for name in names {
+                            self.tables.remove(&relation_key(&name));
+                        }
+                    }
+                    Ok(ObjectType::ObjectIndex) => {
+                        for name in names {
+                            let name = unqualified(&name);
+                            for table in self.tables.values_mut() {
+                                table.indexes.retain(|i| i.name != name);
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn create_table(&mut self, rel: &RangeVar) -> &mut Table {
+        let key = range_var_key(rel);
+        self.tables.entry(key.clone()).or_insert_with(|| Table {
+            name: key,
+            ..Table::default()
+        })
+    }
+
+    fn apply_create(&mut self, create: &CreateStmt) {
+        let Some(rel) = create.relation.as_ref() else {
+            return;
+        };
+        if create.if_not_exists && self.tables.contains_key(&range_var_key(rel)) {
+            return;
+        }
+        let table = self.create_table(rel);
+        for elt in &create.table_elts {
+            match &elt.node {
+                Some(NodeEnum::ColumnDef(col)) => table.add_column(col),
+                Some(NodeEnum::Constraint(c)) => table.add_constraint(c, None),
+                _ => {}
+            }
+        }
+    }
+
+    fn apply_alter(&mut self, alter: &AlterTableStmt) {
+        if alter.objtype != ObjectType::ObjectTable as i32 {
+            return;
+        }
+        let Some(table) = alter
+            .relation
+            .as_ref()
+            .and_then(|rel| self.tables.get_mut(&range_var_key(rel)))
+        else {
+            return;
+        };
+
+        for cmd in alter.cmds.iter().filter_map(|n| match &n.node {
+            Some(NodeEnum::AlterTableCmd(cmd)) => Some(cmd.as_ref()),
+            _ => None,
+        }) {
+            let def = cmd.def.as_ref().and_then(|d| d.node.as_ref());
+            match AlterTableType::try_from(cmd.subtype) {
+                Ok(AlterTableType::AtAddColumn) => {
+                    if let Some(NodeEnum::ColumnDef(col)) = def {
+                        table.add_column(col);
+                    }
+                }
+                Ok(AlterTableType::AtDropColumn) => {
+                    table.columns.retain(|c| c.name != cmd.name);
+                    table.constraints.retain(|c| !c.columns.contains(&cmd.name));
+                    table.indexes.retain(|i| !i.columns.contains(&cmd.name));
+                }
+                Ok(AlterTableType::AtAlterColumnType) => {
+                    if let (Some(NodeEnum::ColumnDef(col)), Some(existing)) =
+                        (def, table.column_mut(&cmd.name))
+                    {
+                        existing.type_name = column_type_name(col);
+                    }
+                }
+                Ok(AlterTableType::AtSetNotNull) => {
+                    if let Some(col) = table.column_mut(&cmd.name) {
+                        col.not_null = true;
+                    }
+                }
+                Ok(AlterTableType::AtDropNotNull) => {
+                    if let Some(col) = table.column_mut(&cmd.name) {
+                        col.not_null = false;
+                    }
+                }
+                Ok(AlterTableType::AtAddConstraint) => {
+                    if let Some(NodeEnum::Constraint(c)) = def {
+                        table.add_constraint(c, None);
+                    }
+                }
+                Ok(AlterTableType::AtDropConstraint) => {
+                    table.constraints.retain(|c| c.name != cmd.name);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn apply_index(&mut self, index: &IndexStmt) {
+        let Some(table) = index
+            .relation
+            .as_ref()
+            .and_then(|rel| self.tables.get_mut(&range_var_key(rel)))
+        else {
+            return;
+        };
+        let columns: Vec<String> = index
+            .index_params
+            .iter()
+            .filter_map(|n| match &n.node {
+                Some(NodeEnum::IndexElem(elem)) if !elem.name.is_empty() => Some(elem.name.clone()),
+                _ => None,
+            })
+            .collect();
+        let name = if index.idxname.is_empty() {
+            format!("{}_{}_idx", unqualified(&table.name), columns.join("_"))
+        } else {
+            index.idxname.clone()
+        };
+        if index.if_not_exists && table.indexes.iter().any(|i| i.name == name) {
+            return;
+        }
+        table.indexes.push(Index {
+            name,
+            columns,
+            unique: index.unique,
+        });
+    }
+
+    fn apply_rename(&mut self, rename: &RenameStmt) {
+        let rel_key = rename.relation.as_ref().map(range_var_key);
+        match ObjectType::try_from(rename.rename_type) {
+            Ok(ObjectType::ObjectTable) => {
+                let Some(old_key) = rel_key else { return };
+                if let Some(mut table) = self.tables.remove(&old_key) {
+                    let new_key = match old_key.rsplit_once('.') {
+                        Some((schema, _)) => format!("{schema}.{}", rename.newname),
+                        None => rename.newname.clone(),
+                    };
+                    table.name.clone_from(&new_key);
+                    self.tables.insert(new_key, table);
+                }
+            }
+            Ok(ObjectType::ObjectColumn) => {
+                let Some(table) = rel_key.and_then(|k| self.tables.get_mut(&k)) else {
+                    return;
+                };
+                if let Some(col) = table.column_mut(&rename.subname) {
+                    col.name.clone_from(&rename.newname);
+                }
+                let renamed = |cols: &mut Vec<String>| {
+                    for col in cols.iter_mut().filter(|c| **c == rename.subname) {
+                        col.clone_from(&rename.newname);
+                    }
+                };
+                for c in &mut table.constraints {
+                    renamed(&mut c.columns);
+                }
+                for i in &mut table.indexes {
+                    renamed(&mut i.columns);
+                }
+            }
+            Ok(ObjectType::ObjectTabconstraint) => {
+                let Some(table) = rel_key.and_then(|k| self.tables.get_mut(&k)) else {
+                    return;
+                };
+                if let Some(c) = table
+                    .constraints
+                    .iter_mut()
+                    .find(|c| c.name == rename.subname)
+                {
+                    c.name.clone_from(&rename.newname);
+                }
+            }
+            Ok(ObjectType::ObjectIndex) => {
+                let Some(old_name) = rename.relation.as_ref().map(|r| r.relname.clone()) else {
+                    return;
+                };
+                for table in self.tables.values_mut() {
+                    if let Some(i) = table.indexes.iter_mut().find(|i| i.name == old_name) {
+                        i.name.clone_from(&rename.newname);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Normalize a relation name so `users` and `public.users` share a key.
+pub(crate) fn relation_key(name: &str) -> String {
+    name.strip_prefix("public.").unwrap_or(name).to_string()
+}
+
+fn range_var_key(rv: &RangeVar) -> String {
+    if rv.schemaname.is_empty() || rv.schemaname == "public" {
+        rv.relname.clone()
+    } else {
+        format!("{}.{}", rv.schemaname, rv.relname)
+    }
+}
+
+/// Strip any schema qualifier from an object name.
+fn unqualified(name: &str) -> &str {
+    name.rsplit_once('.').map_or(name, |(_, n)| n)
+}
+
+fn string_list(nodes: &[pg_query::protobuf::Node]) -> Vec<String> {
+    nodes
+        .iter()
+        .filter_map(|n| match &n.node {
+            Some(NodeEnum::String(s)) => Some(s.sval.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Distinct columns a CHECK expression refers to, in order of appearance.
+fn referenced_columns(c: &Constraint) -> Vec<String> {
+    let Some(expr) = c.raw_expr.as_ref().and_then(|e| e.node.as_ref()) else {
+        return vec![];
+    };
+    let mut columns: Vec<String> = Vec::new();
+    for (node, ..) in expr.nodes() {
+        if let NodeRef::ColumnRef(col) = node
+            && let Some(name) = string_list(&col.fields).pop()
+            && !columns.contains(&name)
+        {
+            columns.push(name);
+        }
+    }
+    columns
+}
+
+/// Columns of an EXCLUDE constraint; each element is an index element and its operator.
+fn exclusion_columns(c: &Constraint) -> Vec<String> {
+    c.exclusions
+        .iter()
+        .filter_map(|n| match &n.node {
+            Some(NodeEnum::List(pair)) => match pair.items.first().and_then(|i| i.node.as_ref()) {
+                Some(NodeEnum::IndexElem(elem)) if !elem.name.is_empty() => Some(elem.name.clone()),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect()
+}
+
+/// Longest identifier Postgres keeps, in bytes (`NAMEDATALEN - 1`).
+const MAX_IDENTIFIER_LEN: usize = 63;
+
+/// Postgres' `makeObjectName`: join the parts with `_`, shortening the longer
+/// of `name1` and `name2` until the result fits in an identifier.
+fn make_object_name(name1: &str, name2: &str, label: &str) -> String {
+    let overhead = label.len() + 1 + usize::from(!name2.is_empty());
+    let available = MAX_IDENTIFIER_LEN.saturating_sub(overhead);
+    let (mut len1, mut len2) = (name1.len(), name2.len());
+    while len1 + len2 > available {
+        if len1 > len2 {
+            len1 -= 1;
+        } else {
+            len2 -= 1;
+        }
+    }
+
+    let mut name = clip(name1, len1).to_string();
+    if !name2.is_empty() {
+        name.push('_');
+        name.push_str(clip(name2, len2));
+    }
+    name.push('_');
+    name.push_str(label);
+    name
+}
+
+/// The longest prefix of `s` of at most `len` bytes that ends on a character boundary.
+fn clip(s: &str, mut len: usize) -> &str {
+    while !s.is_char_boundary(len) {
+        len -= 1;
+    }
+    &s[..len]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn catalog(sql: &str) -> SchemaCatalog {
+        let stmts = crate::parser::parse(sql).expect("SQL should parse");
+        let mut catalog = SchemaCatalog::default();
+        catalog.apply_stmts(&stmts);
+        catalog
+    }
+
+    #[test]
+    fn test_create_table_records_columns_and_constraints() {
+        let catalog = catalog(
+            "CREATE TABLE users (id BIGINT PRIMARY KEY, email TEXT NOT NULL UNIQUE, name TEXT);",
+        );
+        let users = catalog.table("users").expect("users should exist");
+        assert_eq!(users.columns.len(), 3);
+        assert!(users.column("id").unwrap().not_null);
+        assert!(users.column("email").unwrap().not_null);
+        assert!(!users.column("name").unwrap().not_null);
+        assert_eq!(users.column("id").unwrap().type_name, "int8");
+        assert!(users.has_primary_key());
+        assert!(users.constraint("users_email_key").is_some());
+    }
+
+    #[test]
+    fn test_public_schema_is_equivalent_to_unqualified() {
+        let catalog = catalog("CREATE TABLE public.users (id BIGINT);");
+        assert!(catalog.has_table("users"));
+        assert!(catalog.has_table("public.users"));
+        assert!(!catalog.has_table("other.users"));
+    }
+
+    #[test]
+    fn test_alter_table_column_changes() {
+        let catalog = catalog(
+            "CREATE TABLE users (id BIGINT, email TEXT, legacy TEXT);
+             ALTER TABLE users ADD COLUMN admin BOOLEAN;
+             ALTER TABLE users DROP COLUMN legacy;
+             ALTER TABLE users ALTER COLUMN email SET NOT NULL;
+             ALTER TABLE users ALTER COLUMN id TYPE INTEGER;",
+        );
+        let users = catalog.table("users").unwrap();
+        assert!(users.column("admin").is_some());
+        assert!(users.column("legacy").is_none());
+        assert!(users.column("email").unwrap().not_null);
+        assert_eq!(users.column("id").unwrap().type_name, "int4");
+    }
+
+    #[test]
+    fn test_constraints_added_and_dropped() {
+        let catalog = catalog(
+            "CREATE TABLE posts (id BIGINT, user_id BIGINT);
+             ALTER TABLE posts ADD CONSTRAINT posts_user_fk FOREIGN KEY (user_id) REFERENCES users(id);
+             ALTER TABLE posts ADD PRIMARY KEY (id);
+             ALTER TABLE posts DROP CONSTRAINT posts_user_fk;",
+        );
+        let posts = catalog.table("posts").unwrap();
+        assert!(posts.constraint("posts_user_fk").is_none());
+        assert!(posts.constraint("posts_pkey").is_some());
+        assert!(posts.column("id").unwrap().not_null);
+    }
+
+    #[test]
+    fn test_indexes_created_renamed_and_dropped() {
+        let catalog = catalog(
+            "CREATE TABLE users (id BIGINT, email TEXT);
+             CREATE UNIQUE INDEX idx_email ON users(email);
+             CREATE INDEX idx_id ON users(id);
+             ALTER INDEX idx_id RENAME TO idx_users_id;
+             DROP INDEX idx_email;",
+        );
+        let users = catalog.table("users").unwrap();
+        assert_eq!(users.indexes.len(), 1);
+        assert_eq!(users.indexes[0].name, "idx_users_id");
+        assert_eq!(catalog.index_table("idx_users_id").unwrap().name, "users");
+    }
+
+    #[test]
+    fn test_rename_table_and_column() {
+        let catalog = catalog(
+            "CREATE TABLE users (id BIGINT, email TEXT);
+             ALTER TABLE users RENAME COLUMN email TO email_address;
+             ALTER TABLE users RENAME TO accounts;",
+        );
+        assert!(!catalog.has_table("users"));
+        let accounts = catalog.table("accounts").unwrap();
+        assert_eq!(accounts.name, "accounts");
+        assert!(accounts.column("email_address").is_some());
+    }
+
+    #[test]
+    fn test_drop_table_removes_it() {
+        let catalog = catalog("CREATE TABLE users (id BIGINT); DROP TABLE IF EXISTS users;");
+        assert!(!catalog.has_table("users"));
+    }
+
+    #[test]
+    fn test_create_table_if_not_exists_keeps_existing_definition() {
+        let catalog = catalog(
+            "CREATE TABLE users (id BIGINT, email TEXT);
+             CREATE TABLE IF NOT EXISTS users (id BIGINT);",
+        );
+        assert_eq!(catalog.table("users").unwrap().columns.len(), 2);
+    }
+
+    #[test]
+    fn test_statements_on_unknown_tables_are_ignored() {
+        let catalog = catalog(
+            "ALTER TABLE ghosts ADD COLUMN name TEXT;
+             CREATE INDEX idx_ghosts ON ghosts(name);",
+        );
+        assert_eq!(catalog.tables().count(), 0);
+    }
+
+    #[test]
+    fn test_generated_constraint_names_match_postgres() {
+        let catalog = catalog(
+            "CREATE TABLE orders (a INT, b INT, price INT, qty INT CHECK (qty > 0));
+             ALTER TABLE orders ADD FOREIGN KEY (a, b) REFERENCES items(a, b);
+             ALTER TABLE orders ADD CHECK (price > 0 AND price < 1000);
+             ALTER TABLE orders ADD CHECK (price > a);
+             ALTER TABLE orders ADD UNIQUE (a, b);",
+        );
+        let names: Vec<_> = catalog
+            .table("orders")
+            .unwrap()
+            .constraints
+            .iter()
+            .map(|c| c.name.as_str())
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "orders_qty_check",
+                "orders_a_b_fkey",
+                "orders_price_check",
+                "orders_check",
+                "orders_a_b_key",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_generated_constraint_names_are_numbered_on_collision() {
+        let catalog = catalog(
+            "CREATE TABLE orders (price INT);
+             ALTER TABLE orders ADD CHECK (price > 0);
+             ALTER TABLE orders ADD CHECK (price < 1000);
+             ALTER TABLE orders ADD CHECK (price <> 13);",
+        );
+        let orders = catalog.table("orders").unwrap();
+        assert!(orders.constraint("orders_price_check").is_some());
+        assert!(orders.constraint("orders_price_check1").is_some());
+        assert!(orders.constraint("orders_price_check2").is_some());
+    }
+
+    #[test]
+    fn test_generated_constraint_names_are_truncated_like_postgres() {
+        let table = "t".repeat(40);
+        let column = "c".repeat(40);
+        let catalog = catalog(&format!(
+            "CREATE TABLE {table} ({column} INT REFERENCES users(id));"
+        ));
+        let constraint = &catalog.table(&table).unwrap().constraints[0];
+        // 63 bytes: the longer part gives way first, so the table keeps one more character
+        assert_eq!(
+            constraint.name,
+            format!("{}_{}_fkey", "t".repeat(29), "c".repeat(28))
+        );
+        assert_eq!(make_object_name("tàble", "é", "check"), "tàble_é_check");
+        assert_eq!(
+            make_object_name(&"é".repeat(40), "", "pkey"),
+            format!("{}_pkey", "é".repeat(29))
+        );
+    }
+
+    #[test]
+    fn test_create_table_as_records_table() {
+        let catalog = catalog("CREATE TABLE archived AS SELECT 1 AS id;");
+        assert!(catalog.has_table("archived"));
+    }
+}
@@ -1,9 +1,10 @@
-use crate::checks::{Check, CheckDoc, MigrationContext};
+use crate::ViolationList;
+use crate::checks::{Check, CheckDoc, MigrationContext, MigrationStmt};
 use crate::config::Config;
-use crate::violation::Violation;
+use crate::violation::{Fix, Violation};
 use camino::Utf8Path;
 use pg_query::protobuf::node::Node as NodeEnum;
-use rhai::{AST, Dynamic, Engine};
+use rhai::{AST, CallFnOptions, Dynamic, Engine, EvalAltResult};
 use std::sync::Arc;
 
 /// Error encountered while loading or running a custom Rhai check script.
@@ -32,6 +33,30 @@ impl CustomCheck {
             "This is likely a diesel-guard bug. Please report it.",
         )]
     }
+
+    fn runtime_error(&self, err: &EvalAltResult) -> Vec<Violation> {
+        vec![Violation::new(
+            format!("SCRIPT ERROR: {}", self.name),
+            format!("Runtime error in custom check '{}': {err}", self.name),
+            "Fix the custom check script to eliminate the runtime error.",
+        )]
+    }
+
+    /// The `config`, `ctx` and `options` values scripts receive.
+    fn script_inputs(
+        &self,
+        config: &Config,
+        ctx: &MigrationContext,
+    ) -> Result<(Dynamic, Dynamic, Dynamic), Box<EvalAltResult>> {
+        let dynamic_config = rhai::serde::to_dynamic(config)?;
+        let dynamic_ctx = rhai::serde::to_dynamic(ctx)?;
+        // The script's own [checks.<name>] table, or an empty map
+        let dynamic_options = match config.checks.get(self.name) {
+            Some(options) => rhai::serde::to_dynamic(options)?,
+            None => Dynamic::from_map(rhai::Map::new()),
+        };
+        Ok((dynamic_config, dynamic_ctx, dynamic_options))
+    }
 }
 
 impl Check for CustomCheck {
@@ -64,30 +89,90 @@ impl Check for CustomCheck {
             Err(e) => return self.internal_error(&e),
         };
 
-        let dynamic_config = match rhai::serde::to_dynamic(config) {
-            Ok(d) => d,
+        let (dynamic_config, dynamic_ctx, dynamic_options) = match self.script_inputs(config, ctx) {
+            Ok(inputs) => inputs,
             Err(e) => return self.internal_error(&e),
         };
 
-        let dynamic_ctx = rhai::serde::to_dynamic(ctx).unwrap();
-
         let mut scope = rhai::Scope::new();
         scope.push("node", dynamic_node);
         scope.push("config", dynamic_config);
         scope.push("ctx", dynamic_ctx);
+        scope.push("options", dynamic_options);
 
         match self
             .engine
             .eval_ast_with_scope::<Dynamic>(&mut scope, &self.ast)
         {
             Ok(result) => parse_script_result(self.name, result),
-            Err(e) => {
-                vec![Violation::new(
+            Err(e) => self.runtime_error(&e),
+        }
+    }
+
+    fn check_file(
+        &self,
+        stmts: &[MigrationStmt<'_>],
+        config: &Config,
+        ctx: &MigrationContext,
+    ) -> ViolationList {
+        // Only scripts that define check_migration look at the whole file
+        let Some(arity) = self
+            .ast
+            .iter_functions()
+            .find(|f| f.name == "check_migration")
+            .map(|f| f.params.len())
+        else {
+            return vec![];
+        };
+        let Some(first_line) = stmts.first().map(|s| s.line) else {
+            return vec![];
+        };
+        let at_first_line = |violations: Vec<Violation>| -> ViolationList {
+            violations.into_iter().map(|v| (first_line, v)).collect()
+        };
+
+        let inputs = self
+            .script_inputs(config, ctx)
+            .and_then(|inputs| Ok((rhai::serde::to_dynamic(stmts)?, inputs)));
+        let (dynamic_stmts, (dynamic_config, dynamic_ctx, dynamic_options)) = match inputs {
+            Ok(inputs) => inputs,
+            Err(e) => return at_first_line(self.internal_error(&e)),
+        };
+
+        // Script functions can't see the scope, so config and options are
+        // passed to the four-parameter form
+        let options = CallFnOptions::new().eval_ast(false);
+        let mut scope = rhai::Scope::new();
+        let result = match arity {
+            2 => self.engine.call_fn_with_options::<Dynamic>(
+                options,
+                &mut scope,
+                &self.ast,
+                "check_migration",
+                (dynamic_stmts, dynamic_ctx),
+            ),
+            4 => self.engine.call_fn_with_options::<Dynamic>(
+                options,
+                &mut scope,
+                &self.ast,
+                "check_migration",
+                (dynamic_stmts, dynamic_ctx, dynamic_config, dynamic_options),
+            ),
+            _ => {
+                return at_first_line(vec![Violation::new(
                     format!("SCRIPT ERROR: {}", self.name),
-                    format!("Runtime error in custom check '{}': {e}", self.name),
-                    "Fix the custom check script to eliminate the runtime error.",
-                )]
+                    format!(
+                        "check_migration in custom check '{}' must take 2 or 4 parameters, found {arity}",
+                        self.name
+                    ),
+                    "Declare it as fn check_migration(stmts, ctx) or fn check_migration(stmts, ctx, config, options).",
+                )]);
             }
+        };
+
+        match result {
+            Ok(result) => parse_migration_result(self.name, result, first_line),
+            Err(e) => at_first_line(self.runtime_error(&e)),
         }
     }
 }
@@ -126,6 +211,51 @@ fn parse_script_result(check_name: &str, result: Dynamic) -> Vec<Violation> {
     )]
 }
 
+/// Parse the return value of a script's `check_migration` function into
+/// `(line, violation)` pairs.
+///
+/// Accepts the same values as [`parse_script_result`]; each map also needs a
+/// `line` key with the line of the statement it belongs to. Errors are
+/// reported on `first_line`, the line of the first statement.
+fn parse_migration_result(check_name: &str, result: Dynamic, first_line: usize) -> ViolationList {
+    if !result.is_map() && !result.is_array() {
+        return parse_script_result(check_name, result)
+            .into_iter()
+            .map(|v| (first_line, v))
+            .collect();
+    }
+
+    let items = if result.is_array() {
+        result.into_array().unwrap_or_default()
+    } else {
+        vec![result]
+    };
+    items
+        .into_iter()
+        .map(|item| {
+            let line = item
+                .read_lock::<rhai::Map>()
+                .map(|map| map.get("line").and_then(|l| l.as_int().ok()));
+            match line {
+                // Not a map: map_to_violation reports the type
+                None => (first_line, map_to_violation(check_name, item)),
+                Some(Some(line)) if line > 0 => (
+                    usize::try_from(line).unwrap_or(first_line),
+                    map_to_violation(check_name, item),
+                ),
+                Some(_) => (
+                    first_line,
+                    Violation::new(
+                        format!("SCRIPT ERROR: {check_name}"),
+                        "Custom check returned a violation from check_migration without a valid 'line' key",
+                        "Add the line of the statement the violation belongs to, e.g. line: stmt.line.",
+                    ),
+                ),
+            }
+        })
+        .collect()
+}
+
 /// Convert a Rhai map Dynamic to a Violation.
 fn map_to_violation(check_name: &str, value: Dynamic) -> Violation {
     let type_name = value.type_name().to_owned();
@@ -150,7 +280,16 @@ fn map_to_violation(check_name: &str, value: Dynamic) -> Violation {
         .and_then(|v| v.clone().into_string().ok());
 
     if let (Some(op), Some(prob), Some(alt)) = (operation, problem, safe_alternative) {
-        Violation::new(op, prob, alt)
+        let violation = Violation::new(op, prob, alt);
+        match map.get("fix").map(parse_fix) {
+            None => violation,
+            Some(Ok(fix)) => violation.with_fix(fix),
+            Some(Err(issue)) => Violation::new(
+                format!("SCRIPT ERROR: {check_name}"),
+                format!("Custom check returned an invalid fix: {issue}"),
+                "Fix the custom check script to return a fix map with an edits array of #{ start, end, replacement } maps.",
+            ),
+        }
     } else {
         let mut issues = Vec::new();
         for key in &["operation", "problem", "safe_alternative"] {
@@ -173,6 +312,18 @@ fn map_to_violation(check_name: &str, value: Dynamic) -> Violation {
     }
 }
 
+/// Convert the optional `fix` key of a returned map to a [`Fix`].
+fn parse_fix(value: &Dynamic) -> Result<Fix, String> {
+    let fix: Fix = rhai::serde::from_dynamic(value).map_err(|e| e.to_string())?;
+    if let Some(edit) = fix.edits.iter().find(|e| e.start > e.end) {
+        return Err(format!(
+            "edit start ({}) is after its end ({})",
+            edit.start, edit.end
+        ));
+    }
+    Ok(fix)
+}
+
 /// Build a Rhai module exposing commonly needed pg_query protobuf enum constants.
 ///
 /// Scripts access these as `pg::OBJECT_TABLE`, `pg::AT_ADD_COLUMN`, etc.
@@ -283,7 +434,7 @@ pub fn load_custom_checks(
             .unwrap_or("unknown");
 
         // Skip scripts disabled via config
-        if !config.is_check_enabled(stem) {
+        if !config.may_enable_check(stem) {
             continue;
         }
 
@@ -325,6 +476,7 @@ pub fn load_custom_checks(
 mod tests {
     use super::*;
     use crate::checks::pg_helpers::extract_node;
+    use crate::violation::{Applicability, TextEdit};
     use std::fs;
     use tempfile::tempdir;
 
@@ -631,6 +783,160 @@ mod tests {
         assert_eq!(violations.len(), 1);
     }
 
+    #[test]
+    fn test_options_hold_the_scripts_own_section() {
+        let script = r#"
+            let stmt = node.CreateStmt;
+            if stmt == () { return; }
+            let allowed = if "allowed_tables" in options { options.allowed_tables } else { [] };
+            if stmt.relation.relname in allowed { return; }
+            #{ operation: "CREATE TABLE", problem: "not allowed", safe_alternative: "none" }
+            "#;
+        let mut config = crate::config::Config::default();
+        config.checks.insert(
+            "test_check".to_string(),
+            toml::from_str(r#"allowed_tables = ["users"]"#).unwrap(),
+        );
+
+        assert!(run_script_with_config(script, "CREATE TABLE users (id INT);", &config).is_empty());
+        assert_eq!(
+            run_script_with_config(script, "CREATE TABLE posts (id INT);", &config).len(),
+            1
+        );
+        // Without a section, options is an empty map
+        assert_eq!(run_script(script, "CREATE TABLE users (id INT);").len(), 1);
+    }
+
+    /// Helper: run a script over a whole migration through the registry.
+    fn run_migration_script(
+        script: &str,
+        sql: &str,
+        config: &crate::config::Config,
+    ) -> ViolationList {
+        let config = crate::config::Config {
+            enable_checks: vec!["test_check".to_string()],
+            ..config.clone()
+        };
+        let engine = Arc::new(create_engine());
+        let ast = engine.compile(script).expect("script should compile");
+        let mut registry = crate::checks::Registry::with_config(&config);
+        registry.add_check(Box::new(CustomCheck {
+            name: "test_check",
+            engine,
+            ast,
+            path: String::new(),
+        }));
+
+        let stmts = crate::parser::parse(sql).expect("SQL should parse");
+        registry.check_stmts_with_context(
+            &stmts,
+            sql,
+            &[],
+            &config,
+            &crate::checks::MigrationContext::default(),
+        )
+    }
+
+    #[test]
+    fn test_check_migration_sees_all_statements_with_their_lines() {
+        let script = r#"
+            fn check_migration(stmts, ctx) {
+                if stmts.len() < 2 { return; }
+                let found = [];
+                for stmt in stmts {
+                    let index = stmt.node.IndexStmt ?? continue;
+                    if index.concurrent {
+                        found.push(#{
+                            line: stmt.line,
+                            operation: "CONCURRENTLY mixed with other statements",
+                            problem: "p",
+                            safe_alternative: "s"
+                        });
+                    }
+                }
+                found
+            }
+            "#;
+        let sql = "ALTER TABLE users ADD COLUMN email TEXT;
+CREATE INDEX CONCURRENTLY idx_users_email ON users(email);";
+
+        let violations = run_migration_script(script, sql, &crate::config::Config::default());
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].0, 2);
+        assert_eq!(
+            violations[0].1.operation,
+            "CONCURRENTLY mixed with other statements"
+        );
+        assert_eq!(violations[0].1.check_name, "test_check");
+
+        let single = "CREATE INDEX CONCURRENTLY idx_users_email ON users(email);";
+        assert!(run_migration_script(script, single, &crate::config::Config::default()).is_empty());
+    }
+
+    #[test]
+    fn test_check_migration_receives_config_and_options() {
+        let script = r#"
+            fn check_migration(stmts, ctx, config, options) {
+                let max = if "max_statements" in options { options.max_statements } else { 10 };
+                if stmts.len() <= max { return; }
+                #{
+                    line: stmts[max].line,
+                    operation: "Too many statements",
+                    problem: "pg " + config.postgres_version,
+                    safe_alternative: "s"
+                }
+            }
+            "#;
+        let mut config = crate::config::Config {
+            postgres_version: Some(16),
+            ..Default::default()
+        };
+        config.checks.insert(
+            "test_check".to_string(),
+            toml::from_str("max_statements = 1").unwrap(),
+        );
+        let sql = "DROP TABLE a;
+DROP TABLE b;
+DROP TABLE c;";
+
+        let violations = run_migration_script(script, sql, &config);
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].0, 2);
+        assert_eq!(violations[0].1.problem, "pg 16");
+    }
+
+    #[test]
+    fn test_check_migration_without_line_produces_error_violation() {
+        let script = r#"
+            fn check_migration(stmts, ctx) {
+                #{ operation: "op", problem: "p", safe_alternative: "s" }
+            }
+            "#;
+        let sql = "DROP TABLE a;
+DROP TABLE b;";
+
+        let violations = run_migration_script(script, sql, &crate::config::Config::default());
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].0, 1);
+        assert_eq!(violations[0].1.operation, "SCRIPT ERROR: test_check");
+        assert_eq!(
+            violations[0].1.problem,
+            "Custom check returned a violation from check_migration without a valid 'line' key"
+        );
+    }
+
+    #[test]
+    fn test_check_migration_with_wrong_arity_produces_error_violation() {
+        let script = "fn check_migration(stmts) { }";
+        let violations =
+            run_migration_script(script, "DROP TABLE a;", &crate::config::Config::default());
+        assert_eq!(violations.len(), 1);
+        assert_eq!(
+            violations[0].1.problem,
+            "check_migration in custom check 'test_check' must take 2 or 4 parameters, found 1"
+        );
+    }
+
     #[test]
     fn test_pg_constants_no_match() {
         // Script checks for OBJECT_TABLE but SQL drops an index — should not match
@@ -795,6 +1101,66 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_map_with_fix_attaches_fix() {
+        let violations = run_script(
+            r#"
+            let stmt = node.IndexStmt;
+            #{
+                operation: "op",
+                problem: "p",
+                safe_alternative: "s",
+                fix: #{
+                    applicability: "machine_applicable",
+                    edits: [#{ start: 12, end: 12, replacement: " CONCURRENTLY" }]
+                }
+            }
+            "#,
+            "CREATE INDEX idx ON users(email);",
+        );
+        assert_eq!(violations.len(), 1);
+        let fix = violations[0].fix.as_ref().expect("fix should be attached");
+        assert_eq!(fix.applicability, Applicability::MachineApplicable);
+        assert_eq!(fix.edits, vec![TextEdit::insert(12, " CONCURRENTLY")]);
+    }
+
+    #[test]
+    fn test_fix_applicability_defaults_to_maybe_incorrect() {
+        let violations = run_script(
+            r#"#{ operation: "op", problem: "p", safe_alternative: "s", fix: #{ edits: [] } }"#,
+            "CREATE INDEX idx ON users(email);",
+        );
+        assert_eq!(
+            violations[0].fix.as_ref().unwrap().applicability,
+            Applicability::MaybeIncorrect
+        );
+    }
+
+    #[test]
+    fn test_map_with_invalid_fix_produces_error_violation() {
+        let violations = run_script(
+            r#"#{ operation: "op", problem: "p", safe_alternative: "s", fix: #{ edits: [#{ start: 5, end: 1, replacement: "" }] } }"#,
+            "CREATE INDEX idx ON users(email);",
+        );
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].operation, "SCRIPT ERROR: test_check");
+        assert_eq!(
+            violations[0].problem,
+            "Custom check returned an invalid fix: edit start (5) is after its end (1)"
+        );
+
+        let violations = run_script(
+            r#"#{ operation: "op", problem: "p", safe_alternative: "s", fix: "CONCURRENTLY" }"#,
+            "CREATE INDEX idx ON users(email);",
+        );
+        assert_eq!(violations[0].operation, "SCRIPT ERROR: test_check");
+        assert!(
+            violations[0]
+                .problem
+                .starts_with("Custom check returned an invalid fix:")
+        );
+    }
+
     fn make_test_check() -> CustomCheck {
         let engine = Arc::new(create_engine());
         let ast = engine.compile("()").expect("script should compile");
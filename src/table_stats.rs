@@ -0,0 +1,277 @@
+//! Offline table-size hints.
+//!
+//! `table_stats` in diesel-guard.toml points at a snapshot of production table
+//! sizes, exported from `pg_class` as JSON or CSV and committed with the
+//! migrations. Lock, rewrite and scan violations then state how big the table
+//! is, and `large_table_rows` / `large_table_bytes` decide whether they are
+//! errors or warnings. Nothing connects to a database.
+//!
+//! JSON files hold an array of objects; CSV files start with a header row.
+//! Both use the same columns:
+//!
+//! - `table` (or `relname`), optionally schema-qualified, plus an optional
+//!   `schema` (or `nspname`)
+//! - `rows` (or `reltuples`); negative values (never analyzed) count as unknown
+//! - `bytes` (or `total_bytes`, `pg_total_relation_size`)
+
+use crate::config::ConfigError;
+use crate::schema::relation_key;
+use camino::Utf8Path;
+use std::collections::BTreeMap;
+
+/// Size of one table from the snapshot.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TableSize {
+    /// Estimated row count (`pg_class.reltuples`), if known.
+    pub rows: Option<u64>,
+    /// Total size in bytes including indexes and TOAST, if known.
+    pub bytes: Option<u64>,
+}
+
+impl TableSize {
+    /// Human-readable size, e.g. "~480M rows / 210 GB".
+    pub fn describe(&self) -> String {
+        let rows = self
+            .rows
+            .map(|rows| format!("~{} rows", format_count(rows)));
+        let bytes = self.bytes.map(format_bytes);
+        match (rows, bytes) {
+            (Some(rows), Some(bytes)) => format!("{rows} / {bytes}"),
+            (Some(rows), None) => rows,
+            (None, Some(bytes)) => bytes,
+            (None, None) => "of unknown size".to_string(),
+        }
+    }
+}
+
+/// Table sizes keyed by relation name (`public.` dropped).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TableStats {
+    tables: BTreeMap<String, TableSize>,
+}
+
+impl TableStats {
+    /// Read a snapshot file; `.csv` files are read as CSV, everything else as JSON.
+    pub fn load(path: &Utf8Path) -> Result<Self, ConfigError> {
+        let contents = std::fs::read_to_string(path)?;
+        let parsed = if path.extension() == Some("csv") {
+            Self::from_csv(&contents)
+        } else {
+            Self::from_json(&contents)
+        };
+        parsed.map_err(|reason| ConfigError::InvalidTableStats {
+            path: path.to_string(),
+            reason,
+        })
+    }
+
+    /// Parse a JSON array of row objects.
+    pub fn from_json(contents: &str) -> Result<Self, String> {
+        let entries: Vec<serde_json::Map<String, serde_json::Value>> =
+            serde_json::from_str(contents).map_err(|e| e.to_string())?;
+        let mut stats = Self::default();
+        for (i, row) in entries.iter().enumerate() {
+            let text = |names: &[&str]| {
+                json_field(row, names)
+                    .and_then(serde_json::Value::as_str)
+                    .unwrap_or_default()
+            };
+            let rows = json_field(row, ROWS_COLUMNS)
+                .and_then(|v| v.as_u64().or_else(|| v.as_f64().and_then(row_estimate)));
+            stats
+                .insert(
+                    text(SCHEMA_COLUMNS),
+                    text(TABLE_COLUMNS),
+                    rows,
+                    json_field(row, BYTES_COLUMNS).and_then(serde_json::Value::as_u64),
+                )
+                .map_err(|reason| format!("entry {}: {reason}", i + 1))?;
+        }
+        Ok(stats)
+    }
+
+    /// Parse CSV with a header row. Values may be double-quoted but must not
+    /// contain commas.
+    pub fn from_csv(contents: &str) -> Result<Self, String> {
+        let mut lines = contents
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty());
+        let header: Vec<&str> = lines
+            .next()
+            .map(|(_, line)| split_csv_line(line))
+            .unwrap_or_default();
+        let column = |names: &[&str]| header.iter().position(|h| names.contains(h));
+        let schema = column(SCHEMA_COLUMNS);
+        let Some(table) = column(TABLE_COLUMNS) else {
+            return Err("missing a \"table\" column".to_string());
+        };
+        let (rows, bytes) = (column(ROWS_COLUMNS), column(BYTES_COLUMNS));
+
+        let mut stats = Self::default();
+        for (i, line) in lines {
+            let values = split_csv_line(line);
+            let value = |index: Option<usize>| {
+                index
+                    .and_then(|i| values.get(i).copied())
+                    .filter(|v| !v.is_empty())
+            };
+            let invalid = |name: &str| format!("line {}: invalid {name}", i + 1);
+            let row_count = value(rows)
+                .map(|v| v.parse::<f64>().map_err(|_| invalid("rows")))
+                .transpose()?
+                .and_then(row_estimate);
+            let byte_count = value(bytes)
+                .map(|v| v.parse::<u64>().map_err(|_| invalid("bytes")))
+                .transpose()?;
+            stats
+                .insert(
+                    value(schema).unwrap_or_default(),
+                    value(Some(table)).unwrap_or_default(),
+                    row_count,
+                    byte_count,
+                )
+                .map_err(|reason| format!("line {}: {reason}", i + 1))?;
+        }
+        Ok(stats)
+    }
+
+    fn insert(
+        &mut self,
+        schema: &str,
+        table: &str,
+        rows: Option<u64>,
+        bytes: Option<u64>,
+    ) -> Result<(), String> {
+        if table.is_empty() {
+            return Err("missing table name".to_string());
+        }
+        let name = if schema.is_empty() || table.contains('.') {
+            table.to_string()
+        } else {
+            format!("{schema}.{table}")
+        };
+        self.tables.insert(
+            relation_key(&name.to_ascii_lowercase()),
+            TableSize { rows, bytes },
+        );
+        Ok(())
+    }
+
+    /// Size of `table` (as returned by `range_var_name`), if the snapshot has it.
+    pub fn get(&self, table: &str) -> Option<&TableSize> {
+        self.tables.get(&relation_key(&table.to_ascii_lowercase()))
+    }
+}
+
+const SCHEMA_COLUMNS: &[&str] = &["schema", "nspname"];
+const TABLE_COLUMNS: &[&str] = &["table", "relname"];
+const ROWS_COLUMNS: &[&str] = &["rows", "reltuples"];
+const BYTES_COLUMNS: &[&str] = &["bytes", "total_bytes", "pg_total_relation_size"];
+
+fn json_field<'a>(
+    row: &'a serde_json::Map<String, serde_json::Value>,
+    names: &[&str],
+) -> Option<&'a serde_json::Value> {
+    names.iter().find_map(|name| row.get(*name))
+}
+
+fn split_csv_line(line: &str) -> Vec<&str> {
+    line.split(',')
+        .map(|value| value.trim().trim_matches('"'))
+        .collect()
+}
+
+/// `reltuples` as a whole row count; `None` for -1 (never analyzed).
+fn row_estimate(reltuples: f64) -> Option<u64> {
+    format!("{reltuples:.0}").parse().ok()
+}
+
+/// 480000000 -> "480M", 1500 -> "1.5K", 12 -> "12".
+fn format_count(count: u64) -> String {
+    const UNITS: [(u64, &str); 3] = [(1_000_000_000, "B"), (1_000_000, "M"), (1_000, "K")];
+    UNITS.iter().find(|(scale, _)| count >= *scale).map_or_else(
+        || count.to_string(),
+        |(scale, unit)| format!("{}{unit}", round_short(count, *scale)),
+    )
+}
+
+/// Bytes in Postgres' `pg_size_pretty` units, e.g. "210 GB".
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["kB", "MB", "GB", "TB"];
+    if bytes < 1024 {
+        return format!("{bytes} bytes");
+    }
+    let mut scale: u64 = 1024;
+    let mut unit = 0;
+    while unit < UNITS.len() - 1 && bytes / scale >= 1024 {
+        scale *= 1024;
+        unit += 1;
+    }
+    format!("{} {}", round_short(bytes, scale), UNITS[unit])
+}
+
+/// `value / scale` with one decimal below 10 and none above.
+fn round_short(value: u64, scale: u64) -> String {
+    let (value, scale) = (u128::from(value), u128::from(scale));
+    let tenths = (value * 10 + scale / 2) / scale;
+    if tenths >= 100 {
+        ((value + scale / 2) / scale).to_string()
+    } else if tenths % 10 == 0 {
+        (tenths / 10).to_string()
+    } else {
+        format!("{}.{}", tenths / 10, tenths % 10)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_json_with_pg_class_column_names() {
+        let stats = TableStats::from_json(
+            r#"[
+  {"nspname": "public", "relname": "orders", "reltuples": 480000000, "pg_total_relation_size": 225485783040},
+  {"table": "audit.events", "rows": -1, "bytes": 8192}
+]"#,
+        )
+        .unwrap();
+
+        let orders = stats.get("orders").unwrap();
+        assert_eq!(orders.describe(), "~480M rows / 210 GB");
+        assert_eq!(stats.get("public.Orders"), Some(orders));
+        assert_eq!(stats.get("audit.events").unwrap().describe(), "8 kB");
+        assert_eq!(stats.get("events"), None);
+    }
+
+    #[test]
+    fn test_from_csv() {
+        let stats = TableStats::from_csv(
+            "schema,table,rows,bytes\n\"public\",\"countries\",250,65536\nbilling,invoices,1500000,\n",
+        )
+        .unwrap();
+        assert_eq!(
+            stats.get("countries").unwrap().describe(),
+            "~250 rows / 64 kB"
+        );
+        assert_eq!(
+            stats.get("billing.invoices").unwrap().describe(),
+            "~1.5M rows"
+        );
+    }
+
+    #[test]
+    fn test_invalid_snapshots() {
+        assert!(TableStats::from_csv("rows,bytes\n1,2\n").is_err());
+        assert_eq!(
+            TableStats::from_csv("table,rows\norders,many\n").unwrap_err(),
+            "line 2: invalid rows"
+        );
+        assert_eq!(
+            TableStats::from_json(r#"[{"rows": 1}]"#).unwrap_err(),
+            "entry 1: missing table name"
+        );
+        assert!(TableStats::from_json("{}").is_err());
+    }
+}
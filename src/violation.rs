@@ -1,5 +1,6 @@
 use derive_more::Display;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
 #[serde(rename_all = "lowercase")]
@@ -9,6 +10,48 @@ pub enum Severity {
     Warning,
 }
 
+/// How confidently a [`Fix`] can be applied without review.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Applicability {
+    /// The edits are correct on their own and can be applied automatically.
+    MachineApplicable,
+    /// The edits move in the right direction but need more changes or review
+    /// (e.g. CONCURRENTLY inside a migration that still runs in a transaction).
+    #[default]
+    MaybeIncorrect,
+    /// The replacement contains placeholders the user has to fill in.
+    HasPlaceholders,
+}
+
+/// Replace the bytes `start..end` of the migration file with `replacement`.
+///
+/// Insertions have `start == end`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TextEdit {
+    pub start: usize,
+    pub end: usize,
+    pub replacement: String,
+}
+
+impl TextEdit {
+    pub fn insert(offset: usize, text: impl Into<String>) -> Self {
+        Self {
+            start: offset,
+            end: offset,
+            replacement: text.into(),
+        }
+    }
+}
+
+/// Structured fix for a violation: text edits plus how safe they are to apply.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Fix {
+    #[serde(default)]
+    pub applicability: Applicability,
+    pub edits: Vec<TextEdit>,
+}
+
 #[derive(Debug, Clone, Serialize, Display)]
 #[display("{}: {}", operation, problem)]
 pub struct Violation {
@@ -18,6 +61,18 @@ pub struct Violation {
     pub safe_alternative: String,
     #[serde(default)]
     pub severity: Severity,
+    /// Machine-readable fix, when the violation has a mechanical one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fix: Option<Fix>,
+    /// 1-indexed column (in characters) of the statement's first token.
+    /// Set by the registry alongside the line number; `None` when unknown.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub column: Option<usize>,
+    /// Size of the locked table from `table_stats`, e.g. "Table 'orders' is
+    /// ~480M rows / 210 GB.". Kept out of `problem` so report fingerprints
+    /// survive a refreshed stats snapshot.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub table_size: Option<String>,
 }
 
 impl Violation {
@@ -32,9 +87,18 @@ impl Violation {
             problem: problem.into(),
             safe_alternative: safe_alternative.into(),
             severity: Severity::Error,
+            fix: None,
+            column: None,
+            table_size: None,
         }
     }
 
+    #[must_use]
+    pub fn with_fix(mut self, fix: Fix) -> Self {
+        self.fix = Some(fix);
+        self
+    }
+
     #[must_use]
     pub fn with_severity(mut self, severity: Severity) -> Self {
         self.severity = severity;
@@ -46,6 +110,15 @@ impl Violation {
         self.check_name = name.to_string();
         self
     }
+
+    /// `problem` followed by the table size, when known. Reports show this;
+    /// fingerprints use `problem` alone.
+    pub fn full_problem(&self) -> Cow<'_, str> {
+        match &self.table_size {
+            Some(size) => Cow::Owned(format!("{} {size}", self.problem)),
+            None => Cow::Borrowed(&self.problem),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -63,4 +136,22 @@ mod tests {
         let v = Violation::new("op", "prob", "alt").with_check_name("AddIndexCheck");
         assert_eq!(v.check_name, "AddIndexCheck");
     }
+
+    #[test]
+    fn test_fix_serializes_only_when_present() {
+        let v = Violation::new("op", "prob", "alt");
+        assert!(serde_json::to_value(&v).unwrap().get("fix").is_none());
+
+        let v = v.with_fix(Fix {
+            applicability: Applicability::MachineApplicable,
+            edits: vec![TextEdit::insert(12, " CONCURRENTLY")],
+        });
+        assert_eq!(
+            serde_json::to_value(&v).unwrap()["fix"],
+            serde_json::json!({
+                "applicability": "machine_applicable",
+                "edits": [{"start": 12, "end": 12, "replacement": " CONCURRENTLY"}]
+            })
+        );
+    }
 }
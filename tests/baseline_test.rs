@@ -0,0 +1,175 @@
+use assert_cmd::Command;
+use std::fs;
+use std::path::Path;
+use tempfile::tempdir;
+
+fn write_migration(root: &Path, name: &str, sql: &str) {
+    fs::write(
+        root.join("diesel-guard.toml"),
+        "framework = \"diesel\"\nenable_checks = [\"AddIndexCheck\", \"DropColumnCheck\"]\n",
+    )
+    .unwrap();
+    let migration_dir = root.join("migrations").join(name);
+    fs::create_dir_all(&migration_dir).unwrap();
+    fs::write(migration_dir.join("up.sql"), sql).unwrap();
+}
+
+fn diesel_guard(root: &Path, args: &[&str]) -> std::process::Output {
+    Command::cargo_bin("diesel-guard")
+        .unwrap()
+        .args(args)
+        .current_dir(root)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn test_baseline_hides_existing_violations() {
+    let temp_dir = tempdir().expect("Failed to create temp dir");
+    write_migration(
+        temp_dir.path(),
+        "2024_01_01_000000_add_index",
+        "CREATE INDEX idx_users_email ON users(email);\nALTER TABLE users DROP COLUMN legacy;\n",
+    );
+
+    let output = diesel_guard(temp_dir.path(), &["baseline"]);
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "✓ Wrote 2 baseline entries to diesel-guard-baseline.json\n"
+    );
+
+    let baseline: serde_json::Value = serde_json::from_str(
+        &fs::read_to_string(temp_dir.path().join("diesel-guard-baseline.json")).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(baseline["version"], 1);
+    assert_eq!(
+        baseline["entries"][0]["file"],
+        "migrations/2024_01_01_000000_add_index/up.sql"
+    );
+    assert_eq!(baseline["entries"][0]["check_name"], "AddIndexCheck");
+
+    Command::cargo_bin("diesel-guard")
+        .unwrap()
+        .arg("check")
+        .current_dir(temp_dir.path())
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_baseline_reports_new_violations_only() {
+    let temp_dir = tempdir().expect("Failed to create temp dir");
+    write_migration(
+        temp_dir.path(),
+        "2024_01_01_000000_add_index",
+        "CREATE INDEX idx_users_email ON users(email);\n",
+    );
+    assert!(
+        diesel_guard(temp_dir.path(), &["baseline"])
+            .status
+            .success()
+    );
+
+    // Reformatting a baselined statement keeps it baselined
+    write_migration(
+        temp_dir.path(),
+        "2024_01_01_000000_add_index",
+        "-- legacy\ncreate index idx_users_email\n    on users (email);\n\nCREATE INDEX idx_users_name ON users(name);\n",
+    );
+
+    let output = diesel_guard(temp_dir.path(), &["check", "--format", "json"]);
+    assert!(!output.status.success());
+    let results: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let violations = results[0]["violations"].as_array().unwrap();
+    assert_eq!(violations.len(), 1);
+    assert_eq!(violations[0]["line"], 5);
+}
+
+#[test]
+fn test_baseline_warns_about_stale_entries() {
+    let temp_dir = tempdir().expect("Failed to create temp dir");
+    write_migration(
+        temp_dir.path(),
+        "2024_01_01_000000_add_index",
+        "CREATE INDEX idx_users_email ON users(email);\n",
+    );
+    assert!(
+        diesel_guard(temp_dir.path(), &["baseline"])
+            .status
+            .success()
+    );
+
+    write_migration(
+        temp_dir.path(),
+        "2024_01_01_000000_add_index",
+        "CREATE INDEX CONCURRENTLY idx_users_email ON users(email);\n",
+    );
+    fs::write(
+        temp_dir
+            .path()
+            .join("migrations/2024_01_01_000000_add_index/metadata.toml"),
+        "run_in_transaction = false\n",
+    )
+    .unwrap();
+
+    let output = diesel_guard(temp_dir.path(), &["check"]);
+    assert!(output.status.success());
+    assert!(String::from_utf8(output.stderr).unwrap().is_empty());
+
+    let output = diesel_guard(temp_dir.path(), &["check", "--warn-stale-baseline"]);
+    assert!(output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains(
+        "Warning: stale baseline entry migrations/2024_01_01_000000_add_index/up.sql (AddIndexCheck) matches no violation"
+    ));
+    assert!(stderr.contains("Run `diesel-guard baseline` to refresh the baseline."));
+}
+
+#[test]
+fn test_baseline_custom_path() {
+    let temp_dir = tempdir().expect("Failed to create temp dir");
+    write_migration(
+        temp_dir.path(),
+        "2024_01_01_000000_add_index",
+        "CREATE INDEX idx_users_email ON users(email);\n",
+    );
+
+    let output = diesel_guard(
+        temp_dir.path(),
+        &["baseline", "--output", "ci-baseline.json"],
+    );
+    assert!(output.status.success());
+    assert!(!temp_dir.path().join("diesel-guard-baseline.json").exists());
+
+    // Not picked up unless passed explicitly
+    assert!(!diesel_guard(temp_dir.path(), &["check"]).status.success());
+    assert!(
+        diesel_guard(
+            temp_dir.path(),
+            &["check", "--baseline", "ci-baseline.json"]
+        )
+        .status
+        .success()
+    );
+}
+
+#[test]
+fn test_check_rejects_invalid_baseline() {
+    let temp_dir = tempdir().expect("Failed to create temp dir");
+    write_migration(temp_dir.path(), "2024_01_01_000000_noop", "SELECT 1;\n");
+    fs::write(
+        temp_dir.path().join("diesel-guard-baseline.json"),
+        "not json",
+    )
+    .unwrap();
+
+    let output = diesel_guard(temp_dir.path(), &["check"]);
+    assert!(!output.status.success());
+    assert!(
+        String::from_utf8(output.stderr)
+            .unwrap()
+            .contains("Invalid baseline file 'diesel-guard-baseline.json'")
+    );
+}
@@ -0,0 +1,154 @@
+use assert_cmd::Command;
+use std::fs;
+use std::path::Path;
+use tempfile::tempdir;
+
+const UNSAFE_INDEX: &str = "CREATE INDEX idx_users_email ON users(email);\n";
+
+fn git(root: &Path, args: &[&str]) {
+    let status = std::process::Command::new("git")
+        .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+        .args(args)
+        .current_dir(root)
+        .output()
+        .expect("Failed to run git");
+    assert!(status.status.success(), "git {args:?} failed: {status:?}");
+}
+
+fn write_migration(root: &Path, name: &str, sql: &str) {
+    let dir = root.join("migrations").join(name);
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("up.sql"), sql).unwrap();
+}
+
+/// A repo with one committed migration that already violates AddIndexCheck.
+fn setup_repo(root: &Path) {
+    fs::write(
+        root.join("diesel-guard.toml"),
+        "framework = \"diesel\"\nenable_checks = [\"AddIndexCheck\"]\n",
+    )
+    .unwrap();
+    write_migration(root, "2024_01_01_000000_old_index", UNSAFE_INDEX);
+    git(root, &["init", "-q", "-b", "main"]);
+    git(root, &["add", "-A"]);
+    git(root, &["commit", "-q", "-m", "initial"]);
+}
+
+fn check(root: &Path, args: &[&str]) -> (bool, String) {
+    let output = Command::cargo_bin("diesel-guard")
+        .unwrap()
+        .arg("check")
+        .args(args)
+        .args(["--format", "json"])
+        .current_dir(root)
+        .output()
+        .unwrap();
+    (
+        output.status.success(),
+        String::from_utf8(output.stdout).unwrap(),
+    )
+}
+
+#[test]
+fn test_since_checks_only_migrations_changed_on_branch() {
+    let temp_dir = tempdir().expect("Failed to create temp dir");
+    let root = temp_dir.path();
+    setup_repo(root);
+
+    git(root, &["checkout", "-q", "-b", "feature"]);
+    write_migration(root, "2024_02_01_000000_new_index", UNSAFE_INDEX);
+    git(root, &["add", "-A"]);
+    git(root, &["commit", "-q", "-m", "add index"]);
+
+    let (success, stdout) = check(root, &["--since", "main"]);
+    assert!(!success);
+    assert!(stdout.contains("2024_02_01_000000_new_index"));
+    assert!(!stdout.contains("2024_01_01_000000_old_index"));
+}
+
+#[test]
+fn test_since_passes_when_no_migration_changed() {
+    let temp_dir = tempdir().expect("Failed to create temp dir");
+    let root = temp_dir.path();
+    setup_repo(root);
+
+    let (success, stdout) = check(root, &["--since", "main"]);
+    assert!(success);
+    assert_eq!(stdout.trim(), "[]");
+}
+
+#[test]
+fn test_since_follows_renamed_migration_directory() {
+    let temp_dir = tempdir().expect("Failed to create temp dir");
+    let root = temp_dir.path();
+    setup_repo(root);
+
+    git(root, &["checkout", "-q", "-b", "feature"]);
+    git(
+        root,
+        &[
+            "mv",
+            "migrations/2024_01_01_000000_old_index",
+            "migrations/2024_03_01_000000_old_index",
+        ],
+    );
+    git(root, &["commit", "-q", "-m", "regenerate"]);
+
+    let (success, stdout) = check(root, &["--since", "main"]);
+    assert!(!success);
+    assert!(stdout.contains("2024_03_01_000000_old_index"));
+}
+
+#[test]
+fn test_changed_only_checks_untracked_and_modified_migrations() {
+    let temp_dir = tempdir().expect("Failed to create temp dir");
+    let root = temp_dir.path();
+    setup_repo(root);
+    write_migration(root, "2024_01_02_000000_clean", "SELECT 1;\n");
+    git(root, &["add", "-A"]);
+    git(root, &["commit", "-q", "-m", "clean"]);
+
+    write_migration(root, "2024_02_01_000000_untracked", UNSAFE_INDEX);
+    fs::write(
+        root.join("migrations/2024_01_02_000000_clean/up.sql"),
+        UNSAFE_INDEX,
+    )
+    .unwrap();
+
+    let (success, stdout) = check(root, &["--changed-only"]);
+    assert!(!success);
+    assert!(stdout.contains("2024_02_01_000000_untracked"));
+    assert!(stdout.contains("2024_01_02_000000_clean"));
+    assert!(!stdout.contains("2024_01_01_000000_old_index"));
+}
+
+#[test]
+fn test_changed_only_from_subdirectory_sees_untracked_migrations() {
+    let temp_dir = tempdir().expect("Failed to create temp dir");
+    let root = temp_dir.path();
+    setup_repo(root);
+    fs::create_dir(root.join("src")).unwrap();
+
+    write_migration(root, "2024_02_01_000000_untracked", UNSAFE_INDEX);
+
+    let (success, stdout) = check(&root.join("src"), &["--changed-only"]);
+    assert!(!success);
+    assert!(stdout.contains("2024_02_01_000000_untracked"));
+    assert!(!stdout.contains("2024_01_01_000000_old_index"));
+}
+
+#[test]
+fn test_since_unknown_ref_fails() {
+    let temp_dir = tempdir().expect("Failed to create temp dir");
+    let root = temp_dir.path();
+    setup_repo(root);
+
+    let output = Command::cargo_bin("diesel-guard")
+        .unwrap()
+        .args(["check", "--since", "does-not-exist"])
+        .current_dir(root)
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("git merge-base failed"));
+}
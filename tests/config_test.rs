@@ -1,5 +1,6 @@
 use assert_cmd::Command;
 use camino::Utf8Path;
+use diesel_guard::violation::Severity;
 use diesel_guard::{Config, ConfigError, SafetyChecker};
 use miette::Diagnostic as _;
 use std::fs;
@@ -691,7 +692,7 @@ fn test_config_invalid_toml_syntax() {
 }
 
 #[test]
-fn test_config_empty_file_errors() {
+fn test_config_empty_file_leaves_framework_unset() {
     let temp_dir = tempdir().expect("Failed to create temp dir");
     let config_path = temp_dir.path().join("diesel-guard.toml");
 
@@ -699,10 +700,10 @@ fn test_config_empty_file_errors() {
     fs::write(&config_path, "").unwrap();
 
     let config_path_utf8 = Utf8Path::from_path(&config_path).unwrap();
-    let err = Config::load_from_path(config_path_utf8).unwrap_err();
-    assert!(
-        matches!(err, ConfigError::MissingFramework),
-        "Expected MissingFramework for empty config, got: {err:?}"
+    let config = Config::load_from_path(config_path_utf8).unwrap();
+    assert_eq!(
+        config.framework, "",
+        "framework is detected from the project"
     );
 }
 
@@ -754,3 +755,359 @@ fn test_check_stdin_with_violations() {
         "Expected violation output, got: {stdout}"
     );
 }
+
+#[test]
+fn test_config_is_found_in_parent_directory() {
+    let temp_dir = tempdir().expect("Failed to create temp dir");
+    let root = Utf8Path::from_path(temp_dir.path()).unwrap();
+    fs::create_dir(root.join(".git")).unwrap();
+    fs::create_dir_all(root.join("crates/app")).unwrap();
+    fs::write(
+        root.join("diesel-guard.toml"),
+        "framework = \"sqlx\"\ncustom_checks_dir = \"checks\"\n",
+    )
+    .unwrap();
+
+    let found = Config::find(&root.join("crates/app")).unwrap().unwrap();
+    assert_eq!(found, root.join("crates/app/../../diesel-guard.toml"));
+
+    // Relative paths in the file are resolved against its directory
+    let config = Config::load_from_path(&found).unwrap();
+    assert_eq!(config.framework, "sqlx");
+    assert_eq!(
+        config.custom_checks_dir.as_deref(),
+        Some(root.join("crates/app/../../checks").as_str())
+    );
+    assert_eq!(config.source, Some(found));
+}
+
+#[test]
+fn test_config_search_stops_at_repository_root() {
+    let temp_dir = tempdir().expect("Failed to create temp dir");
+    let outer = Utf8Path::from_path(temp_dir.path()).unwrap();
+    fs::write(outer.join("diesel-guard.toml"), "framework = \"sqlx\"\n").unwrap();
+    fs::create_dir_all(outer.join("repo/.git")).unwrap();
+
+    assert_eq!(Config::find(&outer.join("repo")).unwrap(), None);
+}
+
+#[test]
+fn test_config_from_cargo_metadata() {
+    let temp_dir = tempdir().expect("Failed to create temp dir");
+    let dir = Utf8Path::from_path(temp_dir.path()).unwrap();
+    fs::create_dir(dir.join(".git")).unwrap();
+    fs::write(
+        dir.join("Cargo.toml"),
+        "[workspace]\nmembers = []\n\n[workspace.metadata.diesel-guard]\nframework = \"refinery\"\nwarn_checks = [\"AddIndexCheck\"]\n",
+    )
+    .unwrap();
+
+    let found = Config::find(dir).unwrap().unwrap();
+    assert_eq!(found, dir.join("Cargo.toml"));
+    let config = Config::load_from_path(&found).unwrap();
+    assert_eq!(config.framework, "refinery");
+    assert!(config.is_check_warning("AddIndexCheck"));
+
+    // diesel-guard.toml wins over Cargo.toml metadata in the same directory
+    fs::write(dir.join("diesel-guard.toml"), "framework = \"sqlx\"\n").unwrap();
+    assert_eq!(
+        Config::find(dir).unwrap().unwrap(),
+        dir.join("diesel-guard.toml")
+    );
+}
+
+#[test]
+fn test_cargo_toml_without_metadata_is_rejected() {
+    let temp_dir = tempdir().expect("Failed to create temp dir");
+    let manifest = Utf8Path::from_path(temp_dir.path())
+        .unwrap()
+        .join("Cargo.toml");
+    fs::write(&manifest, "[package]\nname = \"app\"\n").unwrap();
+
+    let err = Config::load_from_path(&manifest).unwrap_err();
+    assert!(matches!(err, ConfigError::MissingCargoMetadata { .. }));
+    assert_eq!(
+        err.code().unwrap().to_string(),
+        "diesel_guard::config::missing_cargo_metadata"
+    );
+}
+
+#[test]
+fn test_config_flag_and_list_checks_report_config_path() {
+    let temp_dir = tempdir().expect("Failed to create temp dir");
+    fs::create_dir_all(temp_dir.path().join(".git")).unwrap();
+    fs::create_dir_all(temp_dir.path().join("app")).unwrap();
+    fs::create_dir_all(temp_dir.path().join("ci")).unwrap();
+    fs::write(
+        temp_dir.path().join("diesel-guard.toml"),
+        "framework = \"diesel\"\n",
+    )
+    .unwrap();
+    fs::write(
+        temp_dir.path().join("ci/strict.toml"),
+        "framework = \"diesel\"\ndisable_checks = [\"AddColumnCheck\"]\n",
+    )
+    .unwrap();
+
+    let output = Command::cargo_bin("diesel-guard")
+        .unwrap()
+        .arg("list-checks")
+        .current_dir(temp_dir.path().join("app"))
+        .output()
+        .unwrap();
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "Config: ../diesel-guard.toml\n"
+    );
+
+    let output = Command::cargo_bin("diesel-guard")
+        .unwrap()
+        .args(["list-checks", "--config", "../ci/strict.toml"])
+        .current_dir(temp_dir.path().join("app"))
+        .output()
+        .unwrap();
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "Config: ../ci/strict.toml\n"
+    );
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let line = stdout
+        .lines()
+        .find(|l| l.starts_with("AddColumnCheck"))
+        .unwrap();
+    assert!(line.ends_with("no"));
+}
+
+#[test]
+fn test_small_tables_skip_or_downgrade_lock_violations() {
+    let sql = "CREATE INDEX idx_countries_name ON countries(name);
+CREATE INDEX idx_orders_user_id ON orders(user_id);";
+    let config = Config {
+        small_tables: vec!["countries".to_string(), "orders".to_string()],
+        large_tables: vec!["orders".to_string()],
+        enable_checks: vec!["AddIndexCheck".to_string()],
+        ..Default::default()
+    };
+    let checker = SafetyChecker::with_config(config.clone()).unwrap();
+    let violations = checker.check_sql(sql).unwrap();
+    assert_eq!(violations.len(), 1);
+    assert_eq!(violations[0].0, 2);
+
+    let warn = Config {
+        small_tables_mode: diesel_guard::SmallTablesMode::Warn,
+        ..config
+    };
+    let checker = SafetyChecker::with_config(warn).unwrap();
+    let severities: Vec<_> = checker
+        .check_sql(sql)
+        .unwrap()
+        .into_iter()
+        .map(|(line, v)| (line, v.severity))
+        .collect();
+    assert_eq!(severities, [(1, Severity::Warning), (2, Severity::Error)]);
+}
+
+#[test]
+fn test_table_stats_make_lock_violations_concrete() {
+    let temp_dir = tempdir().expect("Failed to create temp dir");
+    let dir = Utf8Path::from_path(temp_dir.path()).unwrap();
+    fs::write(
+        dir.join("table_stats.json"),
+        r#"[
+  {"schema": "public", "table": "orders", "rows": 480000000, "bytes": 225485783040},
+  {"schema": "public", "table": "countries", "rows": 250, "bytes": 65536}
+]"#,
+    )
+    .unwrap();
+    fs::write(
+        dir.join("diesel-guard.toml"),
+        "enable_checks = [\"AddIndexCheck\"]\ntable_stats = \"table_stats.json\"\n",
+    )
+    .unwrap();
+    let config = Config::load_from_path(&dir.join("diesel-guard.toml")).unwrap();
+    let checker = SafetyChecker::with_config(config).unwrap();
+
+    let violations = checker
+        .check_sql(
+            "CREATE INDEX idx_orders_user_id ON orders(user_id);
+CREATE INDEX idx_countries_name ON countries(name);
+CREATE INDEX idx_users_email ON users(email);",
+        )
+        .unwrap();
+    let summary: Vec<_> = violations
+        .iter()
+        .map(|(line, v)| {
+            (
+                *line,
+                v.severity,
+                v.full_problem().contains("~480M rows / 210 GB"),
+            )
+        })
+        .collect();
+    assert_eq!(
+        summary,
+        [
+            (1, Severity::Error, true),
+            (2, Severity::Warning, false),
+            (3, Severity::Error, false),
+        ]
+    );
+    // The size is kept apart from the problem text
+    assert_eq!(
+        violations[1].1.table_size.as_deref(),
+        Some("Table 'countries' is ~250 rows / 64 kB.")
+    );
+    assert!(!violations[1].1.problem.contains("~250 rows"));
+    assert!(
+        violations[1]
+            .1
+            .full_problem()
+            .ends_with("Reads are still allowed. Table 'countries' is ~250 rows / 64 kB.")
+    );
+    assert_eq!(violations[2].1.table_size, None);
+}
+
+#[test]
+fn test_opt_in_checks_turn_on_require_lock_timeout() {
+    let sql = "ALTER TABLE users DROP COLUMN legacy_id;
+SET LOCAL lock_timeout = '2s';
+ALTER TABLE orders DROP COLUMN legacy_id;";
+
+    let checker = SafetyChecker::with_config(Config::default()).unwrap();
+    let violations = checker.check_sql(sql).unwrap();
+    assert!(
+        violations
+            .iter()
+            .all(|(_, v)| v.check_name != "RequireLockTimeoutCheck")
+    );
+
+    let config: Config = toml::from_str(
+        r#"
+opt_in_checks = ["RequireLockTimeoutCheck"]
+
+[checks.RequireLockTimeoutCheck]
+max_lock_timeout_ms = 5000
+"#,
+    )
+    .unwrap();
+    let checker = SafetyChecker::with_config(config).unwrap();
+    let violations = checker.check_sql(sql).unwrap();
+    let lock_timeout: Vec<_> = violations
+        .iter()
+        .filter(|(_, v)| v.check_name == "RequireLockTimeoutCheck")
+        .map(|(line, v)| (*line, v.operation.as_str()))
+        .collect();
+    assert_eq!(
+        lock_timeout,
+        [(1, "ALTER TABLE on 'users' without lock_timeout")]
+    );
+}
+
+#[test]
+fn test_opt_in_lock_accumulation_reports_the_statement_over_the_limit() {
+    let sql = "ALTER TABLE users ADD COLUMN admin BOOLEAN;
+ALTER TABLE orders ADD COLUMN note TEXT;
+ALTER TABLE payments ADD COLUMN note TEXT;
+ALTER TABLE invoices ADD COLUMN note TEXT;";
+
+    let accumulation = |config: Config| -> Vec<(usize, String)> {
+        let checker = SafetyChecker::with_config(config).unwrap();
+        checker
+            .check_sql(sql)
+            .unwrap()
+            .into_iter()
+            .filter(|(_, v)| v.check_name == "LockAccumulationCheck")
+            .map(|(line, v)| (line, v.operation))
+            .collect()
+    };
+
+    assert!(accumulation(Config::default()).is_empty());
+
+    let config: Config = toml::from_str(
+        r#"
+opt_in_checks = ["LockAccumulationCheck"]
+
+[checks.LockAccumulationCheck]
+max_tables = 3
+"#,
+    )
+    .unwrap();
+    assert_eq!(
+        accumulation(config),
+        [(
+            4,
+            "Write-blocking locks on many tables in one transaction".to_string()
+        )]
+    );
+}
+
+#[test]
+fn test_unknown_opt_in_check_name_is_rejected() {
+    let config = Config {
+        opt_in_checks: vec!["LockTimeoutCheck".to_string()],
+        ..Default::default()
+    };
+    assert!(matches!(
+        SafetyChecker::with_config(config),
+        Err(ConfigError::InvalidCheckName { .. })
+    ));
+}
+
+#[test]
+fn test_defaults_resolve_next_to_config_found_in_parent_directory() {
+    let temp_dir = tempdir().expect("Failed to create temp dir");
+    let root = Utf8Path::from_path(temp_dir.path()).unwrap();
+    fs::create_dir(root.join(".git")).unwrap();
+    fs::create_dir_all(root.join("crates/app")).unwrap();
+    fs::create_dir_all(root.join("migrations/2024_01_01_000000_add_index")).unwrap();
+    fs::write(
+        root.join("migrations/2024_01_01_000000_add_index/up.sql"),
+        "CREATE INDEX idx_users_email ON users(email);",
+    )
+    .unwrap();
+    fs::write(root.join("diesel.toml"), "").unwrap();
+    fs::write(
+        root.join("diesel-guard.toml"),
+        "enable_checks = [\"AddIndexCheck\"]\n",
+    )
+    .unwrap();
+    let app = root.join("crates/app");
+
+    // The framework comes from the root's diesel.toml and ../../migrations is checked
+    let output = Command::cargo_bin("diesel-guard")
+        .unwrap()
+        .arg("check")
+        .current_dir(&app)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert!(
+        String::from_utf8(output.stderr)
+            .unwrap()
+            .contains("Detected framework \"diesel\"")
+    );
+
+    // Baseline and lock are written next to the config, keyed relative to it
+    for command in ["baseline", "lock"] {
+        Command::cargo_bin("diesel-guard")
+            .unwrap()
+            .arg(command)
+            .current_dir(&app)
+            .assert()
+            .success();
+    }
+    let baseline = fs::read_to_string(root.join("diesel-guard-baseline.json")).unwrap();
+    assert!(baseline.contains("\"migrations/2024_01_01_000000_add_index/up.sql\""));
+    assert!(root.join("diesel-guard.lock").is_file());
+    assert!(!app.join("diesel-guard-baseline.json").exists());
+
+    // ...and found again from the subcrate and from the root alike
+    for dir in [&app, root] {
+        Command::cargo_bin("diesel-guard")
+            .unwrap()
+            .arg("check")
+            .current_dir(dir)
+            .assert()
+            .success();
+    }
+}
@@ -143,6 +143,51 @@ CREATE INDEX idx ON users(email);
     );
 }
 
+#[test]
+fn test_check_migration_violations_honour_safety_assured_blocks() {
+    let dir = tempdir().expect("Failed to create temp dir");
+
+    fs::write(
+        dir.path().join("every_statement.rhai"),
+        r#"
+        fn check_migration(stmts, ctx) {
+            let violations = [];
+            for stmt in stmts {
+                violations.push(#{
+                    line: stmt.line,
+                    operation: "file-level",
+                    problem: "p",
+                    safe_alternative: "s"
+                });
+            }
+            violations
+        }
+        "#,
+    )
+    .unwrap();
+
+    let config = Config {
+        custom_checks_dir: Some(dir.path().to_str().unwrap().to_string()),
+        enable_checks: vec!["every_statement".to_string()],
+        ..Default::default()
+    };
+    let checker = SafetyChecker::with_config(config).unwrap();
+
+    let sql = "DROP TABLE a;
+-- safety-assured:start
+DROP TABLE b;
+-- safety-assured:end
+DROP TABLE c;";
+
+    let lines: Vec<usize> = checker
+        .check_sql(sql)
+        .unwrap()
+        .iter()
+        .map(|(line, _)| *line)
+        .collect();
+    assert_eq!(lines, [1, 5]);
+}
+
 #[test]
 fn test_custom_check_in_migration_directory() {
     let checks_dir = tempdir().expect("Failed to create temp dir");
@@ -370,6 +415,34 @@ fn has_violation_containing(violations: &ViolationList, substring: &str) -> bool
         .any(|(_, v)| v.operation.contains(substring))
 }
 
+// -- concurrent_index_alone.rhai --
+
+#[test]
+fn test_example_concurrent_index_alone_detects() {
+    let violations = check_with_examples(
+        "ALTER TABLE users ADD COLUMN email TEXT;
+CREATE INDEX CONCURRENTLY idx_users_email ON users(email);",
+    );
+    let found: Vec<_> = violations
+        .iter()
+        .filter(|(_, v)| {
+            v.operation
+                .starts_with("INDEX CONCURRENTLY mixed with other statements")
+        })
+        .map(|(line, _)| *line)
+        .collect();
+    assert_eq!(found, [2]);
+}
+
+#[test]
+fn test_example_concurrent_index_alone_allows() {
+    let violations = check_with_examples("CREATE INDEX CONCURRENTLY idx ON users(email);");
+    assert!(
+        !has_violation_containing(&violations, "mixed with other statements"),
+        "A lone CREATE INDEX CONCURRENTLY should not trigger violation"
+    );
+}
+
 // -- require_concurrent_index.rhai --
 
 #[test]
@@ -0,0 +1,146 @@
+use assert_cmd::Command;
+use camino::Utf8Path;
+use diesel_guard::{Config, SafetyChecker};
+use std::fs;
+use tempfile::tempdir;
+
+const MIGRATION: &str = "-- migrate:up
+CREATE TABLE notes (id BIGINT PRIMARY KEY);
+CREATE INDEX idx_users_email ON users(email);
+
+-- migrate:down
+DROP INDEX idx_users_email;
+DROP TABLE notes;
+";
+
+fn checker(check_down: bool) -> SafetyChecker {
+    SafetyChecker::with_config(Config {
+        framework: "dbmate".to_string(),
+        check_down,
+        enable_checks: vec![
+            "AddIndexCheck".to_string(),
+            "DropIndexCheck".to_string(),
+            "DropTableCheck".to_string(),
+        ],
+        ..Default::default()
+    })
+    .unwrap()
+}
+
+fn write_migration(dir: &Utf8Path) {
+    fs::write(dir.join("20240101120000_add_index.sql"), MIGRATION).unwrap();
+}
+
+#[test]
+fn test_only_up_section_is_checked_by_default() {
+    let temp_dir = tempdir().expect("Failed to create temp dir");
+    let dir = Utf8Path::from_path(temp_dir.path()).unwrap();
+    write_migration(dir);
+
+    let results = checker(false).check_directory(dir).unwrap();
+
+    assert_eq!(results.len(), 1);
+    let lines: Vec<_> = results[0]
+        .1
+        .iter()
+        .map(|(line, v)| (*line, v.check_name.as_str()))
+        .collect();
+    assert_eq!(lines, [(3, "AddIndexCheck")]);
+}
+
+#[test]
+fn test_check_down_reports_down_section_with_file_line_numbers() {
+    let temp_dir = tempdir().expect("Failed to create temp dir");
+    let dir = Utf8Path::from_path(temp_dir.path()).unwrap();
+    write_migration(dir);
+
+    let results = checker(true).check_directory(dir).unwrap();
+
+    let lines: Vec<_> = results
+        .iter()
+        .flat_map(|(_, violations)| violations)
+        .map(|(line, v)| (*line, v.check_name.as_str()))
+        .collect();
+    assert_eq!(
+        lines,
+        [
+            (3, "AddIndexCheck"),
+            (6, "DropIndexCheck"),
+            (7, "DropTableCheck")
+        ]
+    );
+}
+
+#[test]
+fn test_fix_adds_transaction_option_to_up_marker() {
+    let temp_dir = tempdir().expect("Failed to create temp dir");
+    let dir = Utf8Path::from_path(temp_dir.path()).unwrap();
+    fs::create_dir(dir.join("db")).unwrap();
+    write_migration(&dir.join("db"));
+    fs::write(
+        dir.join("diesel-guard.toml"),
+        "framework = \"dbmate\"\ncheck_down = true\nenable_checks = [\"AddIndexCheck\", \"DropIndexCheck\"]\n",
+    )
+    .unwrap();
+
+    Command::cargo_bin("diesel-guard")
+        .unwrap()
+        .args(["fix", "db"])
+        .current_dir(dir)
+        .assert()
+        .success();
+
+    // The down section is left alone: the fix only rewrites the up section
+    assert_eq!(
+        fs::read_to_string(dir.join("db/20240101120000_add_index.sql")).unwrap(),
+        "-- migrate:up transaction:false
+CREATE TABLE notes (id BIGINT PRIMARY KEY);
+CREATE INDEX CONCURRENTLY idx_users_email ON users(email);
+
+-- migrate:down
+DROP INDEX idx_users_email;
+DROP TABLE notes;
+"
+    );
+}
+
+#[test]
+fn test_fix_rewrites_last_up_statement_not_down_section() {
+    let temp_dir = tempdir().expect("Failed to create temp dir");
+    let dir = Utf8Path::from_path(temp_dir.path()).unwrap();
+    fs::create_dir(dir.join("db")).unwrap();
+    fs::write(
+        dir.join("db/20240101120000_add_fk.sql"),
+        "-- migrate:up transaction:false
+ALTER TABLE posts ADD CONSTRAINT fk_posts_user FOREIGN KEY (user_id) REFERENCES users(id);
+
+-- migrate:down
+ALTER TABLE posts DROP CONSTRAINT fk_posts_user;
+",
+    )
+    .unwrap();
+    fs::write(
+        dir.join("diesel-guard.toml"),
+        "framework = \"dbmate\"\nenable_checks = [\"AddForeignKeyCheck\"]\n",
+    )
+    .unwrap();
+
+    Command::cargo_bin("diesel-guard")
+        .unwrap()
+        .args(["fix", "db"])
+        .current_dir(dir)
+        .assert()
+        .success();
+
+    // Both the NOT VALID and the VALIDATE land in the up section
+    assert_eq!(
+        fs::read_to_string(dir.join("db/20240101120000_add_fk.sql")).unwrap(),
+        "-- migrate:up transaction:false
+ALTER TABLE posts ADD CONSTRAINT fk_posts_user FOREIGN KEY (user_id) REFERENCES users(id) NOT VALID;
+ALTER TABLE posts VALIDATE CONSTRAINT fk_posts_user;
+
+-- migrate:down
+ALTER TABLE posts DROP CONSTRAINT fk_posts_user;
+"
+    );
+}
@@ -0,0 +1,246 @@
+use assert_cmd::Command;
+use std::fs;
+use std::path::Path;
+use tempfile::tempdir;
+
+fn write_diesel_migration(root: &Path, sql: &str) -> std::path::PathBuf {
+    let migration_dir = root.join("migrations/2024_01_01_000000_add_index");
+    fs::create_dir_all(&migration_dir).unwrap();
+    fs::write(migration_dir.join("up.sql"), sql).unwrap();
+    migration_dir
+}
+
+#[test]
+fn test_fix_dry_run_prints_diff_without_writing() {
+    let temp_dir = tempdir().expect("Failed to create temp dir");
+    let sql = "CREATE INDEX idx_users_email ON users(email);\n";
+    let migration_dir = write_diesel_migration(temp_dir.path(), sql);
+
+    let output = Command::cargo_bin("diesel-guard")
+        .unwrap()
+        .args(["fix", "--dry-run"])
+        .current_dir(temp_dir.path())
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("-CREATE INDEX idx_users_email ON users(email);"));
+    assert!(
+        stdout
+            .contains("+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_users_email ON users(email);")
+    );
+    assert!(stdout.contains("+++ migrations/2024_01_01_000000_add_index/metadata.toml"));
+
+    assert_eq!(
+        fs::read_to_string(migration_dir.join("up.sql")).unwrap(),
+        sql
+    );
+    assert!(!migration_dir.join("metadata.toml").exists());
+}
+
+#[test]
+fn test_fix_rewrites_diesel_migration_and_metadata() {
+    let temp_dir = tempdir().expect("Failed to create temp dir");
+    let migration_dir = write_diesel_migration(
+        temp_dir.path(),
+        "CREATE INDEX IF NOT EXISTS idx_users_email ON users(email);\n",
+    );
+    fs::write(
+        migration_dir.join("metadata.toml"),
+        "disable_checks = [\"AddColumnCheck\"]\n",
+    )
+    .unwrap();
+
+    Command::cargo_bin("diesel-guard")
+        .unwrap()
+        .arg("fix")
+        .current_dir(temp_dir.path())
+        .assert()
+        .success();
+
+    assert_eq!(
+        fs::read_to_string(migration_dir.join("up.sql")).unwrap(),
+        "CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_users_email ON users(email);\n"
+    );
+    assert_eq!(
+        fs::read_to_string(migration_dir.join("metadata.toml")).unwrap(),
+        "run_in_transaction = false\ndisable_checks = [\"AddColumnCheck\"]\n"
+    );
+
+    // The fixed migration passes the checks that were fixed
+    Command::cargo_bin("diesel-guard")
+        .unwrap()
+        .arg("check")
+        .current_dir(temp_dir.path())
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_fix_sqlx_adds_directive_and_prints_follow_up_validate() {
+    let temp_dir = tempdir().expect("Failed to create temp dir");
+    fs::write(
+        temp_dir.path().join("diesel-guard.toml"),
+        "framework = \"sqlx\"\n",
+    )
+    .unwrap();
+    let migrations = temp_dir.path().join("migrations");
+    fs::create_dir(&migrations).unwrap();
+    let index_file = migrations.join("20240101000000_add_index.sql");
+    let fk_file = migrations.join("20240102000000_add_fk.sql");
+    fs::write(&index_file, "DROP INDEX IF EXISTS idx_users_email;\n").unwrap();
+    fs::write(
+        &fk_file,
+        "ALTER TABLE posts ADD CONSTRAINT fk_posts_user FOREIGN KEY (user_id) REFERENCES users(id);\n",
+    )
+    .unwrap();
+
+    let output = Command::cargo_bin("diesel-guard")
+        .unwrap()
+        .arg("fix")
+        .current_dir(temp_dir.path())
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("ALTER TABLE posts VALIDATE CONSTRAINT fk_posts_user;"));
+
+    assert_eq!(
+        fs::read_to_string(&index_file).unwrap(),
+        "-- no-transaction\nDROP INDEX CONCURRENTLY IF EXISTS idx_users_email;\n"
+    );
+    assert_eq!(
+        fs::read_to_string(&fk_file).unwrap(),
+        "ALTER TABLE posts ADD CONSTRAINT fk_posts_user FOREIGN KEY (user_id) REFERENCES users(id) NOT VALID;\n"
+    );
+}
+
+#[test]
+fn test_fix_applies_machine_applicable_fixes_from_custom_checks() {
+    let temp_dir = tempdir().expect("Failed to create temp dir");
+    let checks_dir = temp_dir.path().join("checks");
+    fs::create_dir(&checks_dir).unwrap();
+    fs::write(
+        checks_dir.join("qualify_table.rhai"),
+        r#"
+let rel = node.AlterTableStmt?.relation ?? return;
+if rel.schemaname == "" {
+    #{
+        operation: "unqualified table",
+        problem: "p",
+        safe_alternative: "s",
+        fix: #{
+            applicability: "machine_applicable",
+            edits: [#{ start: rel.location, end: rel.location, replacement: "public." }]
+        }
+    }
+}
+"#,
+    )
+    .unwrap();
+    fs::write(
+        temp_dir.path().join("diesel-guard.toml"),
+        "framework = \"diesel\"\nenable_checks = [\"qualify_table\"]\ncustom_checks_dir = \"checks\"\n",
+    )
+    .unwrap();
+    let migration_dir = write_diesel_migration(
+        temp_dir.path(),
+        "ALTER TABLE users ADD COLUMN a INT;\n\n  ALTER TABLE posts ADD COLUMN b INT;\n",
+    );
+
+    Command::cargo_bin("diesel-guard")
+        .unwrap()
+        .arg("fix")
+        .current_dir(temp_dir.path())
+        .assert()
+        .success();
+
+    assert_eq!(
+        fs::read_to_string(migration_dir.join("up.sql")).unwrap(),
+        "ALTER TABLE public.users ADD COLUMN a INT;\n\n  ALTER TABLE public.posts ADD COLUMN b INT;\n"
+    );
+}
+
+#[test]
+fn test_fix_rejects_custom_edit_inside_multibyte_character() {
+    let temp_dir = tempdir().expect("Failed to create temp dir");
+    let checks_dir = temp_dir.path().join("checks");
+    fs::create_dir(&checks_dir).unwrap();
+    // Counting characters instead of bytes lands inside the two-byte "é"
+    fs::write(
+        checks_dir.join("rename_table.rhai"),
+        r#"
+let rel = node.AlterTableStmt?.relation ?? return;
+#{
+    operation: "rename table",
+    problem: "p",
+    safe_alternative: "s",
+    fix: #{
+        applicability: "machine_applicable",
+        edits: [#{ start: rel.location + 2, end: rel.location + 3, replacement: "e" }]
+    }
+}
+"#,
+    )
+    .unwrap();
+    fs::write(
+        temp_dir.path().join("diesel-guard.toml"),
+        "framework = \"diesel\"\nenable_checks = [\"rename_table\"]\ncustom_checks_dir = \"checks\"\n",
+    )
+    .unwrap();
+    let sql = "ALTER TABLE \"café\" ADD COLUMN a INT;\n";
+    let migration_dir = write_diesel_migration(temp_dir.path(), sql);
+
+    let output = Command::cargo_bin("diesel-guard")
+        .unwrap()
+        .arg("fix")
+        .current_dir(temp_dir.path())
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(
+        String::from_utf8(output.stdout)
+            .unwrap()
+            .contains("No fixable violations found.")
+    );
+    assert_eq!(
+        fs::read_to_string(migration_dir.join("up.sql")).unwrap(),
+        sql
+    );
+
+    let output = Command::cargo_bin("diesel-guard")
+        .unwrap()
+        .arg("check")
+        .current_dir(temp_dir.path())
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("SCRIPT ERROR: rename_table"));
+    assert!(stdout.contains("splits a multi-byte character"));
+}
+
+#[test]
+fn test_fix_reports_nothing_to_fix() {
+    let temp_dir = tempdir().expect("Failed to create temp dir");
+    write_diesel_migration(temp_dir.path(), "SELECT 1;\n");
+
+    Command::cargo_bin("diesel-guard")
+        .unwrap()
+        .arg("fix")
+        .current_dir(temp_dir.path())
+        .assert()
+        .success()
+        .stdout("No fixable violations found.\n");
+}
+
+#[test]
+fn test_fix_rejects_stdin() {
+    Command::cargo_bin("diesel-guard")
+        .unwrap()
+        .args(["fix", "-"])
+        .assert()
+        .failure();
+}
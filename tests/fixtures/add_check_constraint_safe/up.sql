@@ -2,5 +2,4 @@
 -- Step 1 (no table scans; lock acquired momentarily)
 ALTER TABLE orders ADD CONSTRAINT check_amount CHECK (amount > 0) NOT VALID;
 
--- Step 2 (separate migration, acquires ShareUpdateExclusiveLock only)
-ALTER TABLE orders VALIDATE CONSTRAINT check_amount;
+-- Step 2 goes in a separate migration (see validate_in_same_transaction_safe)
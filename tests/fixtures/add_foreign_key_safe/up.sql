@@ -3,5 +3,4 @@
 ALTER TABLE orders ADD CONSTRAINT fk_user_id
     FOREIGN KEY (user_id) REFERENCES users(id) NOT VALID;
 
--- Step 2 (separate migration, acquires ShareUpdateExclusiveLock only)
-ALTER TABLE orders VALIDATE CONSTRAINT fk_user_id;
+-- Step 2 goes in a separate migration (see validate_in_same_transaction_safe)
@@ -0,0 +1,11 @@
+-- Safe: Index, constrain, and tighten a table created in the same migration
+CREATE TABLE posts (
+    id BIGINT PRIMARY KEY,
+    user_id BIGINT,
+    slug TEXT
+);
+
+CREATE INDEX idx_posts_user_id ON posts(user_id);
+ALTER TABLE posts ADD CONSTRAINT fk_posts_user_id FOREIGN KEY (user_id) REFERENCES users(id);
+ALTER TABLE posts ADD CONSTRAINT posts_slug_key UNIQUE (slug);
+ALTER TABLE posts ALTER COLUMN slug SET NOT NULL;
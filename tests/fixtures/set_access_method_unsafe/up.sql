@@ -0,0 +1,2 @@
+-- Unsafe: Change the access method of an existing table
+ALTER TABLE events SET ACCESS METHOD columnar;
@@ -0,0 +1,3 @@
+-- Safe: Change persistence of a table created in the same migration
+CREATE UNLOGGED TABLE import_rows (id BIGINT PRIMARY KEY, payload TEXT);
+ALTER TABLE import_rows SET LOGGED;
@@ -0,0 +1,2 @@
+-- Unsafe: Make an existing table unlogged
+ALTER TABLE events SET UNLOGGED;
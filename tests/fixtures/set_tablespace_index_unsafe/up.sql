@@ -0,0 +1,2 @@
+-- Unsafe: Move an index to another tablespace
+ALTER INDEX idx_events_created_at SET TABLESPACE archive;
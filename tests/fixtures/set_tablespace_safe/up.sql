@@ -0,0 +1,2 @@
+-- Safe: Rebuild the index concurrently in the new tablespace
+REINDEX (TABLESPACE archive) INDEX CONCURRENTLY idx_events_created_at;
@@ -0,0 +1,2 @@
+-- Unsafe: Move a table to another tablespace
+ALTER TABLE events SET TABLESPACE archive;
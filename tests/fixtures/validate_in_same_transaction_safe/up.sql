@@ -0,0 +1,3 @@
+-- Safe: Validate a NOT VALID constraint in its own migration
+-- Step 2 (separate migration, acquires ShareUpdateExclusiveLock only)
+ALTER TABLE orders VALIDATE CONSTRAINT fk_user_id;
@@ -0,0 +1,5 @@
+-- Unsafe: Validating a NOT VALID constraint in the same transaction
+ALTER TABLE orders ADD CONSTRAINT fk_user_id
+    FOREIGN KEY (user_id) REFERENCES users(id) NOT VALID;
+
+ALTER TABLE orders VALIDATE CONSTRAINT fk_user_id;
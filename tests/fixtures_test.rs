@@ -72,6 +72,15 @@ fn test_safe_fixtures_pass() {
         ),
         ("char_type_safe", vec!["CharTypeCheck"]),
         ("create_table_serial_safe", vec!["CreateTableSerialCheck"]),
+        (
+            "create_table_then_lock_safe",
+            vec![
+                "AddIndexCheck",
+                "AddForeignKeyCheck",
+                "AddUniqueConstraintCheck",
+                "AddNotNullCheck",
+            ],
+        ),
         (
             "create_table_without_pk_safe",
             vec!["CreateTableWithoutPkCheck"],
@@ -89,6 +98,11 @@ fn test_safe_fixtures_pass() {
         ("refresh_matview_safe", vec!["RefreshMatViewCheck"]),
         ("reindex_safe", vec!["ReindexCheck"]),
         ("safety_assured_drop", vec!["DropColumnCheck"]),
+        ("set_logged_safe", vec!["SetLoggedCheck"]),
+        (
+            "set_tablespace_safe",
+            vec!["SetTablespaceCheck", "ReindexCheck"],
+        ),
         (
             "safety_assured_multiple",
             vec!["AddIndexCheck", "DropColumnCheck"],
@@ -99,6 +113,10 @@ fn test_safe_fixtures_pass() {
         ),
         ("timestamp_type_safe", vec!["TimestampTypeCheck"]),
         ("unnamed_constraint_safe", vec!["UnnamedConstraintCheck"]),
+        (
+            "validate_in_same_transaction_safe",
+            vec!["ValidateInSameTransactionCheck"],
+        ),
         ("wide_index_safe", vec!["WideIndexCheck"]),
     ];
 
@@ -675,6 +693,56 @@ fn test_update_without_where_detected() {
     assert_eq!(violations[0].1.operation, "UPDATE without WHERE");
 }
 
+#[test]
+fn test_set_logged_detected() {
+    let checker = checker_with_enabled_checks(&["SetLoggedCheck"]);
+    let path = fixture_path("set_logged_unsafe");
+
+    let violations = checker.check_file(Utf8Path::new(&path)).unwrap();
+
+    assert_eq!(violations.len(), 1, "Expected 1 violation");
+    assert_eq!(violations[0].1.operation, "SET UNLOGGED");
+}
+
+#[test]
+fn test_set_tablespace_detected() {
+    let checker = checker_with_enabled_checks(&["SetTablespaceCheck"]);
+
+    for fixture in ["set_tablespace_unsafe", "set_tablespace_index_unsafe"] {
+        let path = fixture_path(fixture);
+        let violations = checker.check_file(Utf8Path::new(&path)).unwrap();
+
+        assert_eq!(violations.len(), 1, "Expected 1 violation in {fixture}");
+        assert_eq!(violations[0].1.operation, "SET TABLESPACE");
+    }
+}
+
+#[test]
+fn test_set_access_method_detected() {
+    let checker = checker_with_enabled_checks(&["SetAccessMethodCheck"]);
+    let path = fixture_path("set_access_method_unsafe");
+
+    let violations = checker.check_file(Utf8Path::new(&path)).unwrap();
+
+    assert_eq!(violations.len(), 1, "Expected 1 violation");
+    assert_eq!(violations[0].1.operation, "SET ACCESS METHOD");
+}
+
+#[test]
+fn test_validate_in_same_transaction_detected() {
+    let checker = checker_with_enabled_checks(&["ValidateInSameTransactionCheck"]);
+    let path = fixture_path("validate_in_same_transaction_unsafe");
+
+    let violations = checker.check_file(Utf8Path::new(&path)).unwrap();
+
+    assert_eq!(violations.len(), 1, "Expected 1 violation");
+    assert_eq!(violations[0].0, 5);
+    assert_eq!(
+        violations[0].1.operation,
+        "VALIDATE CONSTRAINT in the same transaction as NOT VALID"
+    );
+}
+
 #[test]
 fn test_check_entire_fixtures_directory() {
     let checker = checker_with_disabled_checks(IDEMPOTENCY_CHECKS);
@@ -686,14 +754,14 @@ fn test_check_entire_fixtures_directory() {
 
     assert_eq!(
         results.len(),
-        42,
-        "Expected violations in 42 files, got {}",
+        47,
+        "Expected violations in 47 files, got {}",
         results.len()
     );
 
     assert_eq!(
-        total_violations, 57,
-        "Expected 57 total violations: 38 files with 1 each, drop_multiple_columns with 2, unnamed_constraint_unsafe with 6, short_int_pk_unsafe with 6 (4 short int + 1 add pk + 1 no pk), add_identity_column_unsafe with 1, and drop_column_if_exists_unsafe with 1, got {total_violations}"
+        total_violations, 62,
+        "Expected 62 total violations: 43 files with 1 each, drop_multiple_columns with 2, unnamed_constraint_unsafe with 6, short_int_pk_unsafe with 6 (4 short int + 1 add pk + 1 no pk), add_identity_column_unsafe with 1, and drop_column_if_exists_unsafe with 1, got {total_violations}"
     );
 }
 
@@ -0,0 +1,68 @@
+use camino::Utf8Path;
+use diesel_guard::{Config, SafetyChecker};
+use std::fs;
+use tempfile::tempdir;
+
+fn checker(start_after: Option<&str>) -> SafetyChecker {
+    SafetyChecker::with_config(Config {
+        framework: "flyway".to_string(),
+        start_after: start_after.map(str::to_string),
+        enable_checks: vec!["AddIndexCheck".to_string()],
+        ..Default::default()
+    })
+    .unwrap()
+}
+
+fn file_names(results: &[(String, diesel_guard::ViolationList)]) -> Vec<&str> {
+    results
+        .iter()
+        .map(|(path, _)| Utf8Path::new(path).file_name().unwrap())
+        .collect()
+}
+
+#[test]
+fn test_versions_compare_numerically_and_repeatables_are_always_checked() {
+    let temp_dir = tempdir().expect("Failed to create temp dir");
+    let dir = Utf8Path::from_path(temp_dir.path()).unwrap();
+    fs::write(
+        dir.join("V1__users.sql"),
+        "CREATE TABLE users (id BIGINT PRIMARY KEY, email TEXT);\n",
+    )
+    .unwrap();
+    for name in ["V1_9__index.sql", "V1_10__index.sql", "R__index.sql"] {
+        fs::write(dir.join(name), "CREATE INDEX idx ON users(email);\n").unwrap();
+    }
+
+    let results = checker(None).check_directory(dir).unwrap();
+    assert_eq!(
+        file_names(&results),
+        ["V1_9__index.sql", "V1_10__index.sql", "R__index.sql"]
+    );
+
+    let results = checker(Some("1.9")).check_directory(dir).unwrap();
+    assert_eq!(file_names(&results), ["V1_10__index.sql", "R__index.sql"]);
+}
+
+#[test]
+fn test_conf_file_runs_migration_outside_transaction() {
+    let temp_dir = tempdir().expect("Failed to create temp dir");
+    let dir = Utf8Path::from_path(temp_dir.path()).unwrap();
+    let sql = "CREATE INDEX CONCURRENTLY idx ON users(email);\nCREATE INDEX CONCURRENTLY idx2 ON users(name);\nANALYZE users;\n";
+    fs::write(dir.join("V1__index.sql"), sql).unwrap();
+
+    let results = checker(None).check_directory(dir).unwrap();
+    assert_eq!(results.len(), 1);
+    assert!(
+        results[0]
+            .1
+            .iter()
+            .all(|(_, v)| v.problem.contains("transaction"))
+    );
+
+    fs::write(
+        dir.join("V1__index.sql.conf"),
+        "executeInTransaction=false\n",
+    )
+    .unwrap();
+    assert!(checker(None).check_directory(dir).unwrap().is_empty());
+}
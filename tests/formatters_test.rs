@@ -1,4 +1,7 @@
-use diesel_guard::formatters::{Formatter, GithubFormatter, JsonFormatter, TextFormatter};
+use diesel_guard::formatters::{
+    Formatter, GithubFormatter, GitlabFormatter, JsonFormatter, JunitFormatter, SarifFormatter,
+    TextFormatter,
+};
 use diesel_guard::{Config, SafetyChecker, Violation};
 
 fn only(check_name: &str) -> SafetyChecker {
@@ -417,6 +420,338 @@ fn test_format_json_end_to_end() {
     assert_eq!(actual, expected);
 }
 
+#[test]
+fn test_format_json_includes_fix() {
+    let sql = "SELECT 1;\nCREATE INDEX idx_users_email ON users(email);";
+    let violations = only("AddIndexCheck").check_sql(sql).unwrap();
+
+    let results = vec![("migrations/001/up.sql".to_string(), violations)];
+    let actual: serde_json::Value =
+        serde_json::from_str(&JsonFormatter.format_results(&results)).unwrap();
+
+    // check_sql runs in a transaction, so CONCURRENTLY alone is not enough
+    assert_eq!(
+        actual[0]["violations"][0]["fix"],
+        serde_json::json!({
+            "applicability": "maybe_incorrect",
+            "edits": [{"start": 22, "end": 22, "replacement": " CONCURRENTLY"}]
+        })
+    );
+}
+
+#[test]
+fn test_format_sarif_end_to_end() {
+    let config = Config {
+        enable_checks: vec!["AddIndexCheck".to_string(), "DropColumnCheck".to_string()],
+        warn_checks: vec!["DropColumnCheck".to_string()],
+        ..Config::default()
+    };
+    let checker = SafetyChecker::with_config(config.clone()).unwrap();
+    let sql =
+        "SELECT 1;\n  ALTER TABLE users DROP COLUMN email;\nCREATE INDEX idx ON users(email);";
+    let violations = checker.check_sql(sql).unwrap();
+    let checks: Vec<_> = checker.registry().iter_checks().collect();
+
+    let results = vec![("migrations/001/up.sql".to_string(), violations)];
+    let sarif: serde_json::Value =
+        serde_json::from_str(&SarifFormatter::new(&checks, &config).format_results(&results))
+            .unwrap();
+
+    assert_eq!(sarif["version"], "2.1.0");
+    let run = &sarif["runs"][0];
+    assert_eq!(run["tool"]["driver"]["name"], "diesel-guard");
+
+    let rules = run["tool"]["driver"]["rules"].as_array().unwrap();
+    assert_eq!(rules[0]["id"], "AddIndexCheck");
+    assert_eq!(rules[0]["shortDescription"]["text"], "Adding an Index");
+    assert!(
+        rules[0]["help"]["markdown"]
+            .as_str()
+            .unwrap()
+            .contains("CREATE INDEX CONCURRENTLY")
+    );
+    assert_eq!(rules[1]["defaultConfiguration"]["level"], "warning");
+
+    let results = run["results"].as_array().unwrap();
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0]["ruleId"], "DropColumnCheck");
+    assert_eq!(results[0]["ruleIndex"], 1);
+    assert_eq!(results[0]["level"], "warning");
+    assert_eq!(
+        results[0]["locations"][0]["physicalLocation"],
+        serde_json::json!({
+            "artifactLocation": { "uri": "migrations/001/up.sql", "uriBaseId": "%SRCROOT%" },
+            "region": { "startLine": 2, "startColumn": 3 }
+        })
+    );
+    assert!(
+        run["originalUriBaseIds"]["%SRCROOT%"]["uri"]
+            .as_str()
+            .unwrap()
+            .starts_with("file:///")
+    );
+    assert_eq!(results[1]["level"], "error");
+    assert_eq!(
+        results[1]["fixes"][0]["artifactChanges"][0]["replacements"][0],
+        serde_json::json!({
+            "deletedRegion": { "byteOffset": 61, "byteLength": 0 },
+            "insertedContent": { "text": " CONCURRENTLY" }
+        })
+    );
+}
+
+#[test]
+fn test_format_sarif_uris_are_encoded() {
+    let uri = |file: &str| -> serde_json::Value {
+        let violations = vec![(1usize, Violation::new("DROP TABLE", "p", "s"))];
+        let results = vec![(file.to_string(), violations)];
+        let sarif: serde_json::Value =
+            serde_json::from_str(&SarifFormatter::default().format_results(&results)).unwrap();
+        sarif["runs"][0]["results"][0]["locations"][0]["physicalLocation"]["artifactLocation"]
+            .clone()
+    };
+
+    assert_eq!(
+        uri("./migrations/2024 01/100%_up.sql"),
+        serde_json::json!({
+            "uri": "migrations/2024%2001/100%25_up.sql",
+            "uriBaseId": "%SRCROOT%"
+        })
+    );
+    // Tests run from the crate root, so /nonexistent is outside the repository
+    assert_eq!(
+        uri("/nonexistent/my migrations/up.sql"),
+        serde_json::json!({ "uri": "file:///nonexistent/my%20migrations/up.sql" })
+    );
+}
+
+#[test]
+fn test_format_sarif_region_without_column() {
+    let violations = vec![(3usize, Violation::new("DROP TABLE", "p", "s"))];
+    let results = vec![("up.sql".to_string(), violations)];
+    let sarif: serde_json::Value =
+        serde_json::from_str(&SarifFormatter::default().format_results(&results)).unwrap();
+
+    let result = &sarif["runs"][0]["results"][0];
+    assert_eq!(
+        result["locations"][0]["physicalLocation"]["region"],
+        serde_json::json!({ "startLine": 3 })
+    );
+    assert!(result.get("ruleIndex").is_none());
+}
+
+#[test]
+fn test_format_sarif_rules_include_custom_checks() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("no_truncate.rhai"),
+        r#"
+        fn describe() { "Flags TRUNCATE" }
+        let stmt = node.TruncateStmt ?? return;
+        #{ operation: "TRUNCATE", problem: "p", safe_alternative: "s" }
+        "#,
+    )
+    .unwrap();
+    let config = Config {
+        custom_checks_dir: Some(dir.path().to_str().unwrap().to_string()),
+        ..Config::default()
+    };
+    let checker = SafetyChecker::with_config(config.clone()).unwrap();
+    let checks: Vec<_> = checker.registry().iter_checks().collect();
+
+    let sarif: serde_json::Value =
+        serde_json::from_str(&SarifFormatter::default().format_checks(&checks, &config)).unwrap();
+    let rules = sarif["runs"][0]["tool"]["driver"]["rules"]
+        .as_array()
+        .unwrap();
+    assert_eq!(rules.len(), checks.len());
+    let custom = rules.iter().find(|r| r["id"] == "no_truncate").unwrap();
+    assert_eq!(custom["properties"]["type"], "custom");
+    assert_eq!(custom["help"]["text"], "Flags TRUNCATE");
+    assert!(sarif["runs"][0]["results"].as_array().unwrap().is_empty());
+}
+
+#[test]
+fn test_format_junit_end_to_end() {
+    let config = Config {
+        enable_checks: vec![
+            "AddIndexCheck".to_string(),
+            "DropColumnCheck".to_string(),
+            "TruncateTableCheck".to_string(),
+        ],
+        warn_checks: vec!["DropColumnCheck".to_string()],
+        ..Config::default()
+    };
+    let checker = SafetyChecker::with_config(config).unwrap();
+    let sql = "ALTER TABLE users DROP COLUMN email;\nCREATE INDEX a ON users(a);\nCREATE INDEX b ON users(b);";
+    let violations = checker.check_sql(sql).unwrap();
+    let checks: Vec<_> = checker.registry().iter_checks().collect();
+
+    let results = vec![("migrations/001_a&b/up.sql".to_string(), violations)];
+    let xml = JunitFormatter::new(&checks).format_results(&results);
+
+    assert!(xml.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n"));
+    assert!(xml.contains(
+        r#"<testsuites name="diesel-guard" tests="3" failures="1" errors="0" skipped="1">"#
+    ));
+    assert!(xml.contains(
+        r#"<testsuite name="migrations/001_a&amp;b/up.sql" tests="3" failures="1" errors="0" skipped="1">"#
+    ));
+    assert!(xml.contains(
+        r#"<failure message="2 violations (lines 2, 3)" type="AddIndexCheck">line 2: ADD INDEX without CONCURRENTLY"#
+    ));
+    assert!(xml.contains(r#"<skipped message="line 1: DROP COLUMN"/>"#));
+    assert!(xml.contains(
+        r#"<testcase name="TruncateTableCheck" classname="migrations/001_a&amp;b/up.sql"/>"#
+    ));
+    assert!(xml.ends_with("</testsuites>\n"));
+}
+
+#[test]
+fn test_format_junit_escapes_and_adds_unknown_checks() {
+    let violations = vec![(
+        4usize,
+        Violation::new("SCRIPT ERROR: <x>", "bad \"quote\"\u{1}", "s").with_check_name("my_check"),
+    )];
+    let results = vec![("up.sql".to_string(), violations)];
+    let xml = JunitFormatter::default().format_results(&results);
+
+    assert!(xml.contains(r#"<testcase name="my_check" classname="up.sql">"#));
+    assert!(xml.contains(r#"message="line 4: SCRIPT ERROR: &lt;x&gt;""#));
+    assert!(xml.contains("bad &quot;quote&quot;\n"));
+}
+
+#[test]
+fn test_format_junit_lists_checked_files_without_violations() {
+    let config = Config {
+        enable_checks: vec!["AddIndexCheck".to_string(), "DropColumnCheck".to_string()],
+        ..Config::default()
+    };
+    let checker = SafetyChecker::with_config(config).unwrap();
+    let violations = checker.check_sql("CREATE INDEX a ON users(a);").unwrap();
+    let checks: Vec<_> = checker.registry().iter_checks().collect();
+
+    let results = vec![("002_index.sql".to_string(), violations)];
+    let files = vec!["001_clean.sql".to_string(), "002_index.sql".to_string()];
+    let xml = JunitFormatter::new(&checks)
+        .with_files(files)
+        .format_results(&results);
+
+    assert!(xml.contains(
+        r#"<testsuites name="diesel-guard" tests="4" failures="1" errors="0" skipped="0">"#
+    ));
+    assert!(xml.contains(
+        r#"<testsuite name="001_clean.sql" tests="2" failures="0" errors="0" skipped="0">"#
+    ));
+    assert!(xml.contains(r#"<testcase name="AddIndexCheck" classname="001_clean.sql"/>"#));
+    assert!(xml.contains(r#"<testcase name="DropColumnCheck" classname="001_clean.sql"/>"#));
+    // Suites follow the order files were checked in
+    assert!(xml.find("001_clean.sql").unwrap() < xml.find("002_index.sql").unwrap());
+}
+
+#[test]
+fn test_format_junit_empty_results() {
+    let xml = JunitFormatter::default().format_results(&[]);
+    assert!(xml.contains(
+        r#"<testsuites name="diesel-guard" tests="0" failures="0" errors="0" skipped="0">"#
+    ));
+}
+
+fn gitlab_report(sql: &str) -> serde_json::Value {
+    let config = Config {
+        enable_checks: vec!["AddIndexCheck".to_string(), "DropColumnCheck".to_string()],
+        warn_checks: vec!["DropColumnCheck".to_string()],
+        ..Config::default()
+    };
+    let violations = SafetyChecker::with_config(config)
+        .unwrap()
+        .check_sql(sql)
+        .unwrap();
+    let results = vec![("./migrations/001/up.sql".to_string(), violations)];
+    serde_json::from_str(&GitlabFormatter.format_results(&results)).unwrap()
+}
+
+#[test]
+fn test_format_gitlab_code_quality_fields() {
+    let report = gitlab_report("ALTER TABLE users DROP COLUMN email;");
+    let issue = &report[0];
+    assert_eq!(issue["check_name"], "DropColumnCheck");
+    assert_eq!(issue["severity"], "minor");
+    assert!(
+        issue["description"]
+            .as_str()
+            .unwrap()
+            .starts_with("DROP COLUMN: Dropping column 'email'")
+    );
+    assert_eq!(
+        issue["location"],
+        serde_json::json!({ "path": "migrations/001/up.sql", "lines": { "begin": 1 } })
+    );
+    assert_eq!(issue["fingerprint"].as_str().unwrap().len(), 64);
+}
+
+#[test]
+fn test_format_gitlab_paths_are_relative_to_the_repository() {
+    let violation = || Violation::new("ADD INDEX", "p", "s").with_check_name("AddIndexCheck");
+    let report = |file: String| -> serde_json::Value {
+        let results = vec![(file, vec![(1, violation())])];
+        serde_json::from_str(&GitlabFormatter.format_results(&results)).unwrap()
+    };
+
+    // Tests run from the crate root
+    let absolute = std::env::current_dir()
+        .unwrap()
+        .join("migrations/001/up.sql");
+    let relative = report("migrations/001/up.sql".to_string());
+    let from_absolute = report(absolute.to_str().unwrap().to_string());
+    assert_eq!(
+        from_absolute[0]["location"]["path"],
+        "migrations/001/up.sql"
+    );
+    assert_eq!(relative[0]["fingerprint"], from_absolute[0]["fingerprint"]);
+}
+
+#[test]
+fn test_format_gitlab_fingerprint_ignores_line_moves() {
+    let before = gitlab_report("CREATE INDEX a ON users(a);\nCREATE INDEX a ON users(a);");
+    let after =
+        gitlab_report("SELECT 1;\n\nCREATE INDEX a ON users(a);\n\nCREATE INDEX a ON users(a);");
+
+    assert_eq!(before[0]["severity"], "major");
+    assert_eq!(after[0]["location"]["lines"]["begin"], 3);
+    assert_eq!(before[0]["fingerprint"], after[0]["fingerprint"]);
+    assert_eq!(before[1]["fingerprint"], after[1]["fingerprint"]);
+    // Identical violations still get distinct fingerprints
+    assert_ne!(before[0]["fingerprint"], before[1]["fingerprint"]);
+
+    let other = gitlab_report("CREATE INDEX b ON users(b);");
+    assert_ne!(before[0]["fingerprint"], other[0]["fingerprint"]);
+}
+
+#[test]
+fn test_format_gitlab_fingerprint_ignores_table_size() {
+    let sized = |size: &str| {
+        let mut v = Violation::new("ADD INDEX", "Duration depends on table size.", "s")
+            .with_check_name("AddIndexCheck");
+        v.table_size = Some(format!("Table 'orders' is {size}."));
+        v
+    };
+    let report = |v: Violation| -> serde_json::Value {
+        let results = vec![("up.sql".to_string(), vec![(1, v)])];
+        serde_json::from_str(&GitlabFormatter.format_results(&results)).unwrap()
+    };
+
+    let before = report(sized("~480M rows / 210 GB"));
+    let after = report(sized("~510M rows / 224 GB"));
+    assert_ne!(before[0]["description"], after[0]["description"]);
+    assert_eq!(before[0]["fingerprint"], after[0]["fingerprint"]);
+}
+
+#[test]
+fn test_format_gitlab_empty_results() {
+    assert_eq!(GitlabFormatter.format_results(&[]), "[]");
+}
+
 #[test]
 fn test_format_checks_text() {
     colored::control::set_override(false);
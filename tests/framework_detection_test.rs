@@ -0,0 +1,83 @@
+use assert_cmd::Command;
+use std::fs;
+use std::path::Path;
+use std::process::Output;
+use tempfile::tempdir;
+
+fn diesel_guard(dir: &Path, args: &[&str]) -> Output {
+    Command::cargo_bin("diesel-guard")
+        .unwrap()
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn test_sqlx_project_without_config_is_detected() {
+    let temp_dir = tempdir().expect("Failed to create temp dir");
+    fs::create_dir(temp_dir.path().join("migrations")).unwrap();
+    fs::write(
+        temp_dir.path().join("migrations/20240101000000_add_index.sql"),
+        "-- no-transaction\nCREATE INDEX CONCURRENTLY IF NOT EXISTS idx_users_email ON users(email);\n",
+    )
+    .unwrap();
+
+    let output = diesel_guard(temp_dir.path(), &["check"]);
+
+    // Under the Diesel adapter the file wouldn't be found at all; under SQLx
+    // the directive is honoured and the migration passes
+    assert!(output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert_eq!(
+        stderr,
+        "Detected framework \"sqlx\": migrations/20240101000000_add_index.sql is named like a SQLx migration\n"
+    );
+}
+
+#[test]
+fn test_shared_layout_is_settled_by_cargo_dependencies() {
+    let temp_dir = tempdir().expect("Failed to create temp dir");
+    fs::create_dir(temp_dir.path().join("migrations")).unwrap();
+    fs::write(
+        temp_dir.path().join("migrations/1_add_index.up.sql"),
+        "CREATE INDEX idx_users_email ON users(email);\n",
+    )
+    .unwrap();
+
+    let output = diesel_guard(temp_dir.path(), &["check"]);
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("Could not tell which migration framework this project uses"));
+    assert!(stderr.contains("\"sqlx\"") && stderr.contains("\"golang-migrate\""));
+
+    fs::write(
+        temp_dir.path().join("Cargo.toml"),
+        "[package]\nname = \"app\"\n\n[dependencies]\nsqlx = \"0.8\"\n",
+    )
+    .unwrap();
+    let output = diesel_guard(temp_dir.path(), &["check"]);
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.starts_with("Detected framework \"sqlx\""));
+    assert!(stderr.contains("./Cargo.toml depends on sqlx"));
+}
+
+#[test]
+fn test_configured_framework_skips_detection() {
+    let temp_dir = tempdir().expect("Failed to create temp dir");
+    fs::create_dir(temp_dir.path().join("migrations")).unwrap();
+    fs::write(
+        temp_dir.path().join("migrations/1_add_index.up.sql"),
+        "SELECT 1;\n",
+    )
+    .unwrap();
+    fs::write(
+        temp_dir.path().join("diesel-guard.toml"),
+        "framework = \"golang-migrate\"\n",
+    )
+    .unwrap();
+
+    let output = diesel_guard(temp_dir.path(), &["check"]);
+    assert!(output.status.success());
+    assert!(String::from_utf8(output.stderr).unwrap().is_empty());
+}
@@ -0,0 +1,50 @@
+use camino::Utf8Path;
+use diesel_guard::{Config, SafetyChecker};
+use std::fs;
+use tempfile::tempdir;
+
+fn checker(check_down: bool) -> SafetyChecker {
+    SafetyChecker::with_config(Config {
+        framework: "golang-migrate".to_string(),
+        check_down,
+        enable_checks: vec!["AddIndexCheck".to_string(), "DropIndexCheck".to_string()],
+        ..Default::default()
+    })
+    .unwrap()
+}
+
+#[test]
+fn test_concurrent_index_in_its_own_file_passes() {
+    let temp_dir = tempdir().expect("Failed to create temp dir");
+    let dir = Utf8Path::from_path(temp_dir.path()).unwrap();
+    fs::write(
+        dir.join("1_index.up.sql"),
+        "CREATE INDEX CONCURRENTLY idx ON users(email);\n",
+    )
+    .unwrap();
+    fs::write(
+        dir.join("2_index.up.sql"),
+        "CREATE INDEX CONCURRENTLY idx_name ON users(name);\nSELECT 1;\n",
+    )
+    .unwrap();
+
+    let results = checker(false).check_directory(dir).unwrap();
+
+    // Several statements run in an implicit transaction, where CONCURRENTLY fails
+    assert_eq!(results.len(), 1);
+    assert!(results[0].0.ends_with("2_index.up.sql"));
+}
+
+#[test]
+fn test_down_files_are_checked_with_check_down() {
+    let temp_dir = tempdir().expect("Failed to create temp dir");
+    let dir = Utf8Path::from_path(temp_dir.path()).unwrap();
+    fs::write(dir.join("1_index.up.sql"), "SELECT 1;\n").unwrap();
+    fs::write(dir.join("1_index.down.sql"), "DROP INDEX idx;\n").unwrap();
+
+    assert!(checker(false).check_directory(dir).unwrap().is_empty());
+
+    let results = checker(true).check_directory(dir).unwrap();
+    assert_eq!(results.len(), 1);
+    assert!(results[0].0.ends_with("1_index.down.sql"));
+}
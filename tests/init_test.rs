@@ -15,7 +15,7 @@ fn test_init_creates_config() {
         .success()
         .stdout(
             "✓ Created diesel-guard.toml\n\nNext steps:\n\
-             1. Edit diesel-guard.toml and set the 'framework' field to \"diesel\" or \"sqlx\"\n\
+             1. Edit diesel-guard.toml and set the 'framework' field to \"diesel\", \"sqlx\", \"refinery\", \"seaorm\", \"golang-migrate\", \"dbmate\" or \"flyway\"\n\
              2. Customize other configuration options as needed\n\
              3. Run 'diesel-guard check' to check your migrations\n",
         )
@@ -84,7 +84,7 @@ fn test_init_force_overwrites_existing() {
         .success()
         .stdout(
             "✓ Overwrote diesel-guard.toml\n\nNext steps:\n\
-             1. Edit diesel-guard.toml and set the 'framework' field to \"diesel\" or \"sqlx\"\n\
+             1. Edit diesel-guard.toml and set the 'framework' field to \"diesel\", \"sqlx\", \"refinery\", \"seaorm\", \"golang-migrate\", \"dbmate\" or \"flyway\"\n\
              2. Customize other configuration options as needed\n\
              3. Run 'diesel-guard check' to check your migrations\n",
         );
@@ -374,3 +374,50 @@ fn test_explain_json_warn_check_has_severity_warning() {
     let json: serde_json::Value = serde_json::from_str(&stdout).unwrap();
     assert_eq!(json["severity"], "warning");
 }
+
+#[test]
+fn test_explain_lists_check_options_with_configured_values() {
+    let tempdir = tempfile::tempdir().unwrap();
+    fs::write(
+        tempdir.path().join("diesel-guard.toml"),
+        "framework = \"diesel\"\n\n[checks.WideIndexCheck]\nmax_columns = 5\n",
+    )
+    .unwrap();
+
+    let output = Command::cargo_bin("diesel-guard")
+        .unwrap()
+        .args(["explain", "WideIndexCheck"])
+        .current_dir(tempdir.path())
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("Options ([checks.WideIndexCheck] in diesel-guard.toml):"));
+    assert!(stdout.contains("  max_columns (integer) = 5\n"));
+    assert!(stdout.contains("  allowed_tables (list of strings)\n"));
+}
+
+#[test]
+fn test_check_uses_check_options() {
+    let tempdir = tempfile::tempdir().unwrap();
+    fs::write(
+        tempdir.path().join("diesel-guard.toml"),
+        "framework = \"diesel\"\nenable_checks = [\"WideIndexCheck\"]\n\n[checks.WideIndexCheck]\nmax_columns = 4\n",
+    )
+    .unwrap();
+    let migration = tempdir.path().join("migrations/2024_01_01_000000_index");
+    fs::create_dir_all(&migration).unwrap();
+    fs::write(
+        migration.join("up.sql"),
+        "CREATE INDEX idx ON users(a, b, c, d);\n",
+    )
+    .unwrap();
+
+    Command::cargo_bin("diesel-guard")
+        .unwrap()
+        .arg("check")
+        .current_dir(tempdir.path())
+        .assert()
+        .success();
+}
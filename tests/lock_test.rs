@@ -0,0 +1,159 @@
+use assert_cmd::Command;
+use std::fs;
+use std::path::Path;
+use tempfile::tempdir;
+
+fn write_migration(root: &Path, name: &str, sql: &str) {
+    let dir = root.join("migrations").join(name);
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("up.sql"), sql).unwrap();
+}
+
+/// Two safe migrations; `start_after` skips the first one for regular checks.
+fn setup(root: &Path) {
+    fs::write(
+        root.join("diesel-guard.toml"),
+        "framework = \"diesel\"\nstart_after = \"2024_01_01_000000\"\nenable_checks = [\"AddIndexCheck\"]\n",
+    )
+    .unwrap();
+    write_migration(
+        root,
+        "2024_01_01_000000_create_users",
+        "CREATE TABLE users (id INT);\n",
+    );
+    write_migration(
+        root,
+        "2024_02_01_000000_create_posts",
+        "CREATE TABLE posts (id INT);\n",
+    );
+}
+
+fn diesel_guard(root: &Path, args: &[&str]) -> std::process::Output {
+    Command::cargo_bin("diesel-guard")
+        .unwrap()
+        .args(args)
+        .current_dir(root)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn test_lock_then_check_passes_when_nothing_changed() {
+    let temp_dir = tempdir().expect("Failed to create temp dir");
+    let root = temp_dir.path();
+    setup(root);
+
+    let output = diesel_guard(root, &["lock"]);
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "✓ Locked 2 migrations in diesel-guard.lock\n"
+    );
+
+    let lock = fs::read_to_string(root.join("diesel-guard.lock")).unwrap();
+    assert!(lock.contains("\"timestamp\": \"20240101000000\""));
+    assert!(lock.contains("migrations/2024_01_01_000000_create_users/up.sql"));
+
+    assert!(diesel_guard(root, &["check"]).status.success());
+}
+
+#[test]
+fn test_check_reports_edited_migration_before_start_after() {
+    let temp_dir = tempdir().expect("Failed to create temp dir");
+    let root = temp_dir.path();
+    setup(root);
+    assert!(diesel_guard(root, &["lock"]).status.success());
+
+    fs::write(
+        root.join("migrations/2024_01_01_000000_create_users/up.sql"),
+        "CREATE TABLE users (id BIGINT);\n",
+    )
+    .unwrap();
+    // A new migration is not an edit
+    write_migration(root, "2024_03_01_000000_add_col", "SELECT 1;\n");
+
+    let output = diesel_guard(root, &["check", "--format", "json"]);
+    assert!(!output.status.success());
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let results = json.as_array().unwrap();
+    assert_eq!(results.len(), 1);
+    assert!(
+        results[0]["file"]
+            .as_str()
+            .unwrap()
+            .contains("2024_01_01_000000_create_users")
+    );
+    assert_eq!(
+        results[0]["violations"][0]["check_name"],
+        "ModifiedMigration"
+    );
+}
+
+#[test]
+fn test_check_follows_renamed_migration_directory() {
+    let temp_dir = tempdir().expect("Failed to create temp dir");
+    let root = temp_dir.path();
+    setup(root);
+    assert!(diesel_guard(root, &["lock"]).status.success());
+
+    fs::rename(
+        root.join("migrations/2024_02_01_000000_create_posts"),
+        root.join("migrations/2024_02_01_000000_posts"),
+    )
+    .unwrap();
+    assert!(diesel_guard(root, &["check"]).status.success());
+
+    fs::write(
+        root.join("migrations/2024_02_01_000000_posts/up.sql"),
+        "CREATE TABLE posts (id BIGINT);\n",
+    )
+    .unwrap();
+    assert!(!diesel_guard(root, &["check"]).status.success());
+}
+
+#[test]
+fn test_check_shipped_at_git_ref() {
+    let temp_dir = tempdir().expect("Failed to create temp dir");
+    let root = temp_dir.path();
+    setup(root);
+    let git = |args: &[&str]| {
+        let output = std::process::Command::new("git")
+            .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+            .args(args)
+            .current_dir(root)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "git {args:?} failed: {output:?}");
+    };
+    git(&["init", "-q", "-b", "main"]);
+    git(&["add", "-A"]);
+    git(&["commit", "-q", "-m", "initial"]);
+
+    assert!(
+        diesel_guard(root, &["check", "--shipped-at", "main"])
+            .status
+            .success()
+    );
+
+    fs::write(
+        root.join("migrations/2024_01_01_000000_create_users/up.sql"),
+        "CREATE TABLE users (id BIGINT);\n",
+    )
+    .unwrap();
+    let output = diesel_guard(root, &["check", "--shipped-at", "main"]);
+    assert!(!output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("git ref 'main'"));
+}
+
+#[test]
+fn test_check_rejects_invalid_lock_file() {
+    let temp_dir = tempdir().expect("Failed to create temp dir");
+    let root = temp_dir.path();
+    setup(root);
+    fs::write(root.join("diesel-guard.lock"), "not json").unwrap();
+
+    let output = diesel_guard(root, &["check"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Invalid lock file"));
+}
@@ -0,0 +1,94 @@
+use assert_cmd::Command;
+use std::fs;
+use std::path::Path;
+use std::process::Output;
+use tempfile::tempdir;
+
+const CONFIG: &str = r#"framework = "diesel"
+
+[[migrations]]
+path = "migrations"
+
+[[migrations]]
+path = "services/billing/migrations"
+framework = "sqlx"
+warn_checks = ["AddIndexCheck"]
+"#;
+
+fn diesel_guard(dir: &Path, args: &[&str]) -> Output {
+    Command::cargo_bin("diesel-guard")
+        .unwrap()
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .unwrap()
+}
+
+fn write_project(dir: &Path) {
+    fs::write(dir.join("diesel-guard.toml"), CONFIG).unwrap();
+    let diesel = dir.join("migrations/2024_01_01_000000_add_index");
+    fs::create_dir_all(&diesel).unwrap();
+    fs::write(
+        diesel.join("up.sql"),
+        "CREATE INDEX IF NOT EXISTS idx_users_email ON users(email);\n",
+    )
+    .unwrap();
+    let sqlx = dir.join("services/billing/migrations");
+    fs::create_dir_all(&sqlx).unwrap();
+    fs::write(
+        sqlx.join("20240101000000_add_index.sql"),
+        "CREATE INDEX IF NOT EXISTS idx_invoices_user ON invoices(user_id);\n",
+    )
+    .unwrap();
+}
+
+#[test]
+fn test_check_without_path_reports_every_root() {
+    let temp_dir = tempdir().expect("Failed to create temp dir");
+    write_project(temp_dir.path());
+
+    let output = diesel_guard(temp_dir.path(), &["check", "--format", "json"]);
+
+    assert_eq!(output.status.code(), Some(1));
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let files: Vec<(String, String)> = report
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|file| {
+            (
+                file["file"].as_str().unwrap().to_string(),
+                file["violations"][0]["severity"]
+                    .as_str()
+                    .unwrap()
+                    .to_string(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        files,
+        [
+            (
+                "migrations/2024_01_01_000000_add_index/up.sql".to_string(),
+                "error".to_string()
+            ),
+            (
+                "services/billing/migrations/20240101000000_add_index.sql".to_string(),
+                "warning".to_string()
+            ),
+        ]
+    );
+}
+
+#[test]
+fn test_explicit_path_uses_its_root_settings() {
+    let temp_dir = tempdir().expect("Failed to create temp dir");
+    write_project(temp_dir.path());
+
+    // The billing root runs under SQLx and only warns about indexes
+    let output = diesel_guard(temp_dir.path(), &["check", "services/billing/migrations"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("20240101000000_add_index.sql"));
+    assert!(!stdout.contains("up.sql"));
+}
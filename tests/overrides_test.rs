@@ -0,0 +1,83 @@
+use assert_cmd::Command;
+use std::fs;
+use std::path::Path;
+use tempfile::tempdir;
+
+const CONFIG: &str = r#"framework = "diesel"
+disable_checks = ["IdempotencyIndexCheck"]
+
+[[overrides]]
+files = ["migrations/*_seed_*"]
+disable_checks = ["MutationWithoutWhereCheck", "IdempotencyIndexCheck"]
+
+[[overrides]]
+files = ["*_add_index"]
+warn_checks = ["AddIndexCheck"]
+"#;
+
+fn write_migration(dir: &Path, name: &str, sql: &str) {
+    let migration = dir.join("migrations").join(name);
+    fs::create_dir_all(&migration).unwrap();
+    fs::write(migration.join("up.sql"), sql).unwrap();
+}
+
+#[test]
+fn test_overrides_apply_per_migration() {
+    let temp_dir = tempdir().expect("Failed to create temp dir");
+    let dir = temp_dir.path();
+    fs::write(dir.join("diesel-guard.toml"), CONFIG).unwrap();
+    write_migration(
+        dir,
+        "2024_01_01_000000_seed_plans",
+        "UPDATE plans SET active = true;\n",
+    );
+    write_migration(
+        dir,
+        "2024_01_02_000000_reset_plans",
+        "UPDATE plans SET active = false;\n",
+    );
+    write_migration(
+        dir,
+        "2024_01_03_000000_add_index",
+        "CREATE INDEX idx_plans_name ON plans(name);\n",
+    );
+
+    let output = Command::cargo_bin("diesel-guard")
+        .unwrap()
+        .args(["check", "--format", "json"])
+        .current_dir(dir)
+        .output()
+        .unwrap();
+
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let violations: Vec<(&str, &str, &str)> = report
+        .as_array()
+        .unwrap()
+        .iter()
+        .flat_map(|file| {
+            let path = file["file"].as_str().unwrap();
+            file["violations"].as_array().unwrap().iter().map(move |v| {
+                (
+                    path,
+                    v["check_name"].as_str().unwrap(),
+                    v["severity"].as_str().unwrap(),
+                )
+            })
+        })
+        .collect();
+    assert_eq!(
+        violations,
+        [
+            (
+                "migrations/2024_01_02_000000_reset_plans/up.sql",
+                "MutationWithoutWhereCheck",
+                "error"
+            ),
+            (
+                "migrations/2024_01_03_000000_add_index/up.sql",
+                "AddIndexCheck",
+                "warning"
+            ),
+        ]
+    );
+}
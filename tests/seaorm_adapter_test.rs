@@ -0,0 +1,100 @@
+use assert_cmd::Command;
+use camino::Utf8Path;
+use diesel_guard::{Config, SafetyChecker};
+use std::fs;
+use tempfile::tempdir;
+
+const CREATE_INDEX_MIGRATION: &str = r#"use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        db.execute_unprepared("CREATE TABLE users (id BIGINT PRIMARY KEY, email TEXT)")
+            .await?;
+        db.execute_unprepared("CREATE INDEX idx_accounts_email ON accounts(email)")
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.get_connection().execute_unprepared("DROP TABLE users").await?;
+        Ok(())
+    }
+}
+"#;
+
+fn write_migration_crate(root: &std::path::Path) -> std::path::PathBuf {
+    let src = root.join("migration/src");
+    fs::create_dir_all(&src).unwrap();
+    fs::write(src.join("lib.rs"), "pub struct Migrator;\n").unwrap();
+    let module = src.join("m20220101_000001_create_users.rs");
+    fs::write(&module, CREATE_INDEX_MIGRATION).unwrap();
+    module
+}
+
+fn checker() -> SafetyChecker {
+    SafetyChecker::with_config(Config {
+        framework: "seaorm".to_string(),
+        enable_checks: vec!["AddIndexCheck".to_string(), "DropTableCheck".to_string()],
+        ..Default::default()
+    })
+    .unwrap()
+}
+
+#[test]
+fn test_violations_point_at_rust_source_lines() {
+    let temp_dir = tempdir().expect("Failed to create temp dir");
+    write_migration_crate(temp_dir.path());
+    let migration = Utf8Path::from_path(temp_dir.path())
+        .unwrap()
+        .join("migration");
+
+    let results = checker().check_directory(&migration).unwrap();
+
+    assert_eq!(results.len(), 1);
+    assert!(results[0].0.ends_with("m20220101_000001_create_users.rs"));
+    let violations = &results[0].1;
+    // DROP TABLE in `fn down` is not checked
+    assert_eq!(violations.len(), 1);
+    let (line, violation) = &violations[0];
+    assert_eq!(*line, 12);
+    assert_eq!(violation.check_name, "AddIndexCheck");
+    assert_eq!(violation.column, Some(32));
+    assert!(violation.fix.is_none());
+}
+
+#[test]
+fn test_fix_leaves_rust_migrations_alone() {
+    let temp_dir = tempdir().expect("Failed to create temp dir");
+    let module = write_migration_crate(temp_dir.path());
+    fs::write(
+        temp_dir.path().join("diesel-guard.toml"),
+        "framework = \"seaorm\"\nenable_checks = [\"AddIndexCheck\"]\n",
+    )
+    .unwrap();
+
+    Command::cargo_bin("diesel-guard")
+        .unwrap()
+        .args(["fix", "migration"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success()
+        .stdout("No fixable violations found.\n");
+    assert_eq!(fs::read_to_string(module).unwrap(), CREATE_INDEX_MIGRATION);
+}
+
+#[test]
+fn test_check_single_rust_migration() {
+    let temp_dir = tempdir().expect("Failed to create temp dir");
+    let module = write_migration_crate(temp_dir.path());
+
+    let violations = checker()
+        .check_file(Utf8Path::from_path(&module).unwrap())
+        .unwrap();
+    assert_eq!(violations.len(), 1);
+    assert_eq!(violations[0].0, 12);
+}